@@ -0,0 +1,243 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_transaction_builder::{
+    decode_instruction, read_keypair_from_file, write_encoded, Encoding, InstructionPlan,
+    TransactionBuilder, TransactionInstruction, FEE_PAYER_PLACEHOLDER,
+};
+use solana_transaction_builder_executor::{
+    builder_to_execution_data, execute_transaction_data_in_sequence_with_config,
+    simulate_transaction_data, ExecutionConfig, RecentPrioritizationFeesEstimator,
+};
+use solana_transaction_executor::TransactionExecutorBuilder;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Encoding, decoding, previewing and sending of instructions built with solana-transaction-builder.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decodes borsh serialized instructions and prints them as JSON
+    Decode {
+        #[arg(long, value_enum, default_value_t = EncodingArg::Base64)]
+        encoding: EncodingArg,
+        /// Encoded instructions
+        #[arg(required = true)]
+        instructions: Vec<String>,
+    },
+    /// Encodes the instructions of the plan file
+    Encode {
+        #[arg(long, value_enum, default_value_t = EncodingArg::Base64)]
+        encoding: EncodingArg,
+        plan: PathBuf,
+    },
+    /// Prints the transactions the plan file is built into
+    Preview {
+        #[command(flatten)]
+        transaction: TransactionArgs,
+    },
+    /// Simulates the transactions of the plan file
+    Simulate {
+        #[command(flatten)]
+        rpc: RpcArgs,
+        #[command(flatten)]
+        transaction: TransactionArgs,
+    },
+    /// Executes the transactions of the plan file in sequence
+    Execute {
+        #[command(flatten)]
+        rpc: RpcArgs,
+        #[command(flatten)]
+        transaction: TransactionArgs,
+        #[command(flatten)]
+        priority_fee: PriorityFeeArgs,
+        /// Stops on the first failed transaction
+        #[arg(long)]
+        fail_on_first_error: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EncodingArg {
+    Base64,
+    Base58,
+    Hex,
+}
+
+impl From<EncodingArg> for Encoding {
+    fn from(encoding: EncodingArg) -> Self {
+        match encoding {
+            EncodingArg::Base64 => Encoding::Base64,
+            EncodingArg::Base58 => Encoding::Base58,
+            EncodingArg::Hex => Encoding::Hex,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct RpcArgs {
+    #[arg(short, long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+}
+
+#[derive(clap::Args)]
+struct TransactionArgs {
+    /// JSON or YAML instruction plan file
+    plan: PathBuf,
+    /// Keypair file of the fee payer
+    #[arg(short, long)]
+    keypair: PathBuf,
+    /// Keypair files of the other signers of the instructions
+    #[arg(long)]
+    signer: Vec<PathBuf>,
+    /// Instruction packs are merged into transactions as many as fit
+    #[arg(long)]
+    combined: bool,
+}
+
+#[derive(clap::Args)]
+struct PriorityFeeArgs {
+    /// Percentile of the recent prioritization fees of the written accounts paid as the compute unit price
+    #[arg(long)]
+    fee_percentile: Option<u8>,
+    /// Minimal compute unit price in micro-lamports
+    #[arg(long, default_value_t = 0)]
+    min_compute_unit_price: u64,
+    /// Maximal compute unit price in micro-lamports
+    #[arg(long, default_value_t = u64::MAX)]
+    max_compute_unit_price: u64,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Decode {
+            encoding,
+            instructions,
+        } => {
+            let instructions = instructions
+                .iter()
+                .map(|encoded| {
+                    decode_instruction(encoded, encoding.into())
+                        .map(|instruction| TransactionInstruction::from(&instruction))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            println!("{}", serde_json::to_string_pretty(&instructions)?);
+        }
+        Command::Encode { encoding, plan } => {
+            let instructions: Vec<Instruction> = InstructionPlan::from_file(plan)?
+                .instruction_packs(&FEE_PAYER_PLACEHOLDER)?
+                .into_iter()
+                .flatten()
+                .map(|(instruction, _)| instruction)
+                .collect();
+            write_encoded(&instructions, encoding.into(), &mut std::io::stdout())?;
+        }
+        Command::Preview { transaction } => {
+            let builder = transaction.builder()?;
+            print!("{}", builder.plan(transaction.combined));
+        }
+        Command::Simulate { rpc, transaction } => {
+            let rpc_client = Arc::new(RpcClient::new(rpc.url));
+            let mut builder = transaction.builder()?;
+            let execution_data =
+                builder_to_execution_data(rpc_client, &mut builder, None, transaction.combined);
+            let report = simulate_transaction_data(&execution_data).await;
+            for simulation in &report.simulations {
+                println!("Transaction #{}:", simulation.human_index);
+                match &simulation.result {
+                    Ok(result) => {
+                        println!("  error: {:?}", result.err);
+                        println!("  compute units: {:?}", result.units_consumed);
+                        for log in result.logs.iter().flatten() {
+                            println!("  {log}");
+                        }
+                    }
+                    Err(err) => println!("  simulation failed: {err:#}"),
+                }
+            }
+            if !report.is_success() {
+                anyhow::bail!("Simulation of some of the transactions failed");
+            }
+        }
+        Command::Execute {
+            rpc,
+            transaction,
+            priority_fee,
+            fail_on_first_error,
+        } => {
+            let rpc_client = Arc::new(RpcClient::new(rpc.url));
+            let mut builder = transaction.builder()?;
+            let execution_data = builder_to_execution_data(
+                rpc_client.clone(),
+                &mut builder,
+                None,
+                transaction.combined,
+            );
+            let config = ExecutionConfig {
+                fail_on_first_error,
+                priority_fee_estimator: priority_fee.fee_percentile.map(|percentile| {
+                    Arc::new(
+                        RecentPrioritizationFeesEstimator::new(rpc_client.clone())
+                            .with_percentile(percentile)
+                            .with_bounds(
+                                priority_fee.min_compute_unit_price,
+                                priority_fee.max_compute_unit_price,
+                            ),
+                    ) as _
+                }),
+                ..Default::default()
+            };
+            let transaction_executor = Arc::new(
+                TransactionExecutorBuilder::new()
+                    .with_default_providers(rpc_client)
+                    .build(),
+            );
+            let report = execute_transaction_data_in_sequence_with_config(
+                transaction_executor,
+                &execution_data,
+                &config,
+            )
+            .await;
+            for executed in &report.executed {
+                println!(
+                    "Transaction #{} executed: {}",
+                    executed.human_index, executed.signature
+                );
+            }
+            for skipped in &report.skipped {
+                println!(
+                    "Transaction #{} skipped: {:?}",
+                    skipped.human_index, skipped.reason
+                );
+            }
+            report.into_result()?;
+        }
+    }
+    Ok(())
+}
+
+impl TransactionArgs {
+    fn builder(&self) -> anyhow::Result<TransactionBuilder> {
+        let mut builder = TransactionBuilder::limited(load_keypair(&self.keypair)?);
+        for signer in &self.signer {
+            builder.add_signer(load_keypair(signer)?);
+        }
+        InstructionPlan::from_file(&self.plan)?.add_to_builder(&mut builder)?;
+        Ok(builder)
+    }
+}
+
+fn load_keypair(path: &Path) -> anyhow::Result<Arc<Keypair>> {
+    let keypair = read_keypair_from_file(path)?;
+    eprintln!("Loaded keypair {}", keypair.pubkey());
+    Ok(Arc::new(keypair))
+}
@@ -0,0 +1,203 @@
+use crate::{ExecutionObserver, TransactionBuilderExecutionData};
+use serde_json::{json, Value};
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_executor::PriorityFeeConfiguration;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Append-only record of the execution, one JSON line per transaction event
+/// (every signed transaction sent and every result of an attempt).
+/// Registered as an observer in `ExecutionConfig::observers`.
+pub struct AuditLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl AuditLog {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Appends to the file, the file is created when it does not exist.
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+
+    fn write_record(
+        &self,
+        event: &str,
+        execution_data: &TransactionBuilderExecutionData,
+        mut record: Value,
+    ) {
+        record["event"] = json!(event);
+        record["tx_uuid"] = json!(execution_data.tx_uuid);
+        record["labels"] = json!(execution_data.labels);
+        record["timestamp_ms"] = json!(SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_millis()));
+        let mut writer = self.writer.lock().expect("Audit log lock poisoned");
+        if let Err(err) = writeln!(writer, "{record}").and_then(|_| writer.flush()) {
+            warn!(
+                "Failed to write the audit log record of the transaction {}: {err}",
+                execution_data.tx_uuid
+            );
+        }
+    }
+}
+
+impl ExecutionObserver for AuditLog {
+    fn on_sent(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        transaction: &VersionedTransaction,
+        priority_fee_configuration: &PriorityFeeConfiguration,
+    ) {
+        self.write_record(
+            "sent",
+            execution_data,
+            json!({
+                "signature": transaction.signatures.first().map(Signature::to_string),
+                "blockhash": transaction.message.recent_blockhash().to_string(),
+                "priority_fee_configuration": format!("{priority_fee_configuration:?}"),
+            }),
+        );
+    }
+
+    fn on_confirmed(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        signature: &Signature,
+    ) {
+        self.write_record(
+            "confirmed",
+            execution_data,
+            json!({ "signature": signature.to_string() }),
+        );
+    }
+
+    fn on_retry(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        attempt: usize,
+        error: &anyhow::Error,
+        delay: Duration,
+    ) {
+        self.write_record(
+            "retry",
+            execution_data,
+            json!({
+                "attempt": attempt,
+                "error": format!("{error:#}"),
+                "delay_ms": delay.as_millis(),
+            }),
+        );
+    }
+
+    fn on_failed(&self, execution_data: &TransactionBuilderExecutionData, error: &anyhow::Error) {
+        self.write_record(
+            "failed",
+            execution_data,
+            json!({ "error": format!("{error:#}") }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_transaction_builder::TransactionBuilder;
+    use solana_transaction_executor::PriorityFeePolicy;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn records(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn execution_data() -> TransactionBuilderExecutionData {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        TransactionBuilderExecutionData::new(
+            builder.build_one(),
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            PriorityFeePolicy::default(),
+        )
+        .with_label("operation", "rebalance")
+    }
+
+    #[test]
+    fn test_record_shape() {
+        let buffer = SharedBuffer::default();
+        let audit_log = AuditLog::new(buffer.clone());
+        let execution_data = execution_data();
+        let signature = Signature::new_unique();
+
+        audit_log.on_retry(
+            &execution_data,
+            1,
+            &anyhow!("Blockhash not found"),
+            Duration::from_millis(500),
+        );
+        audit_log.on_confirmed(&execution_data, &signature);
+        audit_log.on_failed(
+            &execution_data,
+            &anyhow!("custom program error").context("Transaction failed"),
+        );
+
+        let records = buffer.records();
+        assert_eq!(records.len(), 3);
+        for record in &records {
+            assert_eq!(record["tx_uuid"], json!(execution_data.tx_uuid));
+            assert_eq!(record["labels"]["operation"], json!("rebalance"));
+            assert!(record["timestamp_ms"].as_u64().unwrap() > 0);
+        }
+        assert_eq!(records[0]["event"], json!("retry"));
+        assert_eq!(records[0]["attempt"], json!(1));
+        assert_eq!(records[0]["error"], json!("Blockhash not found"));
+        assert_eq!(records[0]["delay_ms"], json!(500));
+        assert_eq!(records[1]["event"], json!("confirmed"));
+        assert_eq!(records[1]["signature"], json!(signature.to_string()));
+        assert_eq!(records[2]["event"], json!("failed"));
+        // the whole error chain is recorded
+        assert_eq!(
+            records[2]["error"],
+            json!("Transaction failed: custom program error")
+        );
+    }
+}
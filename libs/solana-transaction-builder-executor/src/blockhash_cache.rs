@@ -0,0 +1,247 @@
+use crate::metrics;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+use tracing::debug;
+
+pub const DEFAULT_BLOCKHASH_CACHE_TTL: Duration = Duration::from_secs(10);
+
+// Caches of `BlockhashCache::shared` by the RPC url, alive while any execution data holds them
+static SHARED_CACHES: OnceLock<std::sync::Mutex<HashMap<String, Weak<BlockhashCache>>>> =
+    OnceLock::new();
+
+/// Latest blockhash fetched with the RPC client and reused for the configured time.
+pub struct BlockhashCache {
+    rpc_client: Arc<RpcClient>,
+    commitment: CommitmentConfig,
+    ttl: Duration,
+    // blockhash, its last valid block height and the fetch time
+    cached: Mutex<Option<(Hash, u64, Instant)>>,
+    // kept fresh by the background refresh task, see `with_background_refresh`
+    refreshed: Option<watch::Receiver<Option<(Hash, u64, Instant)>>>,
+    fetches: AtomicU64,
+}
+
+impl BlockhashCache {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            commitment: CommitmentConfig::finalized(),
+            ttl: DEFAULT_BLOCKHASH_CACHE_TTL,
+            cached: Mutex::new(None),
+            refreshed: None,
+            fetches: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache with the default ttl and commitment shared by all the callers using the same RPC url,
+    /// e.g. by the execution data created with `TransactionBuilderExecutionData::new`.
+    pub fn shared(rpc_client: Arc<RpcClient>) -> Arc<Self> {
+        let mut shared_caches = SHARED_CACHES
+            .get_or_init(Default::default)
+            .lock()
+            .expect("Shared blockhash caches lock poisoned");
+        shared_caches.retain(|_, cache| cache.strong_count() > 0);
+        let url = rpc_client.url();
+        if let Some(cache) = shared_caches.get(&url).and_then(Weak::upgrade) {
+            return cache;
+        }
+        let cache = Arc::new(Self::new(rpc_client));
+        shared_caches.insert(url, Arc::downgrade(&cache));
+        cache
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    /// Spawns a task fetching the latest blockhash every interval, the transactions
+    /// use the refreshed blockhash without waiting for a fetch when the cache expires.
+    /// Falls back to the on-demand fetch until the first refresh and when the refreshed
+    /// blockhash is older than the ttl (the refresh fails). The task ends when the cache is dropped.
+    /// Call within a tokio runtime, after `with_commitment`.
+    pub fn with_background_refresh(mut self, interval: Duration) -> Self {
+        let (sender, receiver) = watch::channel(None);
+        let rpc_client = self.rpc_client.clone();
+        let commitment = self.commitment;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if sender.is_closed() {
+                    break;
+                }
+                match rpc_client
+                    .get_latest_blockhash_with_commitment(commitment)
+                    .await
+                {
+                    Ok((blockhash, last_valid_block_height)) => {
+                        sender.send_replace(Some((
+                            blockhash,
+                            last_valid_block_height,
+                            Instant::now(),
+                        )));
+                    }
+                    Err(err) => {
+                        let err = anyhow::Error::from(err);
+                        metrics::rpc_error(&err);
+                        debug!("Background blockhash refresh failed: {err:?}");
+                    }
+                }
+            }
+        });
+        self.refreshed = Some(receiver);
+        self
+    }
+
+    pub fn rpc_client(&self) -> &Arc<RpcClient> {
+        &self.rpc_client
+    }
+
+    /// Number of the blockhash requests made on demand (the background refresh is not counted).
+    pub fn fetch_count(&self) -> u64 {
+        self.fetches.load(Ordering::Relaxed)
+    }
+
+    pub async fn get_latest_blockhash(&self) -> anyhow::Result<Hash> {
+        let (blockhash, _) = self
+            .get_latest_blockhash_with_last_valid_block_height()
+            .await?;
+        Ok(blockhash)
+    }
+
+    /// Latest blockhash with the last block height the transactions using it can be processed at.
+    pub async fn get_latest_blockhash_with_last_valid_block_height(
+        &self,
+    ) -> anyhow::Result<(Hash, u64)> {
+        if let Some(refreshed) = &self.refreshed {
+            if let Some((blockhash, last_valid_block_height, fetched_at)) = *refreshed.borrow() {
+                if fetched_at.elapsed() < self.ttl {
+                    metrics::blockhash_cache_lookup(true);
+                    return Ok((blockhash, last_valid_block_height));
+                }
+            }
+        }
+        // the lock is held while fetching, concurrent callers wait for the single request
+        let mut cached = self.cached.lock().await;
+        if let Some((blockhash, last_valid_block_height, fetched_at)) = *cached {
+            if fetched_at.elapsed() < self.ttl {
+                metrics::blockhash_cache_lookup(true);
+                return Ok((blockhash, last_valid_block_height));
+            }
+        }
+        metrics::blockhash_cache_lookup(false);
+        self.fetches.fetch_add(1, Ordering::Relaxed);
+        let (blockhash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(self.commitment)
+            .await
+            .map_err(|err| {
+                let err = anyhow::Error::from(err);
+                metrics::rpc_error(&err);
+                err
+            })?;
+        debug!("Fetched a new blockhash: {blockhash}, last valid block height: {last_valid_block_height}");
+        *cached = Some((blockhash, last_valid_block_height, Instant::now()));
+        Ok((blockhash, last_valid_block_height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+
+    fn mock_rpc_client() -> Arc<RpcClient> {
+        Arc::new(RpcClient::new_mock("succeeds".to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let cache = BlockhashCache::new(mock_rpc_client());
+        cache.get_latest_blockhash().await.unwrap();
+        cache.get_latest_blockhash().await.unwrap();
+        assert_eq!(cache.fetch_count(), 1);
+
+        let expiring_cache = BlockhashCache::new(mock_rpc_client()).with_ttl(Duration::ZERO);
+        expiring_cache.get_latest_blockhash().await.unwrap();
+        expiring_cache.get_latest_blockhash().await.unwrap();
+        assert_eq!(expiring_cache.fetch_count(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_single_flight() {
+        let cache = Arc::new(BlockhashCache::new(mock_rpc_client()));
+        let lookups: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.get_latest_blockhash().await.unwrap() })
+            })
+            .collect();
+        for lookup in lookups {
+            lookup.await.unwrap();
+        }
+        assert_eq!(cache.fetch_count(), 1);
+    }
+
+    #[test]
+    fn test_shared() {
+        let url = "http://127.0.0.1:18899".to_string();
+        let cache = BlockhashCache::shared(Arc::new(RpcClient::new(url.clone())));
+        assert!(Arc::ptr_eq(
+            &cache,
+            &BlockhashCache::shared(Arc::new(RpcClient::new(url)))
+        ));
+        let other_url = Arc::new(RpcClient::new("http://127.0.0.1:18900".to_string()));
+        assert!(!Arc::ptr_eq(&cache, &BlockhashCache::shared(other_url)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_background_refresh() {
+        let blockhash = Hash::new_unique();
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetLatestBlockhash,
+            json!({
+                "context": { "slot": 1 },
+                "value": { "blockhash": blockhash.to_string(), "lastValidBlockHeight": 100 },
+            }),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        let cache =
+            BlockhashCache::new(rpc_client.clone()).with_background_refresh(Duration::from_secs(1));
+        let mut refreshed = cache.refreshed.clone().unwrap();
+        refreshed.changed().await.unwrap();
+
+        assert_eq!(
+            cache
+                .get_latest_blockhash_with_last_valid_block_height()
+                .await
+                .unwrap(),
+            (blockhash, 100)
+        );
+        assert_eq!(cache.fetch_count(), 0);
+
+        // the task holding the RPC client ends with the next tick after the cache is dropped
+        drop(refreshed);
+        drop(cache);
+        assert_eq!(Arc::strong_count(&rpc_client), 2);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(Arc::strong_count(&rpc_client), 1);
+    }
+}
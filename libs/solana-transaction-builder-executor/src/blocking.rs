@@ -0,0 +1,175 @@
+use crate::execution_report::PARALLEL_EXECUTION_LIMIT;
+use crate::{ExecutionReport, SkipReason};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+use solana_transaction_builder::{
+    get_prepared_transaction_iterator, PreparedTransaction, SignedTransaction, TransactionBuilder,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Synchronous sequential execution over the blocking RPC client, every transaction is signed
+/// with the latest blockhash, sent and confirmed before the next one is started.
+/// No priority fees, retries or observers are applied, transactions requiring
+/// remote signatures fail as they cannot be signed synchronously.
+pub fn execute_prepared_transactions_in_sequence_blocking(
+    rpc_client: &RpcClient,
+    prepared_transactions: &[PreparedTransaction],
+    fail_on_first_error: bool,
+) -> ExecutionReport {
+    let mut report = ExecutionReport::default();
+    for (index, prepared_transaction) in prepared_transactions.iter().enumerate() {
+        let human_index = index + 1;
+        if fail_on_first_error && !report.errors.is_empty() {
+            report.add_prepared_skipped(
+                prepared_transaction,
+                human_index,
+                SkipReason::PreviousFailure,
+            );
+            continue;
+        }
+        let result = execute_blocking(rpc_client, prepared_transaction);
+        report.add_prepared_result(
+            prepared_transaction,
+            human_index,
+            result,
+            "Blocking execution failed",
+        );
+    }
+    report
+}
+
+/// Synchronous parallel execution on up to the parallel execution limit of threads,
+/// a thread takes the next transaction as soon as its previous one is done,
+/// i.e., a slow confirmation does not hold the other threads back.
+/// The results are reported in the order of the transactions.
+pub fn execute_prepared_transactions_in_parallel_blocking(
+    rpc_client: &RpcClient,
+    prepared_transactions: &[PreparedTransaction],
+    parallel_execution_limit: Option<usize>,
+) -> ExecutionReport {
+    let parallel_execution_limit = parallel_execution_limit
+        .unwrap_or(PARALLEL_EXECUTION_LIMIT)
+        .clamp(1, prepared_transactions.len().max(1));
+    let next_index = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<anyhow::Result<Signature>>>> =
+        Mutex::new(prepared_transactions.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..parallel_execution_limit)
+            .map(|_| {
+                scope.spawn(|| loop {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    let Some(prepared_transaction) = prepared_transactions.get(index) else {
+                        break;
+                    };
+                    let result = execute_blocking(rpc_client, prepared_transaction);
+                    results
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())[index] = Some(result);
+                })
+            })
+            .collect();
+        // a panicked thread leaves its transaction without a result, reported below
+        for handle in handles {
+            let _ = handle.join();
+        }
+    });
+    let results = results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut report = ExecutionReport::default();
+    for (index, (prepared_transaction, result)) in
+        prepared_transactions.iter().zip(results).enumerate()
+    {
+        report.add_prepared_result(
+            prepared_transaction,
+            index + 1,
+            result.unwrap_or_else(|| Err(anyhow::anyhow!("Execution thread panicked"))),
+            "Blocking execution failed",
+        );
+    }
+    report
+}
+
+/// Builds all transactions of the builder and executes them in sequence,
+/// `is_one_by_one` as in `get_prepared_transaction_iterator`.
+pub fn execute_builder_blocking(
+    rpc_client: &RpcClient,
+    transaction_builder: &mut TransactionBuilder,
+    is_one_by_one: bool,
+    fail_on_first_error: bool,
+) -> ExecutionReport {
+    let prepared_transactions: Vec<PreparedTransaction> =
+        get_prepared_transaction_iterator(transaction_builder, is_one_by_one).collect();
+    execute_prepared_transactions_in_sequence_blocking(
+        rpc_client,
+        &prepared_transactions,
+        fail_on_first_error,
+    )
+}
+
+fn execute_blocking(
+    rpc_client: &RpcClient,
+    prepared_transaction: &PreparedTransaction,
+) -> anyhow::Result<Signature> {
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = prepared_transaction.signed_versioned_transaction(blockhash)?;
+    Ok(rpc_client.send_and_confirm_transaction(&transaction)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use std::sync::Arc;
+
+    fn prepared_transactions(count: usize) -> Vec<PreparedTransaction> {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        for _ in 0..count {
+            builder
+                .add_instruction(system_instruction::transfer(
+                    &fee_payer.pubkey(),
+                    &Keypair::new().pubkey(),
+                    1,
+                ))
+                .unwrap();
+            builder.finish_instruction_pack();
+        }
+        builder.sequence().collect()
+    }
+
+    #[test]
+    fn test_execute_in_sequence_blocking() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let prepared_transactions = prepared_transactions(3);
+
+        let report = execute_prepared_transactions_in_sequence_blocking(
+            &rpc_client,
+            &prepared_transactions,
+            true,
+        );
+        assert!(report.is_success());
+        assert_eq!(report.executed.len(), 3);
+    }
+
+    #[test]
+    fn test_execute_in_parallel_blocking() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let prepared_transactions = prepared_transactions(5);
+
+        let report = execute_prepared_transactions_in_parallel_blocking(
+            &rpc_client,
+            &prepared_transactions,
+            Some(2),
+        );
+        assert!(report.is_success());
+        let human_indexes: Vec<usize> = report
+            .executed
+            .iter()
+            .map(|executed| executed.human_index)
+            .collect();
+        assert_eq!(human_indexes, vec![1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::EndpointHealth;
+use futures::future::{join_all, BoxFuture};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ENDPOINT_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Additional channel the signed transactions are pushed through, registered
+/// in `ExecutionConfig::broadcasters`. The transaction executor keeps sending the transaction
+/// over its RPC (as the fallback) and it is the one confirming the transaction.
+pub trait TransactionBroadcaster: Send + Sync {
+    fn broadcast<'a>(
+        &'a self,
+        transaction: &'a VersionedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Sends every transaction to more RPC endpoints concurrently (a.k.a. spam mode).
+/// All the endpoints get the same signed transaction, i.e., the signature is the same
+/// and the transaction lands at most once, the first confirmation is the success.
+/// The health of every endpoint is tracked from the sends, see `RpcBroadcaster::health`.
+pub struct RpcBroadcaster {
+    rpc_clients: Vec<Arc<RpcClient>>,
+    send_config: RpcSendTransactionConfig,
+    max_endpoints: Option<usize>,
+    probe_interval: Duration,
+    // in the order of the clients
+    health: Mutex<Vec<EndpointHealth>>,
+    // when the transactions were last sent to the clients, in the order of the clients
+    last_sent_at: Mutex<Vec<Option<Instant>>>,
+}
+
+impl RpcBroadcaster {
+    /// The preflight is skipped by default as it is run by the transaction executor sending.
+    pub fn new(rpc_clients: Vec<Arc<RpcClient>>) -> Self {
+        let health = rpc_clients
+            .iter()
+            .map(|rpc_client| EndpointHealth::new(rpc_client.url()))
+            .collect();
+        let last_sent_at = vec![None; rpc_clients.len()];
+        Self {
+            rpc_clients,
+            send_config: RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+            max_endpoints: None,
+            probe_interval: DEFAULT_ENDPOINT_PROBE_INTERVAL,
+            health: Mutex::new(health),
+            last_sent_at: Mutex::new(last_sent_at),
+        }
+    }
+
+    pub fn from_urls<I: IntoIterator<Item = String>>(rpc_urls: I) -> Self {
+        Self::new(
+            rpc_urls
+                .into_iter()
+                .map(|rpc_url| Arc::new(RpcClient::new(rpc_url)))
+                .collect(),
+        )
+    }
+
+    pub fn with_send_config(mut self, send_config: RpcSendTransactionConfig) -> Self {
+        self.send_config = send_config;
+        self
+    }
+
+    /// Every transaction is sent to the healthiest endpoints only (by `EndpointHealth::score`),
+    /// the demoted ones are probed with a transaction once per probe interval
+    /// so that they are preferred again when they recover
+    pub fn with_max_endpoints(mut self, max_endpoints: usize) -> Self {
+        self.max_endpoints = Some(max_endpoints.max(1));
+        self
+    }
+
+    pub fn with_probe_interval(mut self, probe_interval: Duration) -> Self {
+        self.probe_interval = probe_interval;
+        self
+    }
+
+    /// Health snapshot of the endpoints, e.g. for monitoring
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.health.lock().expect("Health lock poisoned").clone()
+    }
+
+    // Indexes of the clients the next transaction is sent to, the healthiest first
+    fn preferred_endpoints(&self) -> Vec<usize> {
+        let health = self.health.lock().expect("Health lock poisoned");
+        let mut last_sent_at = self.last_sent_at.lock().expect("Health lock poisoned");
+        let now = Instant::now();
+        let endpoints = select_endpoints(
+            &health,
+            &last_sent_at,
+            self.max_endpoints,
+            self.probe_interval,
+            now,
+        );
+        for index in &endpoints {
+            last_sent_at[*index] = Some(now);
+        }
+        endpoints
+    }
+}
+
+// The healthiest endpoints up to the max endpoints, followed by the demoted endpoint
+// not sent to for the longest time once it was not sent to for the probe interval
+fn select_endpoints(
+    health: &[EndpointHealth],
+    last_sent_at: &[Option<Instant>],
+    max_endpoints: Option<usize>,
+    probe_interval: Duration,
+    now: Instant,
+) -> Vec<usize> {
+    let mut indexes: Vec<usize> = (0..health.len()).collect();
+    indexes.sort_by(|a, b| health[*b].score().total_cmp(&health[*a].score()));
+    let demoted = indexes.split_off(max_endpoints.unwrap_or(indexes.len()).min(indexes.len()));
+    let probed = demoted
+        .into_iter()
+        .filter(|index| {
+            last_sent_at[*index].map_or(true, |sent_at| {
+                now.duration_since(sent_at) >= probe_interval
+            })
+        })
+        .min_by_key(|index| last_sent_at[*index]);
+    indexes.extend(probed);
+    indexes
+}
+
+impl TransactionBroadcaster for RpcBroadcaster {
+    /// Succeeds when at least one of the endpoints accepted the transaction.
+    fn broadcast<'a>(
+        &'a self,
+        transaction: &'a VersionedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let endpoints = self.preferred_endpoints();
+            let results = join_all(endpoints.iter().map(|index| async move {
+                let started_at = Instant::now();
+                let result = self.rpc_clients[*index]
+                    .send_transaction_with_config(transaction, self.send_config)
+                    .await;
+                (*index, started_at.elapsed(), result)
+            }))
+            .await;
+            let mut health = self.health.lock().expect("Health lock poisoned");
+            let mut succeeded = false;
+            let mut first_error = None;
+            for (index, latency, result) in results {
+                match result {
+                    Ok(_) => {
+                        health[index].record_success(latency);
+                        succeeded = true;
+                    }
+                    Err(err) => {
+                        let err = anyhow::Error::from(err);
+                        health[index].record_failure(latency, &err);
+                        first_error.get_or_insert(err);
+                    }
+                }
+            }
+            match first_error {
+                Some(err) if !succeeded => Err(err),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_select_endpoints() {
+        let mut health: Vec<EndpointHealth> = (0..3)
+            .map(|index| EndpointHealth::new(format!("http://rpc-{index}")))
+            .collect();
+        health[0].record_failure(Duration::from_millis(100), &anyhow!("node is unhealthy"));
+        health[1].record_success(Duration::from_millis(500));
+        health[2].record_success(Duration::from_millis(100));
+        let now = Instant::now();
+        let probe_interval = Duration::from_secs(30);
+
+        assert_eq!(
+            select_endpoints(&health, &[None; 3], None, probe_interval, now),
+            vec![2, 1, 0]
+        );
+        let last_sent_at = [Some(now); 3];
+        assert_eq!(
+            select_endpoints(&health, &last_sent_at, Some(2), probe_interval, now),
+            vec![2, 1]
+        );
+
+        // the demoted endpoint is probed once the probe interval passed
+        let later = now + probe_interval;
+        assert_eq!(
+            select_endpoints(&health, &last_sent_at, Some(2), probe_interval, later),
+            vec![2, 1, 0]
+        );
+        assert_eq!(
+            select_endpoints(&health, &last_sent_at, Some(1), probe_interval, later),
+            vec![2, 1]
+        );
+    }
+}
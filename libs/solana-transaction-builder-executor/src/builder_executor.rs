@@ -1,69 +1,301 @@
-use crate::TransactionBuilderExecutionErrors;
+use crate::concurrency_limiter::ConcurrencyLimiter;
+use crate::execution_report::{execution_error, PARALLEL_EXECUTION_LIMIT};
+use crate::execution_run::ExecutionRun;
+use crate::nonce_pool::fetch_nonce;
+use crate::transaction_adjustments::TransactionAdjustments;
+use crate::{
+    content_tx_uuid, BlockhashCache, DurableNonce, ExecutionConfig, ExecutionErrorKind,
+    ExecutionReport, ExecutionSchedule, LookupTableCache, SkipReason,
+    TransactionBuilderExecutionError, TransactionBuilderExecutionErrors,
+};
 use async_stream::stream;
-use cached::proc_macro::cached;
-use log::debug;
+use futures::{Stream, StreamExt};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_client::SerializableTransaction;
-use solana_sdk::{
-    commitment_config::CommitmentConfig, hash::Hash, transaction::VersionedTransaction,
-};
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::clock::Slot;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::{commitment_config::CommitmentConfig, transaction::VersionedTransaction};
 use solana_transaction_builder::{
     get_prepared_transaction_iterator, PreparedTransaction, SignedTransaction, TransactionBuilder,
 };
 use solana_transaction_executor::{
     PriorityFeeConfiguration, PriorityFeePolicy, TransactionExecutor,
 };
+use std::collections::{BTreeMap, HashSet};
+use std::ops::RangeInclusive;
+use std::pin::pin;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
+use tracing::{debug, field, instrument, Span};
 use uuid::Uuid;
 
-const PARALLEL_EXECUTION_LIMIT: usize = 30;
-
 #[derive(Clone)]
 pub struct TransactionBuilderExecutionData {
-    pub rpc_url: String,
+    pub rpc_client: Arc<RpcClient>,
+    pub blockhash_cache: Arc<BlockhashCache>,
     pub priority_fee_policy: PriorityFeePolicy,
+    /// Policy of this transaction overriding `priority_fee_policy` and `ExecutionConfig::priority_fee_policy`
+    /// (e.g. higher fees of a critical transaction)
+    pub priority_fee_policy_override: Option<PriorityFeePolicy>,
     pub prepared_transaction: PreparedTransaction,
     pub tx_uuid: String,
+    /// User-defined key-value labels (e.g. `operation=rebalance`) propagated into the report,
+    /// the errors and the metrics (see `ExecutionConfig::metric_label_keys`), initialized
+    /// with the tags of the prepared transaction
+    pub labels: BTreeMap<String, String>,
+    /// Already signed transaction that is broadcast as is, the build and sign step is skipped
+    /// (modifications of the prepared transaction, e.g. `with_memo`, have no effect on it)
+    pub signed_transaction: Option<VersionedTransaction>,
+    /// RPC send config of this transaction, see `ExecutionConfig::send_transaction_config`
+    pub send_transaction_config: Option<RpcSendTransactionConfig>,
+    /// Lookup tables the transaction is compiled with as a v0 one, resolved through the cache
+    pub lookup_tables: Option<(Arc<LookupTableCache>, Vec<Pubkey>)>,
+    /// The transaction is submitted only within these slots, the execution waits
+    /// for the window to open and fails the transaction with `SlotWindowPassed` once it passed
+    pub slot_window: Option<RangeInclusive<Slot>>,
+    /// The transaction is signed with the nonce of the account instead of the latest blockhash,
+    /// the nonce advance is prepended as its first instruction and it does not expire
+    pub durable_nonce: Option<DurableNonce>,
 }
 
 impl TransactionBuilderExecutionData {
     pub fn new(
+        prepared_transaction: PreparedTransaction,
+        rpc_client: Arc<RpcClient>,
+        priority_fee_policy: PriorityFeePolicy,
+    ) -> Self {
+        let blockhash_cache = BlockhashCache::shared(rpc_client.clone());
+        Self::new_with_blockhash_cache(
+            prepared_transaction,
+            rpc_client,
+            blockhash_cache,
+            priority_fee_policy,
+        )
+    }
+
+    /// Convenience constructor creating a new RPC client for the url.
+    pub fn new_with_rpc_url(
         prepared_transaction: PreparedTransaction,
         rpc_url: String,
         priority_fee_policy: PriorityFeePolicy,
     ) -> Self {
-        Self {
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
             rpc_url,
+            CommitmentConfig::finalized(),
+        ));
+        Self::new(prepared_transaction, rpc_client, priority_fee_policy)
+    }
+
+    pub fn new_with_blockhash_cache(
+        prepared_transaction: PreparedTransaction,
+        rpc_client: Arc<RpcClient>,
+        blockhash_cache: Arc<BlockhashCache>,
+        priority_fee_policy: PriorityFeePolicy,
+    ) -> Self {
+        Self {
+            rpc_client,
+            blockhash_cache,
             priority_fee_policy,
+            priority_fee_policy_override: None,
+            labels: prepared_transaction.tags(),
             prepared_transaction,
             tx_uuid: Uuid::new_v4().to_string(),
+            signed_transaction: None,
+            send_transaction_config: None,
+            lookup_tables: None,
+            slot_window: None,
+            durable_nonce: None,
         }
     }
 
-    async fn build(
+    /// Execution data of a transaction signed elsewhere (e.g. offline),
+    /// the prepared transaction carries the message of a legacy transaction only.
+    pub fn new_signed(transaction: VersionedTransaction, rpc_client: Arc<RpcClient>) -> Self {
+        let prepared_transaction = PreparedTransaction {
+            transaction: transaction
+                .clone()
+                .into_legacy_transaction()
+                .unwrap_or_default(),
+            signers: vec![],
+            remote_signers: vec![],
+            external_signers: vec![],
+            instruction_descriptions: vec![],
+            instruction_metadata: vec![],
+            instruction_tags: vec![],
+            pre_assertions: vec![],
+            post_assertions: vec![],
+            fee_payer_meta: None,
+        };
+        let mut execution_data = Self::new(
+            prepared_transaction,
+            rpc_client,
+            PriorityFeePolicy::default(),
+        );
+        execution_data.signed_transaction = Some(transaction);
+        execution_data
+    }
+
+    /// Uses the provided cache (e.g. shared by more execution data) to get the latest blockhash.
+    pub fn with_blockhash_cache(mut self, blockhash_cache: Arc<BlockhashCache>) -> Self {
+        self.blockhash_cache = blockhash_cache;
+        self
+    }
+
+    pub fn with_send_transaction_config(
+        mut self,
+        send_transaction_config: RpcSendTransactionConfig,
+    ) -> Self {
+        self.send_transaction_config = Some(send_transaction_config);
+        self
+    }
+
+    pub fn with_priority_fee_policy(mut self, priority_fee_policy: PriorityFeePolicy) -> Self {
+        self.set_priority_fee_policy(priority_fee_policy);
+        self
+    }
+
+    /// Overrides the priority fee policy of already produced execution data
+    /// (e.g. of `builder_to_execution_data`).
+    pub fn set_priority_fee_policy(&mut self, priority_fee_policy: PriorityFeePolicy) {
+        self.priority_fee_policy_override = Some(priority_fee_policy);
+    }
+
+    /// The override of the transaction, the run-level policy of the config or the one
+    /// the execution data was created with, in this order.
+    pub fn effective_priority_fee_policy<'a>(
+        &'a self,
+        config: &'a ExecutionConfig,
+    ) -> &'a PriorityFeePolicy {
+        self.priority_fee_policy_override
+            .as_ref()
+            .or(config.priority_fee_policy.as_ref())
+            .unwrap_or(&self.priority_fee_policy)
+    }
+
+    pub fn with_label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Replaces the random tx_uuid with the one derived from the transaction content,
+    /// re-running the same plan yields the same identifiers.
+    /// Call after the transaction content is final (e.g. after `with_memo`).
+    pub fn with_content_tx_uuid(mut self) -> Self {
+        self.tx_uuid = content_tx_uuid(&self.prepared_transaction);
+        self
+    }
+
+    /// Compiles the transaction as a v0 one with the lookup tables of the addresses,
+    /// fetched through the cache whenever the transaction is built.
+    pub fn with_lookup_tables(
+        mut self,
+        lookup_table_cache: Arc<LookupTableCache>,
+        lookup_table_addresses: Vec<Pubkey>,
+    ) -> Self {
+        self.lookup_tables = Some((lookup_table_cache, lookup_table_addresses));
+        self
+    }
+
+    /// Submits the transaction only between the slots (inclusive), e.g. at the start of an epoch.
+    pub fn with_slot_window(mut self, first_slot: Slot, last_slot: Slot) -> Self {
+        self.slot_window = Some(first_slot..=last_slot);
+        self
+    }
+
+    /// Signs the transaction with the durable nonce of the account, the authority
+    /// has to be a signer (usually the fee payer) of the transaction.
+    pub fn with_durable_nonce(mut self, nonce_account: Pubkey, authority: Pubkey) -> Self {
+        self.durable_nonce = Some(DurableNonce {
+            nonce_account,
+            authority,
+        });
+        self
+    }
+
+    /// Prepends a memo instruction to the prepared transaction,
+    /// fails when the memo does not fit into the transaction.
+    pub fn with_memo(mut self, memo: &str) -> anyhow::Result<Self> {
+        self.prepared_transaction = self.prepared_transaction.with_memo(memo)?;
+        Ok(self)
+    }
+
+    /// Prepends a memo instruction carrying the tx_uuid to make the transaction
+    /// on-chain traceable to the execution logs.
+    pub fn with_tx_uuid_memo(self) -> anyhow::Result<Self> {
+        let tx_uuid = self.tx_uuid.clone();
+        self.with_memo(&tx_uuid)
+    }
+
+    /// Signs the adjusted transaction with the latest blockhash, returns it with
+    /// the last valid block height of the blockhash (unknown for a pre-signed one
+    /// and for a durable nonce one).
+    pub(crate) async fn build(
         &self,
-        priority_fee_configuration: PriorityFeeConfiguration,
-    ) -> anyhow::Result<VersionedTransaction> {
-        let latest_blockhash = get_latest_blockhash(self.rpc_url.clone()).await?;
-        let transaction = self
-            .prepared_transaction
-            .signed_versioned_transaction(latest_blockhash)?;
+        priority_fee_configuration: &PriorityFeeConfiguration,
+        adjustments: &TransactionAdjustments,
+    ) -> anyhow::Result<(VersionedTransaction, Option<u64>)> {
+        let (transaction, last_valid_block_height) = match &self.signed_transaction {
+            Some(signed_transaction) => (signed_transaction.clone(), None),
+            None => {
+                let (blockhash, last_valid_block_height) = self.recent_blockhash().await?;
+                let transaction = adjustments
+                    .sign(&self.prepared_transaction, blockhash)
+                    .await?;
+                (transaction, last_valid_block_height)
+            }
+        };
+        let span = Span::current();
+        span.record("signature", field::display(transaction.get_signature()));
+        span.record(
+            "priority_fee_configuration",
+            field::debug(priority_fee_configuration),
+        );
         debug!(
-            "Built transaction {} with blockhash {latest_blockhash} and prio fee config {priority_fee_configuration:?}",
-            transaction.get_signature()
+            "Built transaction {} with blockhash {} and prio fee config {priority_fee_configuration:?}",
+            transaction.get_signature(),
+            transaction.message.recent_blockhash()
         );
-        Ok(transaction)
+        Ok((transaction, last_valid_block_height))
+    }
+
+    /// Signs the prepared transaction with the blockhash, the latest one (or the durable nonce)
+    /// when not provided. A pre-signed transaction is returned as is.
+    pub(crate) async fn sign(
+        &self,
+        recent_blockhash: Option<Hash>,
+    ) -> anyhow::Result<VersionedTransaction> {
+        if let Some(signed_transaction) = &self.signed_transaction {
+            return Ok(signed_transaction.clone());
+        }
+        let blockhash = match recent_blockhash {
+            Some(blockhash) => blockhash,
+            None => self.recent_blockhash().await?.0,
+        };
+        let adjustments = TransactionAdjustments {
+            nonce_advance: self
+                .durable_nonce
+                .map(|durable_nonce| durable_nonce.advance_instruction()),
+            ..Default::default()
+        };
+        adjustments
+            .sign(&self.prepared_transaction, blockhash)
+            .await
     }
-}
 
-#[cached(result = true, time = 10, sync_writes = true)]
-async fn get_latest_blockhash(url: String) -> anyhow::Result<Hash> {
-    let blockhash = RpcClient::new_with_commitment(url, CommitmentConfig::finalized())
-        .get_latest_blockhash()
-        .await?;
-    debug!("Fetched a new blockhash: {blockhash}");
-    Ok(blockhash)
+    // The durable nonce, or the latest blockhash with its last valid block height
+    async fn recent_blockhash(&self) -> anyhow::Result<(Hash, Option<u64>)> {
+        if let Some(durable_nonce) = &self.durable_nonce {
+            let nonce = fetch_nonce(&self.rpc_client, &durable_nonce.nonce_account).await?;
+            return Ok((nonce, None));
+        }
+        let (blockhash, last_valid_block_height) = self
+            .blockhash_cache
+            .get_latest_blockhash_with_last_valid_block_height()
+            .await?;
+        Ok((blockhash, Some(last_valid_block_height)))
+    }
 }
 
 pub async fn execute_transaction_data_in_sequence(
@@ -71,48 +303,158 @@ pub async fn execute_transaction_data_in_sequence(
     execution_data: &[TransactionBuilderExecutionData],
     fail_on_first_error: bool,
 ) -> Result<(), TransactionBuilderExecutionErrors> {
+    let config = ExecutionConfig {
+        fail_on_first_error,
+        ..Default::default()
+    };
+    execute_transaction_data_in_sequence_with_config(transaction_executor, execution_data, &config)
+        .await
+        .into_result()
+        .map(|_| ())
+}
+
+#[instrument(
+    name = "execution_run",
+    skip_all,
+    fields(mode = "sequence", transactions = execution_data.len())
+)]
+pub async fn execute_transaction_data_in_sequence_with_config(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: &[TransactionBuilderExecutionData],
+    config: &ExecutionConfig,
+) -> ExecutionReport {
     let sequence_length = execution_data.len();
-    let mut errors = TransactionBuilderExecutionErrors::new();
-
-    for (index, async_transaction_builder) in execution_data.into_iter().enumerate() {
-        let human_index = index + 1;
-        let tx_uuid = &async_transaction_builder.tx_uuid;
-        debug!("Building the transaction {human_index}/{tx_uuid} (size: {sequence_length})");
-
-        let async_transaction_builder = async_transaction_builder.clone();
-        match transaction_executor
-            .execute_transaction(stream! {
-                let async_transaction_builder = async_transaction_builder.clone();
-                for priority_fee_configuration in async_transaction_builder.priority_fee_policy.iter_priority_fee_configuration() {
-                    yield async_transaction_builder.build(priority_fee_configuration).await;
-                }
-            })
-            .await
-        {
-            Ok(sig) => {
-                debug!("Transaction {sig} {human_index}/{tx_uuid} executed in sequence successfully");
-            }
-            Err(err) => {
-                let error_description = format!("Transaction {human_index}/{tx_uuid} sequential execution failed: {:?}", err);
-                debug!("{}", error_description);
-                errors.add_error(
-                    err,
-                    error_description,
-                    human_index,
-                    tx_uuid.clone(),
-                );
-                if fail_on_first_error {
-                    return Err(errors);
-                }
-            }
-        };
+    let run = ExecutionRun::new(transaction_executor, config);
+    let mut report = ExecutionReport::default();
+
+    for (index, async_transaction_builder) in execution_data.iter().enumerate() {
+        execute_sequence_item(
+            &run,
+            config,
+            &mut report,
+            async_transaction_builder,
+            index + 1,
+            Some(sequence_length),
+        )
+        .await;
+    }
+
+    run.finish_report(&mut report);
+    report
+}
+
+/// Sequential execution of the transactions as they are produced by the stream
+/// (e.g. `builder_to_execution_stream`), the next one is pulled when the previous one finished.
+#[instrument(name = "execution_run", skip_all, fields(mode = "sequence_stream"))]
+pub async fn execute_transaction_stream_in_sequence_with_config<S>(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: S,
+    config: &ExecutionConfig,
+) -> ExecutionReport
+where
+    S: Stream<Item = TransactionBuilderExecutionData>,
+{
+    let run = ExecutionRun::new(transaction_executor, config);
+    let mut report = ExecutionReport::default();
+
+    let mut execution_data = pin!(execution_data.enumerate());
+    while let Some((index, async_transaction_builder)) = execution_data.next().await {
+        execute_sequence_item(
+            &run,
+            config,
+            &mut report,
+            &async_transaction_builder,
+            index + 1,
+            None,
+        )
+        .await;
     }
 
-    if !errors.is_empty() {
-        return Err(errors);
+    run.finish_report(&mut report);
+    report
+}
+
+// Executes a transaction of the sequence unless it is skipped, the outcome is added to the report
+async fn execute_sequence_item(
+    run: &ExecutionRun<'_>,
+    config: &ExecutionConfig,
+    report: &mut ExecutionReport,
+    async_transaction_builder: &TransactionBuilderExecutionData,
+    human_index: usize,
+    sequence_length: Option<usize>,
+) {
+    let tx_uuid = &async_transaction_builder.tx_uuid;
+    run.wait_while_paused().await;
+    if let Err(err) = run.wait_for_slot_window(async_transaction_builder).await {
+        let error_description = format!("Transaction {human_index}/{tx_uuid} not started: {err}");
+        debug!("{}", error_description);
+        report.add_failed(
+            async_transaction_builder,
+            human_index,
+            err,
+            error_description,
+        );
+        return;
+    }
+    if let Some(reason) = run.skip_reason() {
+        debug!("Skipping the transaction {human_index}/{tx_uuid}: {reason:?}");
+        report.add_skipped(async_transaction_builder, human_index, reason);
+        return;
+    }
+    if config.stops_on(&report.errors) {
+        report.add_skipped(
+            async_transaction_builder,
+            human_index,
+            SkipReason::PreviousFailure,
+        );
+        return;
+    }
+    if let Some(reason) = run.slot_skip_reason(async_transaction_builder).await {
+        debug!("Skipping the transaction {human_index}/{tx_uuid}: {reason:?}");
+        report.add_skipped(async_transaction_builder, human_index, reason);
+        return;
     }
+    if let Some(reason) = run.approval_skip_reason(async_transaction_builder).await {
+        debug!("Skipping the transaction {human_index}/{tx_uuid}: {reason:?}");
+        report.add_skipped(async_transaction_builder, human_index, reason);
+        return;
+    }
+    if let Some(exhausted) = run.spend_budget_exhausted() {
+        let error_description =
+            format!("Transaction {human_index}/{tx_uuid} not started: {exhausted}");
+        debug!("{}", error_description);
+        report.add_failed(
+            async_transaction_builder,
+            human_index,
+            exhausted.into(),
+            error_description,
+        );
+        return;
+    }
+    debug!(
+        "Building the transaction {human_index}/{tx_uuid}{}",
+        size_description(sequence_length)
+    );
 
-    Ok(())
+    match run.execute(async_transaction_builder).await {
+        Ok(sig) => {
+            debug!("Transaction {sig} {human_index}/{tx_uuid} executed in sequence successfully");
+            report.add_executed(async_transaction_builder, human_index, sig);
+        }
+        Err(err) => {
+            let error_description = format!(
+                "Transaction {human_index}/{tx_uuid} sequential execution failed: {:?}",
+                err
+            );
+            debug!("{}", error_description);
+            report.add_failed(
+                async_transaction_builder,
+                human_index,
+                err,
+                error_description,
+            );
+        }
+    };
 }
 
 pub async fn execute_transaction_data_in_parallel(
@@ -120,97 +462,375 @@ pub async fn execute_transaction_data_in_parallel(
     execution_data: &[TransactionBuilderExecutionData],
     parallel_execution_limit: Option<usize>,
 ) -> Result<(), TransactionBuilderExecutionErrors> {
+    let config = ExecutionConfig {
+        parallel_execution_limit,
+        ..Default::default()
+    };
+    execute_transaction_data_in_parallel_with_config(transaction_executor, execution_data, &config)
+        .await
+        .into_result()
+        .map(|_| ())
+}
+
+#[instrument(
+    name = "execution_run",
+    skip_all,
+    fields(mode = "parallel", transactions = execution_data.len())
+)]
+pub async fn execute_transaction_data_in_parallel_with_config(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: &[TransactionBuilderExecutionData],
+    config: &ExecutionConfig,
+) -> ExecutionReport {
     let sequence_length = execution_data.len();
-    let parallel_execution_limit = parallel_execution_limit.unwrap_or(PARALLEL_EXECUTION_LIMIT);
-    let semaphore = Arc::new(Semaphore::new(parallel_execution_limit));
+    let run = &ExecutionRun::new(transaction_executor, config);
+    let parallel_execution_limit = config
+        .parallel_execution_limit
+        .unwrap_or(PARALLEL_EXECUTION_LIMIT);
+    let concurrency_limiter =
+        &ConcurrencyLimiter::new(parallel_execution_limit, config.adaptive_parallelism);
+
+    let waves = if config.conflict_scheduling {
+        let schedule = ExecutionSchedule::conflict_free_waves(execution_data);
+        debug!("Conflict-free execution schedule: {:?}", schedule.waves);
+        schedule.waves
+    } else {
+        vec![(0..sequence_length).collect()]
+    };
+
+    let mut report = ExecutionReport::default();
+    for wave in waves {
+        // Prepare the list of futures, a future returns the skip reason when the transaction was not started
+        let futures = wave
+            .into_iter()
+            .map(|index| {
+                let async_transaction_builder = &execution_data[index];
+                let human_index = index + 1;
+                async move {
+                    let result = execute_parallel_item(
+                        run,
+                        concurrency_limiter,
+                        async_transaction_builder,
+                        human_index,
+                        Some(sequence_length),
+                    )
+                    .await;
+                    (async_transaction_builder, human_index, result)
+                }
+            })
+            .collect::<Vec<_>>();
 
-    // Prepare the list of futures with their associated tx_uuid and human_index
-    let futures = execution_data
-        .into_iter()
+        // Await completion of all futures using join_all, or in order within the lookahead window
+        let results = match config.ordered_lookahead {
+            Some(lookahead) => {
+                futures::stream::iter(futures)
+                    .buffered(lookahead.max(1))
+                    .collect::<Vec<_>>()
+                    .await
+            }
+            None => futures::future::join_all(futures).await,
+        };
+
+        for (async_transaction_builder, human_index, result) in results {
+            add_parallel_result(&mut report, async_transaction_builder, human_index, result);
+        }
+    }
+
+    run.finish_report(&mut report);
+    report
+}
+
+/// Parallel execution of the transactions as they are produced by the stream
+/// (e.g. `builder_to_execution_stream`), up to the parallel execution limit is in flight at once.
+/// The conflict scheduling is not applied as the transactions are not known up front.
+#[instrument(name = "execution_run", skip_all, fields(mode = "parallel_stream"))]
+pub async fn execute_transaction_stream_in_parallel_with_config<S>(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: S,
+    config: &ExecutionConfig,
+) -> ExecutionReport
+where
+    S: Stream<Item = TransactionBuilderExecutionData>,
+{
+    let run = &ExecutionRun::new(transaction_executor, config);
+    let parallel_execution_limit = config
+        .parallel_execution_limit
+        .unwrap_or(PARALLEL_EXECUTION_LIMIT);
+    let concurrency_limiter =
+        &ConcurrencyLimiter::new(parallel_execution_limit, config.adaptive_parallelism);
+
+    let mut report = ExecutionReport::default();
+    let mut results = pin!(execution_data
         .enumerate()
-        .map(|(index, async_transaction_builder)| {
+        .map(|(index, async_transaction_builder)| async move {
             let human_index = index + 1;
-            let tx_uuid = async_transaction_builder.tx_uuid.clone();
-            let semaphore = Arc::clone(&semaphore);
-            debug!("Building the transaction {human_index}/{tx_uuid} (size: {sequence_length})");
-            let transaction_executor = Arc::clone(&transaction_executor);
-            let transaction_future = async move {
-                let _permit = semaphore.acquire().await.expect("Failed to acquire semaphore");
-                let transaction_result = stream! {
-                    for priority_fee_configuration in async_transaction_builder.priority_fee_policy.iter_priority_fee_configuration() {
-                        yield async_transaction_builder.build(priority_fee_configuration).await;
-                    }
-                };
-                transaction_executor.execute_transaction(transaction_result).await
-            };
-            // Return a tuple of tx_uuid, human_index, and the future
-            (tx_uuid, human_index, transaction_future)
+            let result = execute_parallel_item(
+                run,
+                concurrency_limiter,
+                &async_transaction_builder,
+                human_index,
+                None,
+            )
+            .await;
+            (async_transaction_builder, human_index, result)
         })
-        .collect::<Vec<_>>();
+        .buffer_unordered(parallel_execution_limit));
+    while let Some((async_transaction_builder, human_index, result)) = results.next().await {
+        add_parallel_result(&mut report, &async_transaction_builder, human_index, result);
+    }
 
-    // Await completion of all futures using join_all
-    let results = futures::future::join_all(futures.into_iter().map(
-        |(tx_uuid, human_index, future)| async move {
-            let result = future.await;
-            (tx_uuid, human_index, result)
-        },
-    ))
-    .await;
-
-    let mut errors = TransactionBuilderExecutionErrors::new();
-    for (tx_uuid, human_index, result) in results {
-        match result {
-            Ok(sig) => {
-                debug!(
-                    "Transaction {sig} {human_index}/{tx_uuid} executed in parallel successfully"
-                );
-            }
-            Err(err) => {
-                let error_description =
-                    format!("Transaction {human_index}/{tx_uuid} failed: {:?}", err);
-                debug!("{}", error_description);
-                errors.add_error(err, error_description, human_index, tx_uuid);
-            }
+    run.finish_report(&mut report);
+    report
+}
+
+/// Parallel execution yielding the result of every transaction as soon as it is known,
+/// a skipped transaction results in an error caused by its `SkipReason`.
+/// The conflict scheduling is not applied, the results come in the order of completion
+/// unless `ExecutionConfig::ordered_lookahead` is set.
+pub fn execute_transaction_data_in_parallel_streaming<'a>(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: &'a [TransactionBuilderExecutionData],
+    config: &'a ExecutionConfig,
+) -> impl Stream<Item = (String, Result<Signature, TransactionBuilderExecutionError>)> + 'a {
+    stream! {
+        let sequence_length = execution_data.len();
+        let run = &ExecutionRun::new(transaction_executor, config);
+        let parallel_execution_limit = config
+            .parallel_execution_limit
+            .unwrap_or(PARALLEL_EXECUTION_LIMIT);
+        let concurrency_limiter =
+            &ConcurrencyLimiter::new(parallel_execution_limit, config.adaptive_parallelism);
+
+        let futures = futures::stream::iter(execution_data.iter().enumerate())
+            .map(|(index, async_transaction_builder)| async move {
+                let human_index = index + 1;
+                let result = execute_parallel_item(
+                    run,
+                    concurrency_limiter,
+                    async_transaction_builder,
+                    human_index,
+                    Some(sequence_length),
+                )
+                .await;
+                (async_transaction_builder, human_index, result)
+            });
+        let mut results = pin!(match config.ordered_lookahead {
+            Some(lookahead) => futures.buffered(lookahead.max(1)).left_stream(),
+            None => futures.buffer_unordered(parallel_execution_limit).right_stream(),
+        });
+        while let Some((async_transaction_builder, human_index, result)) = results.next().await {
+            let tx_uuid = &async_transaction_builder.tx_uuid;
+            let result = match result {
+                Err(reason) => Err(execution_error(
+                    async_transaction_builder,
+                    human_index,
+                    reason.into(),
+                    format!("Transaction {human_index}/{tx_uuid} skipped: {reason:?}"),
+                )),
+                Ok(Ok(sig)) => Ok(sig),
+                Ok(Err(err)) => {
+                    let description = format!("Transaction {human_index}/{tx_uuid} failed: {:?}", err);
+                    Err(execution_error(async_transaction_builder, human_index, err, description))
+                }
+            };
+            yield (tx_uuid.clone(), result);
         }
     }
+}
 
-    if !errors.is_empty() {
-        return Err(errors);
+// Executes a transaction of the parallel run, the skip reason is returned when it was not started
+async fn execute_parallel_item(
+    run: &ExecutionRun<'_>,
+    concurrency_limiter: &ConcurrencyLimiter,
+    async_transaction_builder: &TransactionBuilderExecutionData,
+    human_index: usize,
+    sequence_length: Option<usize>,
+) -> Result<anyhow::Result<Signature>, SkipReason> {
+    let tx_uuid = &async_transaction_builder.tx_uuid;
+    // waiting for the slot window does not hold a permit
+    if let Err(err) = run.wait_for_slot_window(async_transaction_builder).await {
+        return Ok(Err(err));
+    }
+    let _permit = concurrency_limiter.acquire().await;
+    run.wait_while_paused().await;
+    let skip_reason = match run.skip_reason() {
+        Some(reason) => Some(reason),
+        None => match run.slot_skip_reason(async_transaction_builder).await {
+            Some(reason) => Some(reason),
+            None => run.approval_skip_reason(async_transaction_builder).await,
+        },
+    };
+    if let Some(reason) = skip_reason {
+        debug!("Skipping the transaction {human_index}/{tx_uuid}: {reason:?}");
+        return Err(reason);
+    }
+    if let Some(exhausted) = run.spend_budget_exhausted() {
+        return Ok(Err(exhausted.into()));
     }
+    debug!(
+        "Building the transaction {human_index}/{tx_uuid}{}",
+        size_description(sequence_length)
+    );
+    let result = run.execute(async_transaction_builder).await;
+    concurrency_limiter.record(&result);
+    Ok(result)
+}
 
-    Ok(())
+fn add_parallel_result(
+    report: &mut ExecutionReport,
+    async_transaction_builder: &TransactionBuilderExecutionData,
+    human_index: usize,
+    result: Result<anyhow::Result<Signature>, SkipReason>,
+) {
+    let tx_uuid = &async_transaction_builder.tx_uuid;
+    match result {
+        Err(reason) => {
+            report.add_skipped(async_transaction_builder, human_index, reason);
+        }
+        Ok(Ok(sig)) => {
+            debug!("Transaction {sig} {human_index}/{tx_uuid} executed in parallel successfully");
+            report.add_executed(async_transaction_builder, human_index, sig);
+        }
+        Ok(Err(err)) => {
+            let error_description =
+                format!("Transaction {human_index}/{tx_uuid} failed: {:?}", err);
+            debug!("{}", error_description);
+            report.add_failed(
+                async_transaction_builder,
+                human_index,
+                err,
+                error_description,
+            );
+        }
+    }
+}
+
+fn size_description(sequence_length: Option<usize>) -> String {
+    sequence_length.map_or_else(String::new, |length| format!(" (size: {length})"))
 }
 
 pub fn builder_to_execution_data(
-    rpc_url: String,
+    rpc_client: Arc<RpcClient>,
     transaction_builder: &mut TransactionBuilder,
     priority_fee_policy: Option<PriorityFeePolicy>,
     is_one_by_one: bool,
 ) -> Vec<TransactionBuilderExecutionData> {
+    let blockhash_cache = BlockhashCache::shared(rpc_client.clone());
     let transaction_builder_iterator =
         get_prepared_transaction_iterator(transaction_builder, is_one_by_one);
     transaction_builder_iterator
         .map(|prepared_transaction| {
-            let execution_data = TransactionBuilderExecutionData::new(
+            new_execution_data(
+                prepared_transaction,
+                &rpc_client,
+                &blockhash_cache,
+                &priority_fee_policy,
+            )
+        })
+        .collect()
+}
+
+/// Execution data of the failed transactions (matched by tx_uuid) for their re-execution,
+/// in the original order. The ones with `ExecutionErrorKind::OutcomeUnknown` are left out
+/// as they may still land.
+pub fn filter_failed(
+    execution_data: &[TransactionBuilderExecutionData],
+    errors: &TransactionBuilderExecutionErrors,
+) -> Vec<TransactionBuilderExecutionData> {
+    let failed_tx_uuids: HashSet<&str> = errors
+        .iter()
+        .filter(|error| error.kind != ExecutionErrorKind::OutcomeUnknown)
+        .map(|error| error.tx_uuid.as_str())
+        .collect();
+    execution_data
+        .iter()
+        .filter(|data| failed_tx_uuids.contains(data.tx_uuid.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Lazily built execution data, the next transaction is built only when it is pulled
+/// from the stream, i.e., a huge builder is built while the earlier transactions execute.
+pub fn builder_to_execution_stream(
+    rpc_client: Arc<RpcClient>,
+    mut transaction_builder: TransactionBuilder,
+    priority_fee_policy: Option<PriorityFeePolicy>,
+    is_one_by_one: bool,
+) -> impl Stream<Item = TransactionBuilderExecutionData> {
+    let blockhash_cache = BlockhashCache::shared(rpc_client.clone());
+    stream! {
+        // the builder is stepped directly to keep the stream Send
+        while let Some(prepared_transaction) = if is_one_by_one {
+            transaction_builder.build_next_combined()
+        } else {
+            transaction_builder.build_next()
+        } {
+            yield new_execution_data(
                 prepared_transaction,
-                rpc_url.clone(),
-                priority_fee_policy
-                    .clone()
-                    .map_or(PriorityFeePolicy::default(), |policy| policy),
+                &rpc_client,
+                &blockhash_cache,
+                &priority_fee_policy,
             );
+        }
+    }
+}
 
-            if log::log_enabled!(log::Level::Debug) {
-                let description = execution_data
-                    .prepared_transaction
-                    .single_description()
-                    .map_or_else(|| "".to_string(), |v| format!(", description: {}", v));
-                debug!(
-                    "Prepared transaction {}{}",
-                    execution_data.tx_uuid, description
-                );
-            }
+fn new_execution_data(
+    prepared_transaction: PreparedTransaction,
+    rpc_client: &Arc<RpcClient>,
+    blockhash_cache: &Arc<BlockhashCache>,
+    priority_fee_policy: &Option<PriorityFeePolicy>,
+) -> TransactionBuilderExecutionData {
+    let execution_data = TransactionBuilderExecutionData::new_with_blockhash_cache(
+        prepared_transaction,
+        rpc_client.clone(),
+        blockhash_cache.clone(),
+        priority_fee_policy
+            .clone()
+            .map_or(PriorityFeePolicy::default(), |policy| policy),
+    );
 
-            execution_data
-        })
-        .collect()
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        let description = execution_data
+            .prepared_transaction
+            .single_description()
+            .map_or_else(|| "".to_string(), |v| format!(", description: {}", v));
+        debug!(
+            "Prepared transaction {}{}",
+            execution_data.tx_uuid, description
+        );
+    }
+
+    execution_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::signature::Keypair;
+    use solana_transaction_builder::memo_instruction;
+
+    #[test]
+    fn test_with_tx_uuid_memo() {
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            ))
+            .unwrap();
+        let execution_data = TransactionBuilderExecutionData::new(
+            builder.build_one(),
+            Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())),
+            PriorityFeePolicy::default(),
+        )
+        .with_tx_uuid_memo()
+        .unwrap();
+
+        let instructions = execution_data.prepared_transaction.instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0], memo_instruction(&execution_data.tx_uuid));
+    }
 }
@@ -0,0 +1,222 @@
+use crate::is_throttling_error;
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::warn;
+
+/// Adaptive parallelism of the parallel execution: the parallelism is halved when the RPC
+/// throttles the requests and it grows back by one after as many consecutive successes
+/// as is the current parallelism.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveParallelism {
+    pub min_parallelism: usize,
+}
+
+impl Default for AdaptiveParallelism {
+    fn default() -> Self {
+        Self { min_parallelism: 1 }
+    }
+}
+
+// Limits the number of transactions executed at once
+pub(crate) struct ConcurrencyLimiter {
+    semaphore: Semaphore,
+    max_parallelism: usize,
+    adaptive: Option<AdaptiveParallelism>,
+    state: Mutex<ConcurrencyState>,
+}
+
+struct ConcurrencyState {
+    parallelism: usize,
+    // permits to be removed from the semaphore once they are released
+    permits_to_forget: usize,
+    successes: usize,
+}
+
+pub(crate) struct ConcurrencyPermit<'a> {
+    permit: Option<SemaphorePermit<'a>>,
+    limiter: &'a ConcurrencyLimiter,
+}
+
+impl Drop for ConcurrencyPermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().expect("Lock poisoned");
+        if state.permits_to_forget > 0 {
+            state.permits_to_forget -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl ConcurrencyLimiter {
+    pub(crate) fn new(max_parallelism: usize, adaptive: Option<AdaptiveParallelism>) -> Self {
+        let max_parallelism = max_parallelism.max(1);
+        Self {
+            semaphore: Semaphore::new(max_parallelism),
+            max_parallelism,
+            adaptive,
+            state: Mutex::new(ConcurrencyState {
+                parallelism: max_parallelism,
+                permits_to_forget: 0,
+                successes: 0,
+            }),
+        }
+    }
+
+    pub(crate) async fn acquire(&self) -> ConcurrencyPermit<'_> {
+        let permit = self
+            .semaphore
+            .acquire()
+            .await
+            .expect("Failed to acquire semaphore");
+        ConcurrencyPermit {
+            permit: Some(permit),
+            limiter: self,
+        }
+    }
+
+    // Adapts the parallelism to the result of a finished transaction
+    pub(crate) fn record<T>(&self, result: &anyhow::Result<T>) {
+        let Some(adaptive) = self.adaptive else {
+            return;
+        };
+        let mut state = self.state.lock().expect("Lock poisoned");
+        match result {
+            Err(err) if is_throttling_error(err) => {
+                let parallelism = (state.parallelism / 2).max(adaptive.min_parallelism.max(1));
+                if parallelism < state.parallelism {
+                    warn!(
+                        "RPC throttling detected, decreasing parallelism from {} to {parallelism}",
+                        state.parallelism
+                    );
+                    state.permits_to_forget += state.parallelism - parallelism;
+                    state.parallelism = parallelism;
+                    // permits not held by any transaction are removed right away
+                    while state.permits_to_forget > 0 {
+                        match self.semaphore.try_acquire() {
+                            Ok(permit) => {
+                                permit.forget();
+                                state.permits_to_forget -= 1;
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                }
+                state.successes = 0;
+            }
+            Ok(_) => {
+                state.successes += 1;
+                if state.successes >= state.parallelism && state.parallelism < self.max_parallelism
+                {
+                    state.successes = 0;
+                    state.parallelism += 1;
+                    if state.permits_to_forget > 0 {
+                        state.permits_to_forget -= 1;
+                    } else {
+                        self.semaphore.add_permits(1);
+                    }
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn throttled() -> anyhow::Result<()> {
+        Err(anyhow!("HTTP status client error (429 Too Many Requests)"))
+    }
+
+    fn parallelism(limiter: &ConcurrencyLimiter) -> (usize, usize) {
+        let state = limiter.state.lock().unwrap();
+        (state.parallelism, state.permits_to_forget)
+    }
+
+    #[tokio::test]
+    async fn test_shrink_while_permits_held() {
+        let limiter = ConcurrencyLimiter::new(4, Some(AdaptiveParallelism::default()));
+        let mut permits = Vec::new();
+        for _ in 0..4 {
+            permits.push(limiter.acquire().await);
+        }
+
+        limiter.record(&throttled());
+        assert_eq!(parallelism(&limiter), (2, 2));
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+
+        // the first two released permits are forgotten
+        permits.pop();
+        permits.pop();
+        assert_eq!(parallelism(&limiter), (2, 0));
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+        permits.clear();
+        assert_eq!(limiter.semaphore.available_permits(), 2);
+
+        // the free permits are removed right away, down to the minimum
+        limiter.record(&throttled());
+        limiter.record(&throttled());
+        assert_eq!(parallelism(&limiter), (1, 0));
+        assert_eq!(limiter.semaphore.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_grow_back_to_max_parallelism() {
+        let limiter = ConcurrencyLimiter::new(4, Some(AdaptiveParallelism::default()));
+        let held = limiter.acquire().await;
+        limiter.record(&throttled());
+        // three free permits, two of them forgotten at once
+        assert_eq!(parallelism(&limiter), (2, 0));
+        assert_eq!(limiter.semaphore.available_permits(), 1);
+
+        // grows by one after as many successes as the current parallelism
+        limiter.record(&Ok(()));
+        assert_eq!(parallelism(&limiter).0, 2);
+        limiter.record(&Ok(()));
+        assert_eq!(parallelism(&limiter).0, 3);
+        assert_eq!(limiter.semaphore.available_permits(), 2);
+        for _ in 0..3 {
+            limiter.record(&Ok(()));
+        }
+        assert_eq!(parallelism(&limiter).0, 4);
+        for _ in 0..10 {
+            limiter.record(&Ok(()));
+        }
+        assert_eq!(parallelism(&limiter).0, 4);
+        drop(held);
+        assert_eq!(limiter.semaphore.available_permits(), 4);
+
+        // other errors neither shrink nor count as successes
+        limiter.record(&Err::<(), _>(anyhow!("custom program error")));
+        assert_eq!(parallelism(&limiter).0, 4);
+    }
+
+    #[tokio::test]
+    async fn test_grow_while_permits_to_forget() {
+        let limiter = ConcurrencyLimiter::new(4, Some(AdaptiveParallelism::default()));
+        let mut permits = Vec::new();
+        for _ in 0..4 {
+            permits.push(limiter.acquire().await);
+        }
+        limiter.record(&throttled());
+        assert_eq!(parallelism(&limiter), (2, 2));
+        // growing cancels a pending forget instead of adding a permit
+        limiter.record(&Ok(()));
+        limiter.record(&Ok(()));
+        assert_eq!(parallelism(&limiter), (3, 1));
+        permits.clear();
+        assert_eq!(limiter.semaphore.available_permits(), 3);
+    }
+
+    #[test]
+    fn test_not_adaptive() {
+        let limiter = ConcurrencyLimiter::new(4, None);
+        limiter.record(&throttled());
+        assert_eq!(parallelism(&limiter), (4, 0));
+        assert_eq!(limiter.semaphore.available_permits(), 4);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ExecutionErrorKind;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+// Weight of the latest request in the moving averages of the latency and of the success rate
+const SMOOTHING: f64 = 0.2;
+
+/// Health of an RPC endpoint from the requests sent to it, see `RpcBroadcaster::health`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EndpointHealth {
+    pub url: String,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u64,
+    /// Moving average of the request latency
+    pub average_latency: Option<Duration>,
+    /// Moving average of the request success (1.0 for a success, 0.0 for a failure),
+    /// i.e., the old failures fade out once the endpoint recovers
+    pub recent_success_rate: Option<f64>,
+    /// Failures by the JSON-RPC error code of the response
+    pub rpc_error_codes: BTreeMap<i64, u64>,
+    pub error_kinds: HashMap<ExecutionErrorKind, u64>,
+    pub last_error: Option<String>,
+}
+
+impl EndpointHealth {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            ..Self::default()
+        }
+    }
+
+    /// Share of the successful requests, 1.0 when nothing was sent yet.
+    pub fn success_rate(&self) -> f64 {
+        let total = self.successes + self.failures;
+        if total == 0 {
+            1.0
+        } else {
+            self.successes as f64 / total as f64
+        }
+    }
+
+    /// The higher the healthier: the recent success rate lowered by the latency
+    /// and by the failures in a row, 1.0 when nothing was sent yet.
+    pub fn score(&self) -> f64 {
+        let latency_secs = self
+            .average_latency
+            .map_or(0.0, |latency| latency.as_secs_f64());
+        self.recent_success_rate.unwrap_or(1.0)
+            / (1.0 + latency_secs)
+            / (1.0 + self.consecutive_failures as f64)
+    }
+
+    pub(crate) fn record_success(&mut self, latency: Duration) {
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.record_latency(latency);
+        self.record_success_rate(1.0);
+    }
+
+    pub(crate) fn record_failure(&mut self, latency: Duration, error: &anyhow::Error) {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.record_latency(latency);
+        self.record_success_rate(0.0);
+        if let Some(ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })) =
+            error.downcast_ref::<ClientError>().map(ClientError::kind)
+        {
+            *self.rpc_error_codes.entry(*code).or_default() += 1;
+        }
+        *self
+            .error_kinds
+            .entry(ExecutionErrorKind::classify(error))
+            .or_default() += 1;
+        self.last_error = Some(format!("{error:#}"));
+    }
+
+    fn record_latency(&mut self, latency: Duration) {
+        self.average_latency = Some(self.average_latency.map_or(latency, |average| {
+            average.mul_f64(1.0 - SMOOTHING) + latency.mul_f64(SMOOTHING)
+        }));
+    }
+
+    fn record_success_rate(&mut self, success: f64) {
+        self.recent_success_rate = Some(self.recent_success_rate.map_or(success, |rate| {
+            rate * (1.0 - SMOOTHING) + success * SMOOTHING
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_score() {
+        let mut healthy = EndpointHealth::new("healthy".to_string());
+        assert_eq!(healthy.score(), 1.0);
+        healthy.record_success(Duration::from_millis(100));
+        let mut slow = EndpointHealth::new("slow".to_string());
+        slow.record_success(Duration::from_secs(2));
+        assert!(healthy.score() > slow.score());
+
+        let mut failing = EndpointHealth::new("failing".to_string());
+        for _ in 0..20 {
+            failing.record_failure(Duration::from_millis(100), &anyhow!("node is unhealthy"));
+        }
+        assert_eq!(failing.consecutive_failures, 20);
+        assert_eq!(failing.error_kinds[&ExecutionErrorKind::NodeUnhealthy], 20);
+        assert!(slow.score() > failing.score());
+
+        // the old failures fade out once the endpoint recovers
+        for _ in 0..20 {
+            failing.record_success(Duration::from_millis(100));
+        }
+        assert_eq!(failing.success_rate(), 0.5);
+        assert!(failing.score() > 0.9 * healthy.score());
+    }
+}
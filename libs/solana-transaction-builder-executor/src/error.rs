@@ -1,11 +1,212 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use solana_sdk::clock::Slot;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_builder::InstructionMetadata;
+use std::collections::BTreeMap;
 use std::fmt;
 
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// RPC rejected the request because of rate limits, by the HTTP status (429) or the JSON-RPC
+/// error code of the RPC client error, by the "too many requests" message otherwise
+pub fn is_throttling_error(error: &anyhow::Error) -> bool {
+    let client_error_throttled = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<ClientError>())
+        .any(|client_error| match client_error.kind() {
+            ClientErrorKind::Reqwest(err) => {
+                err.status().map(|status| status.as_u16()) == Some(TOO_MANY_REQUESTS)
+            }
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+                *code == i64::from(TOO_MANY_REQUESTS)
+            }
+            _ => false,
+        });
+    client_error_throttled
+        || format!("{error:#}")
+            .to_lowercase()
+            .contains("too many requests")
+}
+
+/// The transaction was not confirmed in time, it may still land until its blockhash expires
+pub(crate) fn is_unconfirmed_error(error: &anyhow::Error) -> bool {
+    let message = format!("{error:#}").to_lowercase();
+    message.contains("not confirmed")
+        || message.contains("timed out")
+        || message.contains("timeout")
+}
+
+/// The failure carries a transaction error, e.g. of a transaction that landed and failed on-chain
+pub(crate) fn is_transaction_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<ClientError>())
+        .any(|client_error| client_error.get_transaction_error().is_some())
+}
+
+/// The same signed transaction was sent before and it was already processed by the cluster
+pub fn is_already_processed_error(error: &anyhow::Error) -> bool {
+    let message = format!("{error:#}").to_lowercase();
+    message.contains("already been processed") || message.contains("alreadyprocessed")
+}
+
+/// Category of an execution failure, see `ExecutionErrorKind::classify`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ExecutionErrorKind {
+    /// The blockhash of the transaction expired before it landed
+    BlockhashExpired,
+    /// The RPC node is unhealthy or behind the cluster
+    NodeUnhealthy,
+    /// The RPC rejected the request because of rate limits
+    Throttled,
+    /// An account of the transaction was locked by another transaction
+    AccountInUse,
+    /// The fee payer cannot pay the fees or an account cannot pay the rent
+    InsufficientFunds,
+    /// An instruction failed (e.g. with a custom program error)
+    ProgramError,
+    /// The send approval, the spend budget or the assertions of the transaction stopped it
+    Rejected,
+    /// The execution was interrupted (e.g. by a deadline) after the transaction was sent,
+    /// it may still land, see `OutcomeUnknown`
+    OutcomeUnknown,
+    Other,
+}
+
+impl ExecutionErrorKind {
+    /// Classifies by the transaction error of the RPC client error in the chain,
+    /// by the error message otherwise.
+    pub fn classify(error: &anyhow::Error) -> Self {
+        if let Some(transaction_error) = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<ClientError>())
+            .find_map(ClientError::get_transaction_error)
+        {
+            return match transaction_error {
+                TransactionError::BlockhashNotFound => Self::BlockhashExpired,
+                TransactionError::AccountInUse => Self::AccountInUse,
+                TransactionError::InsufficientFundsForFee
+                | TransactionError::InsufficientFundsForRent { .. } => Self::InsufficientFunds,
+                TransactionError::InstructionError(..) => Self::ProgramError,
+                _ => Self::Other,
+            };
+        }
+        if error.chain().any(|cause| cause.is::<OutcomeUnknown>()) {
+            return Self::OutcomeUnknown;
+        }
+        if error
+            .chain()
+            .any(|cause| cause.is::<SpendBudgetExhausted>() || cause.is::<SlotWindowPassed>())
+        {
+            return Self::Rejected;
+        }
+        let message = format!("{error:#}").to_lowercase();
+        if is_throttling_error(error) {
+            Self::Throttled
+        } else if message.contains("blockhash not found")
+            || message.contains("block height exceeded")
+            || message.contains("blockhash expired")
+        {
+            Self::BlockhashExpired
+        } else if message.contains("node is unhealthy") || message.contains("node is behind") {
+            Self::NodeUnhealthy
+        } else if message.contains("account in use") {
+            Self::AccountInUse
+        } else if message.contains("insufficient funds")
+            || message.contains("insufficient lamports")
+        {
+            Self::InsufficientFunds
+        } else if message.contains("custom program error") || message.contains("instructionerror") {
+            Self::ProgramError
+        } else if message.contains("assertions") {
+            Self::Rejected
+        } else {
+            Self::Other
+        }
+    }
+
+    /// A transient failure that may succeed when the transaction is executed again,
+    /// the unknown failures are considered fatal.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::BlockhashExpired | Self::NodeUnhealthy | Self::Throttled | Self::AccountInUse
+        )
+    }
+}
+
+/// The fees and tips spent by the execution run reached its spend budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendBudgetExhausted {
+    pub budget_lamports: u64,
+    pub spent_lamports: u64,
+}
+
+impl fmt::Display for SpendBudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Spend budget exhausted: spent {} of {} lamports",
+            self.spent_lamports, self.budget_lamports
+        )
+    }
+}
+
+impl std::error::Error for SpendBudgetExhausted {}
+
+/// The execution of the sent transaction was interrupted before it was confirmed, it may still land.
+/// Such transactions are not re-executed by `filter_failed`, check their signatures first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutcomeUnknown {
+    pub signatures: Vec<Signature>,
+}
+
+impl fmt::Display for OutcomeUnknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Outcome unknown, the transaction may still land with one of the signatures {:?}",
+            self.signatures
+        )
+    }
+}
+
+impl std::error::Error for OutcomeUnknown {}
+
+/// The slot window of the transaction passed before it was started,
+/// see `TransactionBuilderExecutionData::slot_window`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotWindowPassed {
+    pub window_end: Slot,
+    pub slot: Slot,
+}
+
+impl fmt::Display for SlotWindowPassed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Slot window ending at {} passed, the current slot is {}",
+            self.window_end, self.slot
+        )
+    }
+}
+
+impl std::error::Error for SlotWindowPassed {}
+
 #[derive(Debug)]
 pub struct TransactionBuilderExecutionError {
     pub cause: anyhow::Error,
     pub tx_uuid: String,
     pub human_index: usize,
     pub description: String,
+    /// Classified from the cause
+    pub kind: ExecutionErrorKind,
+    pub labels: BTreeMap<String, String>,
+    /// Metadata of the instructions of the failed transaction
+    pub metadata: Vec<InstructionMetadata>,
 }
 
 impl TransactionBuilderExecutionError {
@@ -16,21 +217,45 @@ impl TransactionBuilderExecutionError {
         description: String,
     ) -> Self {
         TransactionBuilderExecutionError {
+            kind: ExecutionErrorKind::classify(&cause),
             cause,
             tx_uuid,
             human_index,
             description,
+            labels: BTreeMap::new(),
+            metadata: Vec::new(),
         }
     }
+
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+
+    pub fn with_metadata(mut self, metadata: Vec<InstructionMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 impl fmt::Display for TransactionBuilderExecutionError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "TransactionBuilderExecutionError: tx_uuid: {}, human_index: {}, description: {}, cause: {}",
-            self.tx_uuid, self.human_index, self.description, self.cause
-        )
+            "TransactionBuilderExecutionError: tx_uuid: {}, human_index: {}, description: {}, kind: {:?}, cause: {}",
+            self.tx_uuid, self.human_index, self.description, self.kind, self.cause
+        )?;
+        if !self.labels.is_empty() {
+            write!(f, ", labels: {:?}", self.labels)?;
+        }
+        if !self.metadata.is_empty() {
+            write!(f, ", metadata: {:?}", self.metadata)?;
+        }
+        Ok(())
     }
 }
 
@@ -40,14 +265,29 @@ impl std::error::Error for TransactionBuilderExecutionError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct TransactionBuilderExecutionErrors {
     errors: Vec<TransactionBuilderExecutionError>,
+    // (tx_uuid, signature) of the transactions of the same run that succeeded
+    partial_successes: Vec<(String, Signature)>,
 }
 
 impl TransactionBuilderExecutionErrors {
     pub fn new() -> Self {
-        TransactionBuilderExecutionErrors { errors: vec![] }
+        TransactionBuilderExecutionErrors {
+            errors: vec![],
+            partial_successes: vec![],
+        }
+    }
+
+    pub fn add_partial_success(&mut self, tx_uuid: String, signature: Signature) {
+        self.partial_successes.push((tx_uuid, signature));
+    }
+
+    /// The (tx_uuid, signature) pairs of the transactions executed successfully
+    /// by the run that failed, to reconcile its partial progress.
+    pub fn partial_successes(&self) -> &[(String, Signature)] {
+        &self.partial_successes
     }
 
     pub fn add_error_instance(&mut self, error: TransactionBuilderExecutionError) {
@@ -80,6 +320,10 @@ impl TransactionBuilderExecutionErrors {
     pub fn iter(&self) -> impl Iterator<Item = &TransactionBuilderExecutionError> {
         self.errors.iter()
     }
+
+    pub fn has_fatal_error(&self) -> bool {
+        self.errors.iter().any(|error| !error.is_retryable())
+    }
 }
 
 impl IntoIterator for TransactionBuilderExecutionErrors {
@@ -97,6 +341,14 @@ impl fmt::Display for TransactionBuilderExecutionErrors {
         for error in self.iter() {
             write!(f, "\n{}", error)?;
         }
+        if !self.partial_successes.is_empty() {
+            write!(
+                f,
+                "\n{} transactions succeeded: {:?}",
+                self.partial_successes.len(),
+                self.partial_successes
+            )?;
+        }
         Ok(())
     }
 }
@@ -108,3 +360,96 @@ impl std::error::Error for TransactionBuilderExecutionErrors {
             .map(|e| e as &(dyn std::error::Error + 'static))
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TransactionBuilderExecutionError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("TransactionBuilderExecutionError", 7)?;
+        state.serialize_field("tx_uuid", &self.tx_uuid)?;
+        state.serialize_field("human_index", &self.human_index)?;
+        state.serialize_field("description", &self.description)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field(
+            "cause",
+            &self
+                .cause
+                .chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        )?;
+        state.serialize_field("labels", &self.labels)?;
+        state.serialize_field(
+            "metadata",
+            &self
+                .metadata
+                .iter()
+                .map(|metadata| format!("{metadata:?}"))
+                .collect::<Vec<_>>(),
+        )?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TransactionBuilderExecutionErrors {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("TransactionBuilderExecutionErrors", 2)?;
+        state.serialize_field("errors", &self.errors)?;
+        state.serialize_field(
+            "partial_successes",
+            &self
+                .partial_successes
+                .iter()
+                .map(|(tx_uuid, signature)| (tx_uuid, signature.to_string()))
+                .collect::<Vec<_>>(),
+        )?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use solana_client::rpc_request::RpcResponseErrorData;
+    use solana_sdk::instruction::InstructionError;
+
+    #[test]
+    fn test_is_throttling_error() {
+        let rpc_error = |code: i64| {
+            anyhow::Error::from(ClientError::from(ClientErrorKind::RpcError(
+                RpcError::RpcResponseError {
+                    code,
+                    message: "RPC error".to_string(),
+                    data: RpcResponseErrorData::Empty,
+                },
+            )))
+        };
+        assert!(is_throttling_error(&rpc_error(429)));
+        assert!(!is_throttling_error(&rpc_error(-32002)));
+        assert!(is_throttling_error(&anyhow!(
+            "HTTP status client error (429 Too Many Requests)"
+        )));
+        assert!(!is_throttling_error(&anyhow!(
+            "Transaction 4294SigXyz failed: insufficient lamports 1429, need 5000"
+        )));
+        assert_eq!(
+            ExecutionErrorKind::classify(&anyhow!("Slot 254290429 is behind")),
+            ExecutionErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_is_transaction_error() {
+        let landed_error = anyhow::Error::from(ClientError::from(
+            TransactionError::InstructionError(0, InstructionError::Custom(1)),
+        ))
+        .context("Transaction failed");
+        assert!(is_transaction_error(&landed_error));
+        assert!(!is_transaction_error(&anyhow!("Transaction not confirmed")));
+    }
+}
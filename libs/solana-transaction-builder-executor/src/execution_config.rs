@@ -0,0 +1,124 @@
+use crate::{
+    AdaptiveParallelism, ExecutionController, ExecutionObserver, FeePayerPool, JitoTip,
+    LeaderAwareTiming, LookupTableManager, PriorityFeeEstimator, RateLimiter, RetryPolicy,
+    SlotPacer, TransactionBroadcaster, TransactionBuilderExecutionErrors,
+};
+use futures::future::BoxFuture;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::clock::Slot;
+use solana_transaction_builder::PreparedTransaction;
+use solana_transaction_executor::PriorityFeePolicy;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+pub const DEFAULT_MAX_BLOCKHASH_RESIGNS: usize = 3;
+
+/// Callback consulted before a transaction is sent, returning false skips the transaction.
+pub type SendApproval =
+    Arc<dyn for<'a> Fn(&'a PreparedTransaction) -> BoxFuture<'a, bool> + Send + Sync>;
+
+/// Options of the execution of a list of transaction data.
+#[derive(Clone, Default)]
+pub struct ExecutionConfig {
+    /// Sequential execution: stop on the first failed transaction
+    pub fail_on_first_error: bool,
+    /// Sequential execution: `fail_on_first_error` stops on the first fatal
+    /// (not `ExecutionErrorKind::is_retryable`) failure only
+    pub fail_on_fatal_error_only: bool,
+    /// Parallel execution: how many transactions are executed at once
+    pub parallel_execution_limit: Option<usize>,
+    /// Parallel execution: the parallelism is lowered when the RPC throttles the requests
+    pub adaptive_parallelism: Option<AdaptiveParallelism>,
+    /// Parallel execution: transactions writing to the same accounts are executed in separate waves
+    pub conflict_scheduling: bool,
+    /// Parallel execution: a transaction is started only when it is at most this many
+    /// transactions ahead of the earliest unfinished one, and the results are reported
+    /// in the original order (e.g. by `execute_transaction_data_in_parallel_streaming`)
+    pub ordered_lookahead: Option<usize>,
+    /// When cancelled no new transactions are started, the in-flight ones are let finish
+    /// (e.g. on a shutdown signal, see `cancel_on_shutdown_signal`)
+    pub cancellation_token: Option<CancellationToken>,
+    /// Pauses and resumes the execution, reports its progress
+    pub controller: Option<ExecutionController>,
+    /// Time the in-flight transactions are awaited after the cancellation, then the sent ones
+    /// not landed yet are reported with `ExecutionErrorKind::OutcomeUnknown`,
+    /// without it they are awaited until they finish
+    pub shutdown_grace_period: Option<Duration>,
+    /// Time after which the transaction is not escalated anymore, it is marked as failed
+    /// when it was not sent yet, with `ExecutionErrorKind::OutcomeUnknown` otherwise
+    pub transaction_timeout: Option<Duration>,
+    /// How many times a transaction is re-signed with a fresh blockhash when the blockhash
+    /// of all its sent transactions expires, then the attempt fails
+    /// with `ExecutionErrorKind::BlockhashExpired`, `DEFAULT_MAX_BLOCKHASH_RESIGNS` when not set
+    pub max_blockhash_resigns: Option<usize>,
+    /// Time budget of the whole execution, transactions not started in time are skipped
+    pub execution_timeout: Option<Duration>,
+    /// Transactions not started before the cluster reaches this slot are skipped
+    pub deadline_slot: Option<Slot>,
+    /// Transactions not started within the epoch the execution started in are skipped,
+    /// for operations that must not straddle the epoch boundary (the in-flight ones are let finish)
+    pub stop_at_epoch_boundary: bool,
+    /// Retrying of failed transactions, no retries by default
+    pub retry_policy: RetryPolicy,
+    /// Limits the rate of the transaction submissions independently of the parallelism
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Delays the submissions toward the slots of the preferred leaders
+    pub leader_timing: Option<Arc<LeaderAwareTiming>>,
+    /// Caps the transaction submissions per slot
+    pub slot_pacer: Option<Arc<SlotPacer>>,
+    /// Listeners notified about the lifecycle of the executed transactions
+    pub observers: Vec<Arc<dyn ExecutionObserver>>,
+    /// Approval of every transaction before it is sent (e.g. human-in-the-loop for admin transactions),
+    /// the whole execution can be aborted by cancelling the `cancellation_token` from the callback
+    pub send_approval: Option<SendApproval>,
+    /// Additional channels (e.g. TPU) every signed transaction is pushed through
+    pub broadcasters: Vec<Arc<dyn TransactionBroadcaster>>,
+    /// When set, every signed transaction is sent once over the RPC of the execution data
+    /// with this config (e.g. `skip_preflight`, `max_retries`, `min_context_slot`) and confirmed
+    /// by polling its signature status instead of being handed over to the transaction executor
+    /// (its send settings do not apply), a failed send fails the attempt. Without a confirmation
+    /// in 15 seconds the transaction is sent with the next priority fee configuration.
+    /// Overridden by `TransactionBuilderExecutionData::send_transaction_config`.
+    pub send_transaction_config: Option<RpcSendTransactionConfig>,
+    /// Run-level priority fee policy overriding the one of every execution data,
+    /// see `TransactionBuilderExecutionData::priority_fee_policy_override`
+    pub priority_fee_policy: Option<PriorityFeePolicy>,
+    /// Keys of the execution data labels exported as the `labels` metric label (`metrics` feature),
+    /// none by default as every distinct value (e.g. `epoch=601`) creates a new time series
+    pub metric_label_keys: Vec<String>,
+    /// The compute units consumed and the fee paid are fetched for every confirmed transaction
+    /// into `ExecutedTransaction::cost` (one `getTransaction` request per transaction)
+    pub fetch_transaction_costs: bool,
+    /// Compute unit price estimated whenever a transaction is built
+    pub priority_fee_estimator: Option<Arc<dyn PriorityFeeEstimator>>,
+    /// Jito tip transfer appended to every built transaction
+    pub jito_tip: Option<Arc<JitoTip>>,
+    /// Lamports the run may spend on fees and tips of the landed transactions (the ones failed
+    /// on-chain included), the fees are estimated until fetched with `fetch_transaction_costs`,
+    /// once exhausted no new transactions are started and they fail with `SpendBudgetExhausted`
+    pub spend_budget_lamports: Option<u64>,
+    /// Transactions exceeding the packet size as legacy ones are sent as v0 transactions
+    /// with their accounts in lookup tables of the manager
+    pub lookup_table_manager: Option<Arc<LookupTableManager>>,
+    /// Every built transaction is paid by the next fee payer of the pool instead of its own one
+    pub fee_payer_pool: Option<Arc<FeePayerPool>>,
+}
+
+impl ExecutionConfig {
+    // The sequence stops on the failures of the errors
+    pub(crate) fn stops_on(&self, errors: &TransactionBuilderExecutionErrors) -> bool {
+        self.fail_on_first_error
+            && if self.fail_on_fatal_error_only {
+                errors.has_fatal_error()
+            } else {
+                !errors.is_empty()
+            }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .map_or(false, CancellationToken::is_cancelled)
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Handle controlling a running execution, registered in `ExecutionConfig::controller`
+/// and kept (cloned) by the operator. A paused execution starts no new transactions,
+/// the in-flight ones continue.
+#[derive(Debug, Clone)]
+pub struct ExecutionController {
+    inner: Arc<ControllerState>,
+}
+
+#[derive(Debug)]
+struct ControllerState {
+    paused: watch::Sender<bool>,
+    started: AtomicUsize,
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+/// Counters of the transactions of the execution at the time of the query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionProgress {
+    pub started: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl ExecutionProgress {
+    /// Started transactions that are not finished yet.
+    pub fn in_flight(&self) -> usize {
+        self.started.saturating_sub(self.succeeded + self.failed)
+    }
+}
+
+impl Default for ExecutionController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionController {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(ControllerState {
+                paused: watch::Sender::new(false),
+                started: AtomicUsize::new(0),
+                succeeded: AtomicUsize::new(0),
+                failed: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.inner.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.inner.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.inner.paused.borrow()
+    }
+
+    pub fn progress(&self) -> ExecutionProgress {
+        ExecutionProgress {
+            started: self.inner.started.load(Ordering::Relaxed),
+            succeeded: self.inner.succeeded.load(Ordering::Relaxed),
+            failed: self.inner.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) async fn wait_while_paused(&self) {
+        let mut paused = self.inner.paused.subscribe();
+        // the sender lives in the controller, the wait cannot fail
+        let _ = paused.wait_for(|paused| !paused).await;
+    }
+
+    pub(crate) fn record_started(&self) {
+        self.inner.started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_finished<T>(&self, result: &anyhow::Result<T>) {
+        let counter = if result.is_ok() {
+            &self.inner.succeeded
+        } else {
+            &self.inner.failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_resume() {
+        let controller = ExecutionController::new();
+        // not paused, no wait
+        controller.wait_while_paused().await;
+
+        let operator = controller.clone();
+        operator.pause();
+        assert!(controller.is_paused());
+        let waiting = tokio::spawn({
+            let controller = controller.clone();
+            async move { controller.wait_while_paused().await }
+        });
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(!waiting.is_finished());
+
+        operator.resume();
+        assert!(!controller.is_paused());
+        waiting.await.unwrap();
+    }
+
+    #[test]
+    fn test_progress() {
+        let controller = ExecutionController::default();
+        assert_eq!(controller.progress(), ExecutionProgress::default());
+        for _ in 0..4 {
+            controller.record_started();
+        }
+        controller.record_finished(&Ok(()));
+        controller.record_finished(&Ok(()));
+        controller.record_finished(&Err::<(), _>(anyhow!("failed")));
+
+        let progress = controller.clone().progress();
+        assert_eq!(
+            progress,
+            ExecutionProgress {
+                started: 4,
+                succeeded: 2,
+                failed: 1,
+            }
+        );
+        assert_eq!(progress.in_flight(), 1);
+    }
+}
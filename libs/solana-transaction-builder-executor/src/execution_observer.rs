@@ -0,0 +1,53 @@
+use crate::TransactionBuilderExecutionData;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_executor::PriorityFeeConfiguration;
+use std::time::Duration;
+
+/// Listener of the transaction execution lifecycle, registered in `ExecutionConfig::observers`.
+/// The hooks are called synchronously from the execution, a long-running work
+/// (e.g. pushing a notification) is expected to be spawned by the observer.
+pub trait ExecutionObserver: Send + Sync {
+    /// The transaction was built and signed with a fresh blockhash.
+    fn on_built(
+        &self,
+        _execution_data: &TransactionBuilderExecutionData,
+        _transaction: &VersionedTransaction,
+        _priority_fee_configuration: &PriorityFeeConfiguration,
+    ) {
+    }
+
+    /// The signed transaction is submitted: with a send config (see
+    /// `ExecutionConfig::send_transaction_config`) it was accepted by the RPC, otherwise
+    /// it is being handed over to the transaction executor and the executor sends it
+    /// only afterwards, i.e., the send itself may still fail.
+    fn on_sent(
+        &self,
+        _execution_data: &TransactionBuilderExecutionData,
+        _transaction: &VersionedTransaction,
+        _priority_fee_configuration: &PriorityFeeConfiguration,
+    ) {
+    }
+
+    /// The transaction was executed successfully.
+    fn on_confirmed(
+        &self,
+        _execution_data: &TransactionBuilderExecutionData,
+        _signature: &Signature,
+    ) {
+    }
+
+    /// The attempt failed and the transaction is going to be retried after the delay.
+    fn on_retry(
+        &self,
+        _execution_data: &TransactionBuilderExecutionData,
+        _attempt: usize,
+        _error: &anyhow::Error,
+        _delay: Duration,
+    ) {
+    }
+
+    /// The transaction failed and is not retried anymore.
+    fn on_failed(&self, _execution_data: &TransactionBuilderExecutionData, _error: &anyhow::Error) {
+    }
+}
@@ -0,0 +1,319 @@
+#[cfg(feature = "async")]
+use crate::TransactionBuilderExecutionData;
+use crate::{
+    FeeSpendSummary, TransactionBuilderExecutionError, TransactionBuilderExecutionErrors,
+    TransactionCost, TransactionSpend,
+};
+use solana_sdk::signature::Signature;
+use solana_transaction_builder::{InstructionMetadata, PreparedTransaction};
+use std::collections::BTreeMap;
+#[cfg(feature = "async")]
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+// shared by the async and the blocking parallel execution
+pub(crate) const PARALLEL_EXECUTION_LIMIT: usize = 30;
+
+/// UUID formatted `PreparedTransaction::content_hash` (the blockhash is excluded).
+/// The same instructions with the same fee payer and signers result in the same identifier.
+pub fn content_tx_uuid(prepared_transaction: &PreparedTransaction) -> String {
+    let content_hash = prepared_transaction.content_hash().to_bytes();
+    let mut uuid_bytes = [0u8; 16];
+    uuid_bytes.copy_from_slice(&content_hash[..16]);
+    Uuid::from_bytes(uuid_bytes).to_string()
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExecutedTransaction {
+    pub tx_uuid: String,
+    pub human_index: usize,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_signature"))]
+    pub signature: Signature,
+    pub labels: BTreeMap<String, String>,
+    /// Metadata of the instructions of the transaction
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_metadata"))]
+    pub metadata: Vec<InstructionMetadata>,
+    /// Distinct descriptions of the instructions of the transaction
+    pub description: Option<String>,
+    /// Fetched with `ExecutionConfig::fetch_transaction_costs`
+    pub cost: Option<TransactionCost>,
+    /// Fees and tip of the landed transaction, unknown when it was not executed by the run
+    pub spend: Option<TransactionSpend>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SkipReason {
+    /// Execution was cancelled before the transaction was started
+    Cancelled,
+    /// Sequential execution stopped on an earlier failed transaction
+    PreviousFailure,
+    /// Time budget of the execution was exhausted before the transaction was started
+    DeadlineExceeded,
+    /// The cluster reached the deadline slot of the execution before the transaction was started
+    SlotDeadlineExceeded,
+    /// The epoch the execution started in ended before the transaction was started
+    EpochBoundaryReached,
+    /// The send approval callback rejected the transaction
+    NotApproved,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Transaction skipped: {self:?}")
+    }
+}
+
+// The cause of a skipped transaction in the streamed results
+impl std::error::Error for SkipReason {}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SkippedTransaction {
+    pub tx_uuid: String,
+    pub human_index: usize,
+    pub reason: SkipReason,
+    pub labels: BTreeMap<String, String>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_metadata"))]
+    pub metadata: Vec<InstructionMetadata>,
+}
+
+/// Outcome of the execution of a list of transaction data.
+/// Serializable with the `serde` feature, the error causes are flattened to strings.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExecutionReport {
+    pub executed: Vec<ExecutedTransaction>,
+    pub skipped: Vec<SkippedTransaction>,
+    pub errors: TransactionBuilderExecutionErrors,
+}
+
+impl ExecutionReport {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The errors carry the executed transactions as their partial successes.
+    pub fn into_result(self) -> Result<Self, TransactionBuilderExecutionErrors> {
+        if self.errors.is_empty() {
+            Ok(self)
+        } else {
+            let mut errors = self.errors;
+            for executed in self.executed {
+                errors.add_partial_success(executed.tx_uuid, executed.signature);
+            }
+            Err(errors)
+        }
+    }
+
+    /// Compute units consumed by the executed transactions with a known cost.
+    pub fn total_compute_units_consumed(&self) -> u64 {
+        self.executed
+            .iter()
+            .filter_map(|executed| executed.cost?.compute_units_consumed)
+            .sum()
+    }
+
+    /// Fees paid by the executed transactions with a known cost.
+    pub fn total_fee_lamports(&self) -> u64 {
+        self.executed
+            .iter()
+            .filter_map(|executed| executed.cost.map(|cost| cost.fee_lamports))
+            .sum()
+    }
+
+    /// Lamports spent by the executed transactions broken down by description and label.
+    pub fn fee_spend_summary(&self) -> FeeSpendSummary {
+        FeeSpendSummary::new(&self.executed)
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn set_transaction_spends(
+        &mut self,
+        mut transaction_spends: HashMap<Signature, TransactionSpend>,
+    ) {
+        for executed in &mut self.executed {
+            if let Some(spend) = transaction_spends.remove(&executed.signature) {
+                executed.spend = Some(spend);
+            }
+        }
+    }
+
+    // The fetched fee replaces the estimated one
+    #[cfg(feature = "async")]
+    pub(crate) fn set_transaction_costs(
+        &mut self,
+        mut transaction_costs: HashMap<Signature, TransactionCost>,
+    ) {
+        for executed in &mut self.executed {
+            if let Some(cost) = transaction_costs.remove(&executed.signature) {
+                if let Some(spend) = &mut executed.spend {
+                    spend.priority_fee_lamports =
+                        cost.fee_lamports.saturating_sub(spend.base_fee_lamports);
+                }
+                executed.cost = Some(cost);
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn add_executed(
+        &mut self,
+        execution_data: &TransactionBuilderExecutionData,
+        human_index: usize,
+        signature: Signature,
+    ) {
+        self.executed.push(ExecutedTransaction {
+            tx_uuid: execution_data.tx_uuid.clone(),
+            human_index,
+            signature,
+            labels: execution_data.labels.clone(),
+            metadata: execution_data.prepared_transaction.metadata(),
+            description: transaction_description(&execution_data.prepared_transaction),
+            cost: None,
+            spend: None,
+        });
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn add_skipped(
+        &mut self,
+        execution_data: &TransactionBuilderExecutionData,
+        human_index: usize,
+        reason: SkipReason,
+    ) {
+        self.skipped.push(SkippedTransaction {
+            tx_uuid: execution_data.tx_uuid.clone(),
+            human_index,
+            reason,
+            labels: execution_data.labels.clone(),
+            metadata: execution_data.prepared_transaction.metadata(),
+        });
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn add_failed(
+        &mut self,
+        execution_data: &TransactionBuilderExecutionData,
+        human_index: usize,
+        cause: anyhow::Error,
+        description: String,
+    ) {
+        self.errors.add_error_instance(execution_error(
+            execution_data,
+            human_index,
+            cause,
+            description,
+        ));
+    }
+
+    /// Result of a prepared transaction executed outside of the transaction executor
+    /// (e.g. by the blocking or the program test execution), identified by its content tx_uuid.
+    #[allow(dead_code)]
+    pub(crate) fn add_prepared_result(
+        &mut self,
+        prepared_transaction: &PreparedTransaction,
+        human_index: usize,
+        result: anyhow::Result<Signature>,
+        description: &str,
+    ) {
+        let tx_uuid = content_tx_uuid(prepared_transaction);
+        match result {
+            Ok(signature) => {
+                debug!("Transaction {human_index} ({tx_uuid}) executed: {signature}");
+                self.executed.push(ExecutedTransaction {
+                    tx_uuid,
+                    human_index,
+                    signature,
+                    labels: prepared_transaction.tags(),
+                    metadata: prepared_transaction.metadata(),
+                    description: transaction_description(prepared_transaction),
+                    cost: None,
+                    spend: None,
+                });
+            }
+            Err(err) => {
+                error!("Transaction {human_index} ({tx_uuid}) failed: {err:#}");
+                self.errors.add_error_instance(
+                    TransactionBuilderExecutionError::new(
+                        err,
+                        tx_uuid,
+                        human_index,
+                        description.to_string(),
+                    )
+                    .with_labels(prepared_transaction.tags())
+                    .with_metadata(prepared_transaction.metadata()),
+                );
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn add_prepared_skipped(
+        &mut self,
+        prepared_transaction: &PreparedTransaction,
+        human_index: usize,
+        reason: SkipReason,
+    ) {
+        self.skipped.push(SkippedTransaction {
+            tx_uuid: content_tx_uuid(prepared_transaction),
+            human_index,
+            reason,
+            labels: prepared_transaction.tags(),
+            metadata: prepared_transaction.metadata(),
+        });
+    }
+}
+
+// Distinct descriptions of the instructions, the same operation gets the same description
+// regardless of the position of its instructions in the transaction
+fn transaction_description(prepared_transaction: &PreparedTransaction) -> Option<String> {
+    let mut descriptions: Vec<&str> = Vec::new();
+    for description in prepared_transaction
+        .instruction_descriptions
+        .iter()
+        .flatten()
+    {
+        if !descriptions.contains(&description.as_str()) {
+            descriptions.push(description);
+        }
+    }
+    (!descriptions.is_empty()).then(|| descriptions.join(", "))
+}
+
+#[cfg(feature = "async")]
+pub(crate) fn execution_error(
+    execution_data: &TransactionBuilderExecutionData,
+    human_index: usize,
+    cause: anyhow::Error,
+    description: String,
+) -> TransactionBuilderExecutionError {
+    TransactionBuilderExecutionError::new(
+        cause,
+        execution_data.tx_uuid.clone(),
+        human_index,
+        description,
+    )
+    .with_labels(execution_data.labels.clone())
+    .with_metadata(execution_data.prepared_transaction.metadata())
+}
+
+#[cfg(feature = "serde")]
+fn serialize_signature<S: serde::Serializer>(
+    signature: &Signature,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(signature)
+}
+
+// The metadata are of arbitrary types, they are serialized as their debug output
+#[cfg(feature = "serde")]
+fn serialize_metadata<S: serde::Serializer>(
+    metadata: &[InstructionMetadata],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(metadata.iter().map(|metadata| format!("{metadata:?}")))
+}
@@ -0,0 +1,1263 @@
+use crate::error::{is_transaction_error, is_unconfirmed_error};
+use crate::metrics;
+use crate::state_assertions::{check_assertions, fetch_assertion_accounts};
+use crate::transaction_adjustments::TransactionAdjustments;
+use crate::transaction_cost::fetch_transaction_cost;
+use crate::{
+    is_already_processed_error, ExecutionConfig, ExecutionObserver, ExecutionReport, JitoTip,
+    OutcomeUnknown, SkipReason, SlotWindowPassed, SpendBudgetExhausted,
+    TransactionBuilderExecutionData, TransactionCost, TransactionSpend,
+    DEFAULT_MAX_BLOCKHASH_RESIGNS,
+};
+use anyhow::anyhow;
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+use solana_client::client_error::ClientError;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::clock::{Slot, DEFAULT_MS_PER_SLOT};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_builder::AccountAssertion;
+use solana_transaction_executor::{PriorityFeeConfiguration, TransactionExecutor};
+use solana_transaction_status::TransactionStatus;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+use tracing::{debug, field, instrument, Span};
+
+const BLOCKHASH_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const SIGNATURE_STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+// Time a transaction sent with the send config is awaited before the next priority fee configuration
+const SEND_CONFIG_ESCALATION_INTERVAL: Duration = Duration::from_secs(15);
+const SLOT_WINDOW_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// State shared by the transactions of a single execution run
+pub(crate) struct ExecutionRun<'a> {
+    transaction_executor: Arc<TransactionExecutor>,
+    config: &'a ExecutionConfig,
+    deadline: Option<Instant>,
+    // first slot of the epoch following the one of the first started transaction
+    next_epoch_slot: OnceCell<Slot>,
+    // fees and tips of the landed transactions
+    spent_lamports: AtomicU64,
+    // fetched with ExecutionConfig::fetch_transaction_costs
+    transaction_costs: Mutex<HashMap<Signature, TransactionCost>>,
+    // estimated spend of the landed transactions
+    transaction_spends: Mutex<HashMap<Signature, TransactionSpend>>,
+}
+
+struct SentTransaction {
+    signature: Signature,
+    // unknown for a pre-signed transaction
+    last_valid_block_height: Option<u64>,
+    estimated_spend: TransactionSpend,
+    compute_unit_price: Option<u64>,
+}
+
+impl<'a> ExecutionRun<'a> {
+    pub(crate) fn new(
+        transaction_executor: Arc<TransactionExecutor>,
+        config: &'a ExecutionConfig,
+    ) -> Self {
+        Self {
+            transaction_executor,
+            config,
+            deadline: config
+                .execution_timeout
+                .map(|timeout| Instant::now() + timeout),
+            next_epoch_slot: OnceCell::new(),
+            spent_lamports: AtomicU64::new(0),
+            transaction_costs: Mutex::new(HashMap::new()),
+            transaction_spends: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &ExecutionConfig {
+        self.config
+    }
+
+    // Adds the spends and the costs of the landed transactions to the report
+    pub(crate) fn finish_report(&self, report: &mut ExecutionReport) {
+        report.set_transaction_spends(std::mem::take(&mut self.transaction_spends.lock().unwrap()));
+        report.set_transaction_costs(std::mem::take(&mut self.transaction_costs.lock().unwrap()));
+    }
+
+    // Waits while the controller pauses the execution, the cancellation ends the wait
+    pub(crate) async fn wait_while_paused(&self) {
+        let Some(controller) = &self.config.controller else {
+            return;
+        };
+        match &self.config.cancellation_token {
+            Some(cancellation_token) => tokio::select! {
+                () = controller.wait_while_paused() => {}
+                () = cancellation_token.cancelled() => {}
+            },
+            None => controller.wait_while_paused().await,
+        }
+    }
+
+    // Reason to not start a new transaction
+    pub(crate) fn skip_reason(&self) -> Option<SkipReason> {
+        if self.config.is_cancelled() {
+            Some(SkipReason::Cancelled)
+        } else if self
+            .deadline
+            .map_or(false, |deadline| Instant::now() >= deadline)
+        {
+            Some(SkipReason::DeadlineExceeded)
+        } else {
+            None
+        }
+    }
+
+    // Slot deadlines of the run, the current slot is fetched only when one is configured
+    pub(crate) async fn slot_skip_reason(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> Option<SkipReason> {
+        if self.config.deadline_slot.is_none() && !self.config.stop_at_epoch_boundary {
+            return None;
+        }
+        let rpc_client = &execution_data.rpc_client;
+        let slot = rpc_client
+            .get_slot()
+            .await
+            .map_err(|err| {
+                debug!(
+                    "Failed to fetch the slot for the transaction {}: {err:?}",
+                    execution_data.tx_uuid
+                )
+            })
+            .ok()?;
+        if self
+            .config
+            .deadline_slot
+            .map_or(false, |deadline_slot| slot >= deadline_slot)
+        {
+            return Some(SkipReason::SlotDeadlineExceeded);
+        }
+        if self.config.stop_at_epoch_boundary {
+            let next_epoch_slot = self
+                .next_epoch_slot
+                .get_or_try_init(|| async {
+                    let epoch_info = rpc_client.get_epoch_info().await?;
+                    anyhow::Ok(
+                        epoch_info.absolute_slot - epoch_info.slot_index
+                            + epoch_info.slots_in_epoch,
+                    )
+                })
+                .await
+                .map_err(|err| {
+                    debug!(
+                        "Failed to fetch the epoch info for the transaction {}: {err:?}",
+                        execution_data.tx_uuid
+                    )
+                })
+                .ok()?;
+            if slot >= *next_epoch_slot {
+                return Some(SkipReason::EpochBoundaryReached);
+            }
+        }
+        None
+    }
+
+    // Waits until the slot window of the transaction opens, a cancelled or timed out run
+    // stops waiting and the transaction is skipped then
+    pub(crate) async fn wait_for_slot_window(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> anyhow::Result<()> {
+        let Some(slot_window) = &execution_data.slot_window else {
+            return Ok(());
+        };
+        while self.skip_reason().is_none() {
+            let slot = execution_data
+                .rpc_client
+                .get_slot_with_commitment(CommitmentConfig::processed())
+                .await?;
+            if slot > *slot_window.end() {
+                return Err(SlotWindowPassed {
+                    window_end: *slot_window.end(),
+                    slot,
+                }
+                .into());
+            }
+            if slot >= *slot_window.start() {
+                return Ok(());
+            }
+            let slots_to_window = u32::try_from(slot_window.start() - slot).unwrap_or(u32::MAX);
+            let wait = (Duration::from_millis(DEFAULT_MS_PER_SLOT) * slots_to_window)
+                .min(SLOT_WINDOW_CHECK_INTERVAL);
+            match &self.config.cancellation_token {
+                Some(cancellation_token) => tokio::select! {
+                    () = tokio::time::sleep(wait) => {}
+                    () = cancellation_token.cancelled() => {}
+                },
+                None => tokio::time::sleep(wait).await,
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn spend_budget_exhausted(&self) -> Option<SpendBudgetExhausted> {
+        let budget_lamports = self.config.spend_budget_lamports?;
+        let spent_lamports = self.spent_lamports.load(Ordering::Relaxed);
+        (spent_lamports >= budget_lamports).then_some(SpendBudgetExhausted {
+            budget_lamports,
+            spent_lamports,
+        })
+    }
+
+    // Consults the send approval, the run may get cancelled or time out while waiting for it
+    pub(crate) async fn approval_skip_reason(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> Option<SkipReason> {
+        if let Some(send_approval) = &self.config.send_approval {
+            if !send_approval(&execution_data.prepared_transaction).await {
+                return Some(SkipReason::NotApproved);
+            }
+            return self.skip_reason();
+        }
+        None
+    }
+
+    #[instrument(
+        name = "transaction",
+        skip_all,
+        fields(
+            tx_uuid = %execution_data.tx_uuid,
+            attempt = field::Empty,
+            signature = field::Empty,
+            priority_fee_configuration = field::Empty
+        )
+    )]
+    pub(crate) async fn execute(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> anyhow::Result<Signature> {
+        let started_at = Instant::now();
+        if let Some(controller) = &self.config.controller {
+            controller.record_started();
+        }
+        let previous_accounts = self.fetch_previous_accounts(execution_data).await;
+        let sent_signatures = Mutex::new(Vec::new());
+        let execution = self.within_shutdown_grace_period(
+            self.execute_with_retries(execution_data, &sent_signatures),
+        );
+        let transaction_deadline = self
+            .config
+            .transaction_timeout
+            .map(|timeout| started_at + timeout);
+        let interrupted = match transaction_deadline.into_iter().chain(self.deadline).min() {
+            Some(deadline) => tokio::time::timeout_at(deadline, execution)
+                .await
+                .unwrap_or_else(|_| Err("Transaction execution deadline exceeded".to_string())),
+            None => execution.await,
+        };
+        let result = match interrupted {
+            Ok(result) => result,
+            Err(reason) => {
+                self.settle_interrupted(execution_data, &sent_signatures, reason)
+                    .await
+            }
+        };
+        let result = match result {
+            Ok(signature) => self
+                .verify_post_assertions(execution_data, previous_accounts.as_deref())
+                .await
+                .map(|()| signature)
+                .map_err(|err| {
+                    err.context(format!(
+                        "Transaction {signature} confirmed, its post-assertions failed"
+                    ))
+                }),
+            Err(err) => Err(err),
+        };
+        if let Some(controller) = &self.config.controller {
+            controller.record_finished(&result);
+        }
+        match &result {
+            Ok(signature) => {
+                Span::current().record("signature", field::display(signature));
+                self.notify(|observer| observer.on_confirmed(execution_data, signature));
+                metrics::transaction_confirmed(
+                    execution_data,
+                    &self.config.metric_label_keys,
+                    started_at.elapsed(),
+                );
+                self.fetch_transaction_cost(execution_data, signature).await
+            }
+            Err(err) => {
+                self.notify(|observer| observer.on_failed(execution_data, err));
+                metrics::transaction_failed(execution_data, &self.config.metric_label_keys, err)
+            }
+        }
+        result
+    }
+
+    async fn fetch_transaction_cost(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        signature: &Signature,
+    ) {
+        if !self.config.fetch_transaction_costs {
+            return;
+        }
+        match fetch_transaction_cost(&execution_data.rpc_client, signature).await {
+            Ok(cost) => {
+                // the fetched fee replaces the estimated one in the spent lamports
+                if let Some(spend) = self.transaction_spends.lock().unwrap().get(signature) {
+                    let estimated_fee_lamports = spend
+                        .base_fee_lamports
+                        .saturating_add(spend.priority_fee_lamports);
+                    if cost.fee_lamports >= estimated_fee_lamports {
+                        self.spent_lamports.fetch_add(
+                            cost.fee_lamports - estimated_fee_lamports,
+                            Ordering::Relaxed,
+                        );
+                    } else {
+                        self.spent_lamports.fetch_sub(
+                            estimated_fee_lamports - cost.fee_lamports,
+                            Ordering::Relaxed,
+                        );
+                    }
+                }
+                self.transaction_costs
+                    .lock()
+                    .unwrap()
+                    .insert(*signature, cost);
+            }
+            Err(err) => debug!(
+                "Failed to fetch the cost of the transaction {} ({signature}): {err:?}",
+                execution_data.tx_uuid
+            ),
+        }
+    }
+
+    // The reason of the interruption when the grace period after the cancellation passed
+    async fn within_shutdown_grace_period(
+        &self,
+        execution: impl Future<Output = anyhow::Result<Signature>>,
+    ) -> Result<anyhow::Result<Signature>, String> {
+        let (Some(cancellation_token), Some(grace_period)) = (
+            &self.config.cancellation_token,
+            self.config.shutdown_grace_period,
+        ) else {
+            return Ok(execution.await);
+        };
+        tokio::select! {
+            result = execution => Ok(result),
+            () = async {
+                cancellation_token.cancelled().await;
+                tokio::time::sleep(grace_period).await;
+            } => Err(format!("Shutdown grace period of {grace_period:?} exceeded")),
+        }
+    }
+
+    // The interrupted execution dropped the in-flight transaction, it fails only when nothing
+    // was sent, a sent one that has not landed yet is reported with an unknown outcome
+    async fn settle_interrupted(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        sent_signatures: &Mutex<Vec<Signature>>,
+        reason: String,
+    ) -> anyhow::Result<Signature> {
+        let signatures = sent_signatures.lock().unwrap().clone();
+        if signatures.is_empty() {
+            return Err(anyhow!("{reason}, the transaction was not sent"));
+        }
+        match landed_signature(execution_data, &signatures).await {
+            Some(signature) => {
+                debug!(
+                    "Transaction {} landed with signature {signature} before: {reason}",
+                    execution_data.tx_uuid
+                );
+                Ok(signature)
+            }
+            None => Err(anyhow::Error::new(OutcomeUnknown { signatures }).context(reason)),
+        }
+    }
+
+    // State of the accounts the post-assertions compare with, before the transaction is sent
+    async fn fetch_previous_accounts(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> Option<Vec<Option<Account>>> {
+        let post_assertions = &execution_data.prepared_transaction.post_assertions;
+        if !post_assertions
+            .iter()
+            .any(AccountAssertion::needs_previous_state)
+        {
+            return None;
+        }
+        fetch_assertion_accounts(&execution_data.rpc_client, post_assertions)
+            .await
+            .map_err(|err| {
+                debug!(
+                    "Failed to fetch the accounts of the post-assertions of the transaction {}: {err:?}",
+                    execution_data.tx_uuid
+                )
+            })
+            .ok()
+    }
+
+    async fn verify_pre_assertions(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> anyhow::Result<()> {
+        let pre_assertions = &execution_data.prepared_transaction.pre_assertions;
+        if pre_assertions.is_empty() {
+            return Ok(());
+        }
+        let accounts = fetch_assertion_accounts(&execution_data.rpc_client, pre_assertions).await?;
+        check_assertions(pre_assertions, None, &accounts)?;
+        Ok(())
+    }
+
+    // A confirmed transaction not matching its post-assertions is considered failed
+    async fn verify_post_assertions(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        previous_accounts: Option<&[Option<Account>]>,
+    ) -> anyhow::Result<()> {
+        let post_assertions = &execution_data.prepared_transaction.post_assertions;
+        if post_assertions.is_empty() {
+            return Ok(());
+        }
+        let current_accounts =
+            fetch_assertion_accounts(&execution_data.rpc_client, post_assertions).await?;
+        check_assertions(post_assertions, previous_accounts, &current_accounts)?;
+        Ok(())
+    }
+
+    async fn execute_with_retries(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        sent_signatures: &Mutex<Vec<Signature>>,
+    ) -> anyhow::Result<Signature> {
+        let retry_policy = &self.config.retry_policy;
+        let mut attempt = 1;
+        loop {
+            Span::current().record("attempt", attempt);
+            match self.execute_attempt(execution_data, sent_signatures).await {
+                Err(err)
+                    if retry_policy.should_retry(attempt, &err) && self.skip_reason().is_none() =>
+                {
+                    let delay = retry_policy.delay(attempt);
+                    debug!(
+                        "Transaction {} attempt {attempt} failed, retrying in {delay:?}: {err:?}",
+                        execution_data.tx_uuid
+                    );
+                    self.notify(|observer| observer.on_retry(execution_data, attempt, &err, delay));
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // When the blockhash of all the sent transactions expires before the transaction executor
+    // finishes, the transaction is re-signed with a fresh blockhash continuing
+    // with the next priority fee configuration, up to `ExecutionConfig::max_blockhash_resigns` times.
+    async fn execute_attempt(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        sent_signatures: &Mutex<Vec<Signature>>,
+    ) -> anyhow::Result<Signature> {
+        self.verify_pre_assertions(execution_data)
+            .await
+            .map_err(|err| {
+                err.context(format!(
+                    "Pre-assertions of the transaction {} failed, not sent",
+                    execution_data.tx_uuid
+                ))
+            })?;
+        let priority_fee_policy = execution_data.effective_priority_fee_policy(self.config);
+        let configurations_count = priority_fee_policy
+            .iter_priority_fee_configuration()
+            .count();
+        let max_resigns = self
+            .config
+            .max_blockhash_resigns
+            .unwrap_or(DEFAULT_MAX_BLOCKHASH_RESIGNS);
+        let mut resigns = 0;
+        let mut used_configurations = 0;
+        let sent_transactions: Mutex<Vec<SentTransaction>> = Mutex::new(Vec::new());
+        let result = loop {
+            let skipped_configurations = used_configurations;
+            let yielded_configurations = AtomicUsize::new(0);
+            let execution = self.submit(execution_data, stream! {
+                for priority_fee_configuration in priority_fee_policy.iter_priority_fee_configuration().skip(skipped_configurations) {
+                    if let Some(rate_limiter) = &self.config.rate_limiter {
+                        rate_limiter.acquire().await;
+                    }
+                    if let Some(leader_timing) = &self.config.leader_timing {
+                        leader_timing.wait_for_preferred_leader().await;
+                    }
+                    if let Some(slot_pacer) = &self.config.slot_pacer {
+                        slot_pacer.acquire().await;
+                    }
+                    yielded_configurations.fetch_add(1, Ordering::Relaxed);
+                    let adjustments = self.adjustments(execution_data).await;
+                    let transaction = match execution_data.build(&priority_fee_configuration, &adjustments).await {
+                        Ok((transaction, last_valid_block_height)) => {
+                            metrics::transaction_built(execution_data, &self.config.metric_label_keys);
+                            sent_signatures.lock().unwrap().push(transaction.signatures[0]);
+                            sent_transactions.lock().unwrap().push(SentTransaction {
+                                signature: transaction.signatures[0],
+                                last_valid_block_height,
+                                estimated_spend: adjustments
+                                    .estimate_spend(&execution_data.prepared_transaction),
+                                compute_unit_price: adjustments
+                                    .compute_unit_price(&execution_data.prepared_transaction),
+                            });
+                            self.send(execution_data, transaction, &priority_fee_configuration).await
+                        }
+                        Err(err) => Err(err),
+                    };
+                    yield transaction;
+                }
+            });
+            let result = tokio::select! {
+                result = execution => result,
+                () = wait_for_blockhash_expiry(execution_data, &sent_transactions) => {
+                    if let Some(signature) = landed_signature(execution_data, &signatures(&sent_transactions)).await {
+                        break Ok(signature);
+                    }
+                    if resigns >= max_resigns {
+                        break Err(anyhow!(
+                            "Blockhash of the transaction {} expired {} times, the transaction did not land",
+                            execution_data.tx_uuid,
+                            resigns + 1
+                        ));
+                    }
+                    resigns += 1;
+                    used_configurations = (skipped_configurations
+                        + yielded_configurations.load(Ordering::Relaxed))
+                    .min(configurations_count.saturating_sub(1));
+                    debug!(
+                        "Blockhash of the transaction {} expired, re-signing with a fresh blockhash",
+                        execution_data.tx_uuid
+                    );
+                    continue;
+                }
+            };
+            break match result {
+                Err(err) if is_already_processed_error(&err) => {
+                    match landed_signature(execution_data, &signatures(&sent_transactions)).await {
+                        Some(signature) => {
+                            debug!(
+                                "Transaction {} was already processed, landed with signature {signature}",
+                                execution_data.tx_uuid
+                            );
+                            Ok(signature)
+                        }
+                        None => Err(err),
+                    }
+                }
+                Err(err) if is_unconfirmed_error(&err) => {
+                    self.settle_unconfirmed(execution_data, &sent_transactions, err)
+                        .await
+                }
+                result => result,
+            };
+        };
+        let landed = match &result {
+            Ok(signature) => Some(*signature),
+            // the fees of a transaction failed on-chain are charged as well
+            Err(err) if is_transaction_error(err) => {
+                landed_failed_signature(execution_data, &signatures(&sent_transactions)).await
+            }
+            Err(_) => None,
+        };
+        if let Some(signature) = landed {
+            let (spend, compute_unit_price) = sent_transactions
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|sent| sent.signature == signature)
+                .map_or((TransactionSpend::default(), None), |sent| {
+                    (sent.estimated_spend, sent.compute_unit_price)
+                });
+            if result.is_ok() {
+                metrics::transaction_landed(
+                    execution_data,
+                    &self.config.metric_label_keys,
+                    compute_unit_price,
+                );
+            }
+            self.spent_lamports
+                .fetch_add(spend.total_lamports(), Ordering::Relaxed);
+            self.transaction_spends
+                .lock()
+                .unwrap()
+                .insert(signature, spend);
+        }
+        result
+    }
+
+    // A not confirmed transaction may still land, it is reported as expired (and so retryable)
+    // only once the blockhash of all its sent transactions expired and none of them landed
+    async fn settle_unconfirmed(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        sent_transactions: &Mutex<Vec<SentTransaction>>,
+        err: anyhow::Error,
+    ) -> anyhow::Result<Signature> {
+        let expiry_known = {
+            let sent_transactions = sent_transactions.lock().unwrap();
+            !sent_transactions.is_empty()
+                && sent_transactions
+                    .iter()
+                    .all(|sent| sent.last_valid_block_height.is_some())
+        };
+        if !expiry_known {
+            return Err(err);
+        }
+        wait_for_blockhash_expiry(execution_data, sent_transactions).await;
+        match landed_signature(execution_data, &signatures(sent_transactions)).await {
+            Some(signature) => Ok(signature),
+            None => Err(err.context("Blockhash expired, the transaction did not land")),
+        }
+    }
+
+    // The transaction executor sends and confirms the transactions, with a send config
+    // they are sent only once by `send` and confirmed here, the executor is not involved
+    async fn submit(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        transactions: impl Stream<Item = anyhow::Result<VersionedTransaction>>,
+    ) -> anyhow::Result<Signature> {
+        if self.send_transaction_config(execution_data).is_some() {
+            confirm_sent_transactions(execution_data, transactions).await
+        } else {
+            self.transaction_executor
+                .execute_transaction(transactions)
+                .await
+        }
+    }
+
+    fn send_transaction_config(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> Option<RpcSendTransactionConfig> {
+        execution_data
+            .send_transaction_config
+            .or(self.config.send_transaction_config)
+    }
+
+    async fn adjustments(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> TransactionAdjustments {
+        if execution_data.signed_transaction.is_some() {
+            return TransactionAdjustments::default();
+        }
+        let mut adjustments = TransactionAdjustments {
+            compute_unit_price: self.estimate_compute_unit_price(execution_data).await,
+            nonce_advance: execution_data
+                .durable_nonce
+                .map(|durable_nonce| durable_nonce.advance_instruction()),
+            fee_payer: match &self.config.fee_payer_pool {
+                Some(fee_payer_pool) => Some(fee_payer_pool.next_fee_payer().await),
+                None => None,
+            },
+            ..Default::default()
+        };
+        let fee_payer = adjustments.fee_payer.as_ref().map_or_else(
+            || {
+                execution_data
+                    .prepared_transaction
+                    .transaction
+                    .message
+                    .account_keys
+                    .first()
+                    .copied()
+            },
+            |fee_payer| Some(fee_payer.pubkey()),
+        );
+        if let (Some(fee_payer), Some(tip_lamports)) =
+            (fee_payer, self.tip_lamports(execution_data).await)
+        {
+            adjustments
+                .appended_instructions
+                .push(JitoTip::tip_instruction(&fee_payer, tip_lamports));
+            adjustments.tip_lamports = tip_lamports;
+        }
+        adjustments.lookup_tables = self.lookup_tables(execution_data, &adjustments).await;
+        adjustments
+    }
+
+    // Lookup tables of the execution data, or of the manager when the transaction
+    // is too big to be sent as a legacy one
+    async fn lookup_tables(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        adjustments: &TransactionAdjustments,
+    ) -> Vec<AddressLookupTableAccount> {
+        if let Some((lookup_table_cache, addresses)) = &execution_data.lookup_tables {
+            return lookup_table_cache
+                .get_lookup_tables(addresses)
+                .await
+                .unwrap_or_else(|err| {
+                    debug!(
+                        "Failed to fetch the lookup tables of the transaction {}, sending as legacy: {err:?}",
+                        execution_data.tx_uuid
+                    );
+                    vec![]
+                });
+        }
+        let Some(lookup_table_manager) = &self.config.lookup_table_manager else {
+            return vec![];
+        };
+        let adjusted = adjustments.apply(&execution_data.prepared_transaction);
+        let legacy_size = bincode::serialized_size(&adjusted.transaction).unwrap_or(0);
+        if legacy_size <= PACKET_DATA_SIZE as u64 {
+            return vec![];
+        }
+        lookup_table_manager
+            .lookup_tables_for(&adjusted)
+            .await
+            .unwrap_or_else(|err| {
+                debug!(
+                    "Failed to get the lookup tables of the transaction {}, sending as legacy: {err:?}",
+                    execution_data.tx_uuid
+                );
+                vec![]
+            })
+    }
+
+    async fn tip_lamports(&self, execution_data: &TransactionBuilderExecutionData) -> Option<u64> {
+        let jito_tip = self.config.jito_tip.as_ref()?;
+        match jito_tip.tip_lamports().await {
+            Ok(tip_lamports) => Some(tip_lamports),
+            Err(err) => {
+                debug!(
+                    "Failed to get the Jito tip of the transaction {}, sending without the tip: {err:?}",
+                    execution_data.tx_uuid
+                );
+                None
+            }
+        }
+    }
+
+    async fn estimate_compute_unit_price(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+    ) -> Option<u64> {
+        let estimator = self.config.priority_fee_estimator.as_ref()?;
+        match estimator
+            .estimate_compute_unit_price(&execution_data.prepared_transaction)
+            .await
+        {
+            Ok(compute_unit_price) => Some(compute_unit_price),
+            Err(err) => {
+                debug!(
+                    "Failed to estimate the priority fee of the transaction {}, keeping its own: {err:?}",
+                    execution_data.tx_uuid
+                );
+                None
+            }
+        }
+    }
+
+    // Hands the built transaction over to the transaction executor,
+    // with a send config it is sent over the RPC here instead. The sent notifications
+    // of the executor path fire before the executor sends the transaction.
+    async fn send(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        transaction: VersionedTransaction,
+        priority_fee_configuration: &PriorityFeeConfiguration,
+    ) -> anyhow::Result<VersionedTransaction> {
+        self.notify(|observer| {
+            observer.on_built(execution_data, &transaction, priority_fee_configuration)
+        });
+        if let Some(send_transaction_config) = self.send_transaction_config(execution_data) {
+            execution_data
+                .rpc_client
+                .send_transaction_with_config(&transaction, send_transaction_config)
+                .await?;
+        }
+        metrics::transaction_sent(execution_data, &self.config.metric_label_keys);
+        self.notify(|observer| {
+            observer.on_sent(execution_data, &transaction, priority_fee_configuration)
+        });
+        self.broadcast(execution_data, &transaction).await;
+        Ok(transaction)
+    }
+
+    async fn broadcast(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        transaction: &VersionedTransaction,
+    ) {
+        let results = futures::future::join_all(
+            self.config
+                .broadcasters
+                .iter()
+                .map(|broadcaster| broadcaster.broadcast(transaction)),
+        )
+        .await;
+        for err in results.into_iter().filter_map(Result::err) {
+            debug!(
+                "Transaction {} broadcast failed: {err:?}",
+                execution_data.tx_uuid
+            );
+        }
+    }
+
+    fn notify(&self, notification: impl Fn(&dyn ExecutionObserver)) {
+        for observer in &self.config.observers {
+            notification(observer.as_ref());
+        }
+    }
+}
+
+// First of the signatures that landed successfully
+async fn landed_signature(
+    execution_data: &TransactionBuilderExecutionData,
+    signatures: &[Signature],
+) -> Option<Signature> {
+    find_landed_signature(execution_data, signatures, |status| status.err.is_none()).await
+}
+
+// The signature that landed with an error, its fees were charged
+async fn landed_failed_signature(
+    execution_data: &TransactionBuilderExecutionData,
+    signatures: &[Signature],
+) -> Option<Signature> {
+    find_landed_signature(execution_data, signatures, |status| status.err.is_some()).await
+}
+
+async fn find_landed_signature(
+    execution_data: &TransactionBuilderExecutionData,
+    signatures: &[Signature],
+    filter: impl Fn(&TransactionStatus) -> bool,
+) -> Option<Signature> {
+    if signatures.is_empty() {
+        return None;
+    }
+    let rpc_client = &execution_data.rpc_client;
+    let statuses = rpc_client
+        .get_signature_statuses(signatures)
+        .await
+        .map_err(|err| {
+            debug!(
+                "Failed to fetch the signature statuses of the transaction {}: {err:?}",
+                execution_data.tx_uuid
+            )
+        })
+        .ok()?
+        .value;
+    signatures
+        .iter()
+        .zip(statuses)
+        .find_map(|(signature, status)| {
+            status
+                .filter(|status| {
+                    filter(status) && status.satisfies_commitment(rpc_client.commitment())
+                })
+                .map(|_| *signature)
+        })
+}
+
+// Awaits the confirmation of the transactions sent with the send config, the next one
+// (i.e. the next priority fee configuration) is taken when none landed within the escalation
+// interval. Never completes when none lands, the blockhash expiry re-signs the transaction.
+async fn confirm_sent_transactions(
+    execution_data: &TransactionBuilderExecutionData,
+    transactions: impl Stream<Item = anyhow::Result<VersionedTransaction>>,
+) -> anyhow::Result<Signature> {
+    let mut transactions = pin!(transactions);
+    let mut signatures = Vec::new();
+    let mut exhausted = false;
+    loop {
+        if !exhausted {
+            match transactions.next().await {
+                Some(transaction) => signatures.push(transaction?.signatures[0]),
+                None => exhausted = true,
+            }
+        }
+        if signatures.is_empty() {
+            return Err(anyhow!(
+                "No transaction {} was built to be sent",
+                execution_data.tx_uuid
+            ));
+        }
+        let escalate_at = Instant::now() + SEND_CONFIG_ESCALATION_INTERVAL;
+        while exhausted || Instant::now() < escalate_at {
+            tokio::time::sleep(SIGNATURE_STATUS_CHECK_INTERVAL).await;
+            if let Some(result) = signature_outcome(execution_data, &signatures).await {
+                return result;
+            }
+        }
+    }
+}
+
+// Result of the first of the signatures that landed, None when none did yet
+async fn signature_outcome(
+    execution_data: &TransactionBuilderExecutionData,
+    signatures: &[Signature],
+) -> Option<anyhow::Result<Signature>> {
+    let rpc_client = &execution_data.rpc_client;
+    let statuses = rpc_client
+        .get_signature_statuses(signatures)
+        .await
+        .map_err(|err| {
+            debug!(
+                "Failed to fetch the signature statuses of the transaction {}: {err:?}",
+                execution_data.tx_uuid
+            )
+        })
+        .ok()?
+        .value;
+    signatures
+        .iter()
+        .zip(statuses)
+        .find_map(|(signature, status)| {
+            let status =
+                status.filter(|status| status.satisfies_commitment(rpc_client.commitment()))?;
+            Some(match status.err {
+                None => Ok(*signature),
+                Some(err) => Err(anyhow::Error::from(ClientError::from(err))
+                    .context(format!("Transaction {signature} failed"))),
+            })
+        })
+}
+
+fn signatures(sent_transactions: &Mutex<Vec<SentTransaction>>) -> Vec<Signature> {
+    sent_transactions
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|sent| sent.signature)
+        .collect()
+}
+
+// Completes when the blockhash of every sent transaction has expired,
+// never completes for transactions with an unknown blockhash expiry (pre-signed)
+async fn wait_for_blockhash_expiry(
+    execution_data: &TransactionBuilderExecutionData,
+    sent_transactions: &Mutex<Vec<SentTransaction>>,
+) {
+    loop {
+        tokio::time::sleep(BLOCKHASH_EXPIRY_CHECK_INTERVAL).await;
+        let last_valid_block_height = sent_transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|sent| sent.last_valid_block_height)
+            .max()
+            .flatten();
+        let Some(last_valid_block_height) = last_valid_block_height else {
+            continue;
+        };
+        match execution_data.rpc_client.get_block_height().await {
+            Ok(block_height) if block_height > last_valid_block_height => return,
+            Ok(_) => {}
+            Err(err) => debug!(
+                "Failed to fetch the block height for the transaction {}: {err:?}",
+                execution_data.tx_uuid
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockhashCache;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+    use solana_transaction_builder::TransactionBuilder;
+    use solana_transaction_executor::{PriorityFeePolicy, TransactionExecutorBuilder};
+    use tokio_util::sync::CancellationToken;
+
+    fn transaction_executor(rpc_client: Arc<RpcClient>) -> Arc<TransactionExecutor> {
+        Arc::new(
+            TransactionExecutorBuilder::new()
+                .with_default_providers(rpc_client)
+                .build(),
+        )
+    }
+
+    fn execution_data(rpc_client: Arc<RpcClient>) -> TransactionBuilderExecutionData {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        // own cache, the shared one is shared by the tests with the same mock url
+        let blockhash_cache = Arc::new(BlockhashCache::new(rpc_client.clone()));
+        TransactionBuilderExecutionData::new_with_blockhash_cache(
+            builder.build_one(),
+            rpc_client,
+            blockhash_cache,
+            PriorityFeePolicy::default(),
+        )
+    }
+
+    // sent over the RPC and confirmed by the run, the transaction executor is not involved
+    fn send_transaction_config() -> Option<RpcSendTransactionConfig> {
+        Some(RpcSendTransactionConfig {
+            skip_preflight: true,
+            ..Default::default()
+        })
+    }
+
+    fn latest_blockhash_response(last_valid_block_height: u64) -> serde_json::Value {
+        json!({
+            "context": { "slot": 1 },
+            "value": {
+                "blockhash": Hash::new_unique().to_string(),
+                "lastValidBlockHeight": last_valid_block_height,
+            },
+        })
+    }
+
+    fn sent_transaction(last_valid_block_height: Option<u64>) -> SentTransaction {
+        SentTransaction {
+            signature: Signature::new_unique(),
+            last_valid_block_height,
+            estimated_spend: TransactionSpend::default(),
+            compute_unit_price: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resign_on_blockhash_expiry() {
+        let mut mocks = Mocks::new();
+        // the block height of the mock (1234) is past the last valid one
+        mocks.insert(
+            RpcRequest::GetLatestBlockhash,
+            latest_blockhash_response(100),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks(
+            "sig_not_found".to_string(),
+            mocks,
+        ));
+        let config = ExecutionConfig {
+            send_transaction_config: send_transaction_config(),
+            max_blockhash_resigns: Some(1),
+            ..Default::default()
+        };
+        let run = ExecutionRun::new(transaction_executor(rpc_client.clone()), &config);
+        let execution_data = execution_data(rpc_client);
+        let sent_signatures = Mutex::new(Vec::new());
+
+        let err = run
+            .execute_attempt(&execution_data, &sent_signatures)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expired 2 times"), "{err:?}");
+        // sent and re-signed once, nothing landed and so nothing is spent
+        assert_eq!(sent_signatures.lock().unwrap().len(), 2);
+        assert_eq!(run.spent_lamports.load(Ordering::Relaxed), 0);
+        assert!(run.transaction_spends.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interrupted_after_sent_outcome_unknown() {
+        let rpc_client = Arc::new(RpcClient::new_mock("sig_not_found".to_string()));
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let config = ExecutionConfig {
+            send_transaction_config: send_transaction_config(),
+            cancellation_token: Some(cancellation_token),
+            shutdown_grace_period: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let run = ExecutionRun::new(transaction_executor(rpc_client.clone()), &config);
+
+        let err = run.execute(&execution_data(rpc_client)).await.unwrap_err();
+        assert!(err.to_string().contains("Shutdown grace period"), "{err:?}");
+        let outcome_unknown = err.downcast_ref::<OutcomeUnknown>().unwrap();
+        assert_eq!(outcome_unknown.signatures.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_grace_period() {
+        let rpc_client = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        let cancellation_token = CancellationToken::new();
+        let config = ExecutionConfig {
+            cancellation_token: Some(cancellation_token.clone()),
+            shutdown_grace_period: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let run = ExecutionRun::new(transaction_executor(rpc_client), &config);
+        let signature = Signature::new_unique();
+        let execution = |duration| async move {
+            tokio::time::sleep(duration).await;
+            anyhow::Ok(signature)
+        };
+
+        // not cancelled, the execution is awaited whatever long
+        let result = run
+            .within_shutdown_grace_period(execution(Duration::from_secs(60)))
+            .await;
+        assert_eq!(result.unwrap().unwrap(), signature);
+
+        cancellation_token.cancel();
+        let result = run
+            .within_shutdown_grace_period(execution(Duration::from_secs(5)))
+            .await;
+        assert_eq!(result.unwrap().unwrap(), signature);
+        let started_at = Instant::now();
+        let reason = run
+            .within_shutdown_grace_period(execution(Duration::from_secs(60)))
+            .await
+            .unwrap_err();
+        assert!(started_at.elapsed() >= Duration::from_secs(10));
+        assert!(started_at.elapsed() < Duration::from_secs(60));
+        assert_eq!(reason, "Shutdown grace period of 10s exceeded");
+
+        // without the grace period the cancelled execution is awaited
+        let config = ExecutionConfig {
+            cancellation_token: Some(cancellation_token),
+            ..Default::default()
+        };
+        let run = ExecutionRun::new(run.transaction_executor.clone(), &config);
+        let result = run
+            .within_shutdown_grace_period(execution(Duration::from_secs(60)))
+            .await;
+        assert_eq!(result.unwrap().unwrap(), signature);
+    }
+
+    #[tokio::test]
+    async fn test_settle_interrupted() {
+        let rpc_client = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        let config = ExecutionConfig::default();
+        let run = ExecutionRun::new(transaction_executor(rpc_client.clone()), &config);
+        let execution_data = execution_data(rpc_client);
+
+        let err = run
+            .settle_interrupted(
+                &execution_data,
+                &Mutex::new(vec![]),
+                "Interrupted".to_string(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Interrupted, the transaction was not sent");
+        assert!(err.downcast_ref::<OutcomeUnknown>().is_none());
+
+        // landed before the interruption
+        let signature = Signature::new_unique();
+        assert_eq!(
+            run.settle_interrupted(
+                &execution_data,
+                &Mutex::new(vec![signature]),
+                "Interrupted".to_string()
+            )
+            .await
+            .unwrap(),
+            signature
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_settle_unconfirmed() {
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetBlockHeight, json!(101));
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks(
+            "sig_not_found".to_string(),
+            mocks,
+        ));
+        let config = ExecutionConfig::default();
+        let run = ExecutionRun::new(transaction_executor(rpc_client.clone()), &config);
+        let execution_data = execution_data(rpc_client);
+
+        // the expiry of a pre-signed transaction is unknown, the error is kept
+        let err = run
+            .settle_unconfirmed(
+                &execution_data,
+                &Mutex::new(vec![sent_transaction(None)]),
+                anyhow!("Unconfirmed"),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unconfirmed");
+
+        let err = run
+            .settle_unconfirmed(
+                &execution_data,
+                &Mutex::new(vec![sent_transaction(Some(100))]),
+                anyhow!("Unconfirmed"),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Blockhash expired, the transaction did not land"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spend_corrected_by_fetched_fee() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetTransaction,
+            json!({
+                "slot": 1,
+                "blockTime": null,
+                "transaction": ["", "base64"],
+                "meta": {
+                    "err": null,
+                    "status": { "Ok": null },
+                    "fee": 3_000,
+                    "preBalances": [],
+                    "postBalances": [],
+                    "computeUnitsConsumed": 150,
+                },
+            }),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        let config = ExecutionConfig {
+            send_transaction_config: send_transaction_config(),
+            fetch_transaction_costs: true,
+            ..Default::default()
+        };
+        let run = ExecutionRun::new(transaction_executor(rpc_client.clone()), &config);
+
+        let signature = run.execute(&execution_data(rpc_client)).await.unwrap();
+        let spend = run.transaction_spends.lock().unwrap()[&signature];
+        assert_eq!(spend.base_fee_lamports, 5_000);
+        // the estimated fee is replaced by the lower fetched one
+        let estimated_fee_lamports = spend.base_fee_lamports + spend.priority_fee_lamports;
+        assert_eq!(
+            run.spent_lamports.load(Ordering::Relaxed),
+            spend.total_lamports() - (estimated_fee_lamports - 3_000)
+        );
+        assert_eq!(
+            run.transaction_costs.lock().unwrap()[&signature],
+            TransactionCost {
+                compute_units_consumed: Some(150),
+                fee_lamports: 3_000,
+            }
+        );
+    }
+}
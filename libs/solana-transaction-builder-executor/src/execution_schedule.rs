@@ -0,0 +1,115 @@
+use crate::TransactionBuilderExecutionData;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+
+/// Transactions (by their index in the execution data) split into waves executed one after another.
+/// Transactions of a wave do not write to an account used by another transaction of the same wave,
+/// conflicting transactions keep their original order. The fee payer is not considered as a conflict.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionSchedule {
+    pub waves: Vec<Vec<usize>>,
+}
+
+impl ExecutionSchedule {
+    pub fn conflict_free_waves(execution_data: &[TransactionBuilderExecutionData]) -> Self {
+        Self::conflict_free_waves_of_transactions(
+            execution_data
+                .iter()
+                .map(|data| &data.prepared_transaction.transaction),
+        )
+    }
+
+    pub fn conflict_free_waves_of_transactions<'a, I>(transactions: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Transaction>,
+    {
+        // the latest wave an account was written or read in
+        let mut written_in: HashMap<Pubkey, usize> = HashMap::new();
+        let mut read_in: HashMap<Pubkey, usize> = HashMap::new();
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+
+        for (index, transaction) in transactions.into_iter().enumerate() {
+            let message = &transaction.message;
+            let accounts: Vec<(Pubkey, bool)> = message
+                .account_keys
+                .iter()
+                .enumerate()
+                .skip(1) // fee payer
+                .map(|(position, key)| (*key, message.is_writable(position)))
+                .collect();
+
+            let wave = accounts
+                .iter()
+                .filter_map(|(key, is_writable)| {
+                    let after_write = written_in.get(key).map(|wave| wave + 1);
+                    let after_read = if *is_writable {
+                        read_in.get(key).map(|wave| wave + 1)
+                    } else {
+                        None
+                    };
+                    after_write.max(after_read)
+                })
+                .max()
+                .unwrap_or(0);
+
+            for (key, is_writable) in accounts {
+                let used_in = if is_writable {
+                    written_in.entry(key).or_default()
+                } else {
+                    read_in.entry(key).or_default()
+                };
+                *used_in = (*used_in).max(wave);
+            }
+            if waves.len() <= wave {
+                waves.resize_with(wave + 1, Vec::new);
+            }
+            waves[wave].push(index);
+        }
+
+        Self { waves }
+    }
+
+    pub fn is_conflict_free(&self) -> bool {
+        self.waves.len() <= 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+
+    #[test]
+    fn test_conflict_free_waves() {
+        let fee_payer = Pubkey::new_unique();
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let transaction = |accounts: Vec<AccountMeta>| {
+            Transaction::new_with_payer(
+                &[Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    accounts,
+                )],
+                Some(&fee_payer),
+            )
+        };
+        let transactions = [
+            transaction(vec![AccountMeta::new(a, false)]),
+            transaction(vec![AccountMeta::new_readonly(a, false)]),
+            transaction(vec![AccountMeta::new(b, false)]),
+            transaction(vec![AccountMeta::new_readonly(c, false)]),
+            transaction(vec![
+                AccountMeta::new(c, false),
+                AccountMeta::new_readonly(b, false),
+            ]),
+            transaction(vec![AccountMeta::new_readonly(a, false)]),
+        ];
+        let schedule = ExecutionSchedule::conflict_free_waves_of_transactions(&transactions);
+        assert_eq!(schedule.waves, vec![vec![0, 2, 3], vec![1, 4, 5]]);
+    }
+}
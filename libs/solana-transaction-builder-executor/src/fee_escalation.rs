@@ -0,0 +1,273 @@
+use crate::fee_estimator::percentile;
+use crate::{ExecutionObserver, PriorityFeeEstimator, TransactionBuilderExecutionData};
+use futures::future::BoxFuture;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_builder::PreparedTransaction;
+use solana_transaction_executor::PriorityFeeConfiguration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Compute unit price escalated by the observed confirmation latency instead of a static ladder.
+/// The price grows only when the transactions confirm slower than the target latency
+/// and the recent fees of the writable accounts (the fee market of the congested blocks)
+/// are above the current price. Every confirmation bringing the average latency within
+/// the target halves the price back toward the base price.
+///
+/// The same instance has to be registered as `ExecutionConfig::priority_fee_estimator`
+/// and in `ExecutionConfig::observers` (to measure the latency), the priority fee policy
+/// of the transactions is expected to have a single configuration then.
+pub struct LatencyFeedbackFeeEscalation {
+    rpc_client: Arc<RpcClient>,
+    target_latency: Duration,
+    base_compute_unit_price: u64,
+    max_compute_unit_price: u64,
+    percentile: u8,
+    state: Mutex<EscalationState>,
+}
+
+#[derive(Default)]
+struct EscalationState {
+    compute_unit_price: u64,
+    // exponential moving average of the confirmation latency
+    average_latency: Option<Duration>,
+    // first send of the transactions waiting for the confirmation
+    pending: HashMap<String, Instant>,
+}
+
+impl LatencyFeedbackFeeEscalation {
+    pub fn new(rpc_client: Arc<RpcClient>, target_latency: Duration) -> Self {
+        Self {
+            rpc_client,
+            target_latency,
+            base_compute_unit_price: 0,
+            max_compute_unit_price: u64::MAX,
+            percentile: 75,
+            state: Mutex::new(EscalationState::default()),
+        }
+    }
+
+    pub fn with_bounds(
+        mut self,
+        base_compute_unit_price: u64,
+        max_compute_unit_price: u64,
+    ) -> Self {
+        self.base_compute_unit_price = base_compute_unit_price;
+        self.max_compute_unit_price = max_compute_unit_price;
+        self.state.get_mut().unwrap().compute_unit_price = base_compute_unit_price;
+        self
+    }
+
+    /// Percentile (0-100) of the recent fees the price may escalate to.
+    pub fn with_percentile(mut self, percentile: u8) -> Self {
+        self.percentile = percentile.min(100);
+        self
+    }
+
+    /// The latency the escalation is driven by, the confirmed transactions are averaged
+    /// and the oldest pending one is accounted when it waits longer than that.
+    pub fn observed_latency(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        let oldest_pending = state.pending.values().map(Instant::elapsed).max();
+        match (state.average_latency, oldest_pending) {
+            (Some(average), Some(pending)) => Some(average.max(pending)),
+            (average, pending) => average.or(pending),
+        }
+    }
+
+    pub fn current_compute_unit_price(&self) -> u64 {
+        self.state.lock().unwrap().compute_unit_price
+    }
+
+    async fn market_compute_unit_price(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+    ) -> anyhow::Result<u64> {
+        let fees = self
+            .rpc_client
+            .get_recent_prioritization_fees(&prepared_transaction.writable_accounts())
+            .await?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+        Ok(percentile(fees, self.percentile))
+    }
+
+    fn record_latency(&self, tx_uuid: &str, confirmed: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(sent_at) = state.pending.remove(tx_uuid) {
+            if confirmed {
+                let latency = sent_at.elapsed();
+                let average_latency = match state.average_latency {
+                    Some(average) => (average * 3 + latency) / 4,
+                    None => latency,
+                };
+                state.average_latency = Some(average_latency);
+                if average_latency <= self.target_latency {
+                    state.compute_unit_price =
+                        (state.compute_unit_price / 2).max(self.base_compute_unit_price);
+                }
+            }
+        }
+    }
+}
+
+impl PriorityFeeEstimator for LatencyFeedbackFeeEscalation {
+    fn estimate_compute_unit_price<'a>(
+        &'a self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<u64>> {
+        Box::pin(async move {
+            let is_slow = self
+                .observed_latency()
+                .map_or(false, |latency| latency > self.target_latency);
+            if !is_slow {
+                return Ok(self.current_compute_unit_price());
+            }
+            let market_price = self.market_compute_unit_price(prepared_transaction).await?;
+            let mut state = self.state.lock().unwrap();
+            if market_price > state.compute_unit_price {
+                let escalated = state
+                    .compute_unit_price
+                    .saturating_mul(2)
+                    .max(1)
+                    .min(market_price)
+                    .min(self.max_compute_unit_price);
+                debug!(
+                    "Confirmations are slow ({:?}), escalating the compute unit price from {} to {escalated}",
+                    state.average_latency, state.compute_unit_price
+                );
+                state.compute_unit_price = escalated;
+            }
+            Ok(state.compute_unit_price)
+        })
+    }
+}
+
+impl ExecutionObserver for LatencyFeedbackFeeEscalation {
+    fn on_sent(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        _transaction: &VersionedTransaction,
+        _priority_fee_configuration: &PriorityFeeConfiguration,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .entry(execution_data.tx_uuid.clone())
+            .or_insert_with(Instant::now);
+    }
+
+    fn on_confirmed(
+        &self,
+        execution_data: &TransactionBuilderExecutionData,
+        _signature: &Signature,
+    ) {
+        self.record_latency(&execution_data.tx_uuid, true);
+    }
+
+    fn on_failed(&self, execution_data: &TransactionBuilderExecutionData, _error: &anyhow::Error) {
+        self.record_latency(&execution_data.tx_uuid, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+    use solana_transaction_builder::TransactionBuilder;
+
+    fn prepared_transaction() -> PreparedTransaction {
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            ))
+            .unwrap();
+        builder.build_one()
+    }
+
+    fn confirm(escalation: &LatencyFeedbackFeeEscalation, tx_uuid: &str, latency: Duration) {
+        escalation
+            .state
+            .lock()
+            .unwrap()
+            .pending
+            .insert(tx_uuid.to_string(), Instant::now() - latency);
+        escalation.record_latency(tx_uuid, true);
+    }
+
+    #[tokio::test]
+    async fn test_decay_per_confirmation() {
+        let rpc_client = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        let escalation = LatencyFeedbackFeeEscalation::new(rpc_client, Duration::from_secs(10))
+            .with_bounds(100, 10_000);
+        escalation.state.lock().unwrap().compute_unit_price = 800;
+        let prepared_transaction = prepared_transaction();
+
+        // the estimates do not decay the price
+        for _ in 0..3 {
+            assert_eq!(
+                escalation
+                    .estimate_compute_unit_price(&prepared_transaction)
+                    .await
+                    .unwrap(),
+                800
+            );
+        }
+        confirm(&escalation, "first", Duration::from_secs(1));
+        assert_eq!(escalation.current_compute_unit_price(), 400);
+        // a slow confirmation keeping the average above the target does not decay
+        confirm(&escalation, "slow", Duration::from_secs(60));
+        assert_eq!(escalation.current_compute_unit_price(), 400);
+        for index in 0..10 {
+            confirm(&escalation, &format!("fast-{index}"), Duration::ZERO);
+        }
+        assert_eq!(escalation.current_compute_unit_price(), 100);
+        // a failure is not a confirmation
+        escalation
+            .state
+            .lock()
+            .unwrap()
+            .pending
+            .insert("failed".to_string(), Instant::now());
+        escalation.record_latency("failed", false);
+        assert!(escalation.state.lock().unwrap().pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_escalation_when_slow() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetRecentPrioritizationFees,
+            json!([{ "slot": 1, "prioritizationFee": 1_000 }]),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        let escalation = LatencyFeedbackFeeEscalation::new(rpc_client, Duration::from_secs(1))
+            .with_bounds(100, 150);
+        confirm(&escalation, "slow", Duration::from_secs(5));
+        assert!(escalation.observed_latency().unwrap() >= Duration::from_secs(5));
+
+        let price = escalation
+            .estimate_compute_unit_price(&prepared_transaction())
+            .await
+            .unwrap();
+        // doubled, capped by the max price
+        assert_eq!(price, 150);
+        assert_eq!(escalation.current_compute_unit_price(), 150);
+    }
+}
@@ -0,0 +1,372 @@
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_transaction_builder::PreparedTransaction;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Source of the compute unit price the transactions pay, registered in
+/// `ExecutionConfig::priority_fee_estimator`. The price is estimated every time the transaction
+/// is built and it replaces the SetComputeUnitPrice instruction of the transaction.
+pub trait PriorityFeeEstimator: Send + Sync {
+    /// Compute unit price in micro-lamports.
+    fn estimate_compute_unit_price<'a>(
+        &'a self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<u64>>;
+}
+
+/// Percentile of the prioritization fees paid in the recent slots (`getRecentPrioritizationFees`),
+/// by default scoped to the writable accounts of the transaction.
+pub struct RecentPrioritizationFeesEstimator {
+    rpc_client: Arc<RpcClient>,
+    percentile: u8,
+    writable_accounts_scope: bool,
+    min_compute_unit_price: u64,
+    max_compute_unit_price: u64,
+}
+
+impl RecentPrioritizationFeesEstimator {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            percentile: 75,
+            writable_accounts_scope: true,
+            min_compute_unit_price: 0,
+            max_compute_unit_price: u64::MAX,
+        }
+    }
+
+    /// Percentile (0-100) of the recent slot fees.
+    pub fn with_percentile(mut self, percentile: u8) -> Self {
+        self.percentile = percentile.min(100);
+        self
+    }
+
+    /// When disabled the fees of the whole cluster are considered.
+    pub fn with_writable_accounts_scope(mut self, writable_accounts_scope: bool) -> Self {
+        self.writable_accounts_scope = writable_accounts_scope;
+        self
+    }
+
+    pub fn with_bounds(mut self, min_compute_unit_price: u64, max_compute_unit_price: u64) -> Self {
+        self.min_compute_unit_price = min_compute_unit_price;
+        self.max_compute_unit_price = max_compute_unit_price;
+        self
+    }
+}
+
+impl PriorityFeeEstimator for RecentPrioritizationFeesEstimator {
+    fn estimate_compute_unit_price<'a>(
+        &'a self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<u64>> {
+        Box::pin(async move {
+            let accounts = if self.writable_accounts_scope {
+                prepared_transaction.writable_accounts()
+            } else {
+                vec![]
+            };
+            let fees = self
+                .rpc_client
+                .get_recent_prioritization_fees(&accounts)
+                .await?
+                .into_iter()
+                .map(|fee| fee.prioritization_fee)
+                .collect();
+            Ok(percentile(fees, self.percentile)
+                .max(self.min_compute_unit_price)
+                .min(self.max_compute_unit_price))
+        })
+    }
+}
+
+/// Helius `getPriorityFeeEstimate` API for the writable accounts of the transaction.
+pub struct HeliusPriorityFeeEstimator {
+    rpc_client: Arc<RpcClient>,
+    priority_level: String,
+    min_compute_unit_price: u64,
+    max_compute_unit_price: u64,
+}
+
+impl HeliusPriorityFeeEstimator {
+    /// The RPC client has to point to a Helius endpoint.
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            priority_level: "High".to_string(),
+            min_compute_unit_price: 0,
+            max_compute_unit_price: u64::MAX,
+        }
+    }
+
+    /// One of Min, Low, Medium, High, VeryHigh, UnsafeMax.
+    pub fn with_priority_level<S: Into<String>>(mut self, priority_level: S) -> Self {
+        self.priority_level = priority_level.into();
+        self
+    }
+
+    pub fn with_bounds(mut self, min_compute_unit_price: u64, max_compute_unit_price: u64) -> Self {
+        self.min_compute_unit_price = min_compute_unit_price;
+        self.max_compute_unit_price = max_compute_unit_price;
+        self
+    }
+}
+
+impl PriorityFeeEstimator for HeliusPriorityFeeEstimator {
+    fn estimate_compute_unit_price<'a>(
+        &'a self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<u64>> {
+        Box::pin(async move {
+            let account_keys: Vec<String> = prepared_transaction
+                .writable_accounts()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            let response: Value = self
+                .rpc_client
+                .send(
+                    RpcRequest::Custom {
+                        method: "getPriorityFeeEstimate",
+                    },
+                    json!([{
+                        "accountKeys": account_keys,
+                        "options": { "priorityLevel": self.priority_level },
+                    }]),
+                )
+                .await?;
+            let estimate = response["priorityFeeEstimate"].as_f64().ok_or_else(|| {
+                anyhow::anyhow!("Unexpected getPriorityFeeEstimate response: {response}")
+            })?;
+            Ok((estimate.ceil() as u64)
+                .max(self.min_compute_unit_price)
+                .min(self.max_compute_unit_price))
+        })
+    }
+}
+
+pub(crate) fn percentile(mut values: Vec<u64>, percentile: u8) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    values[(values.len() - 1) * usize::from(percentile.min(100)) / 100]
+}
+
+/// Prices the transaction by the local fee market of the writable accounts it touches:
+/// every account is looked up separately and the hottest one sets the price,
+/// a transaction touching idle accounts pays the `cold_compute_unit_price` only.
+pub struct LocalFeeMarketEstimator {
+    rpc_client: Arc<RpcClient>,
+    percentile: u8,
+    cold_compute_unit_price: u64,
+    max_compute_unit_price: u64,
+}
+
+impl LocalFeeMarketEstimator {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            percentile: 75,
+            cold_compute_unit_price: 0,
+            max_compute_unit_price: u64::MAX,
+        }
+    }
+
+    /// Percentile (0-100) of the recent slot fees of an account.
+    pub fn with_percentile(mut self, percentile: u8) -> Self {
+        self.percentile = percentile.min(100);
+        self
+    }
+
+    /// Minimal price, paid when none of the accounts is contended.
+    pub fn with_cold_compute_unit_price(mut self, cold_compute_unit_price: u64) -> Self {
+        self.cold_compute_unit_price = cold_compute_unit_price;
+        self
+    }
+
+    pub fn with_max_compute_unit_price(mut self, max_compute_unit_price: u64) -> Self {
+        self.max_compute_unit_price = max_compute_unit_price;
+        self
+    }
+}
+
+impl PriorityFeeEstimator for LocalFeeMarketEstimator {
+    fn estimate_compute_unit_price<'a>(
+        &'a self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<u64>> {
+        Box::pin(async move {
+            let account_fees = futures::future::try_join_all(
+                prepared_transaction
+                    .writable_accounts()
+                    .into_iter()
+                    .map(|account| async move {
+                        let fees = self
+                            .rpc_client
+                            .get_recent_prioritization_fees(&[account])
+                            .await?
+                            .into_iter()
+                            .map(|fee| fee.prioritization_fee)
+                            .collect();
+                        let account_fee = percentile(fees, self.percentile);
+                        debug!("Local fee market of the account {account}: {account_fee}");
+                        Ok::<_, anyhow::Error>(account_fee)
+                    }),
+            )
+            .await?;
+            Ok(account_fees
+                .into_iter()
+                .max()
+                .unwrap_or(0)
+                .max(self.cold_compute_unit_price)
+                .min(self.max_compute_unit_price))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::mock_sender::Mocks;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+    use solana_transaction_builder::TransactionBuilder;
+
+    fn prepared_transaction() -> PreparedTransaction {
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[0],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            ))
+            .unwrap();
+        builder.build_one()
+    }
+
+    // every mocked response is returned once only
+    fn rpc_client_with_fees(fees: &[u64]) -> Arc<RpcClient> {
+        let fees: Vec<Value> = fees
+            .iter()
+            .enumerate()
+            .map(|(slot, fee)| json!({ "slot": slot, "prioritizationFee": fee }))
+            .collect();
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetRecentPrioritizationFees, json!(fees));
+        Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ))
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(vec![], 75), 0);
+        assert_eq!(percentile(vec![500, 100, 300, 200, 400], 0), 100);
+        assert_eq!(percentile(vec![500, 100, 300, 200, 400], 50), 300);
+        assert_eq!(percentile(vec![500, 100, 300, 200, 400], 75), 400);
+        assert_eq!(percentile(vec![500, 100, 300, 200, 400], 100), 500);
+        assert_eq!(percentile(vec![500, 100, 300, 200, 400], 200), 500);
+    }
+
+    #[tokio::test]
+    async fn test_recent_prioritization_fees() {
+        let prepared_transaction = prepared_transaction();
+        let fees = [500, 100, 300, 200, 400];
+
+        let estimator =
+            RecentPrioritizationFeesEstimator::new(rpc_client_with_fees(&fees)).with_percentile(50);
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            300
+        );
+
+        let estimator = RecentPrioritizationFeesEstimator::new(rpc_client_with_fees(&fees))
+            .with_percentile(50)
+            .with_bounds(350, 1_000);
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            350
+        );
+
+        let estimator = RecentPrioritizationFeesEstimator::new(rpc_client_with_fees(&fees))
+            .with_percentile(100)
+            .with_bounds(0, 450);
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            450
+        );
+
+        // no recent fees
+        let estimator = RecentPrioritizationFeesEstimator::new(rpc_client_with_fees(&[]))
+            .with_bounds(10, 1_000);
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            10
+        );
+    }
+
+    #[tokio::test]
+    async fn test_helius_priority_fee_estimate() {
+        let helius_rpc_client = |response: Value| {
+            let mut mocks = Mocks::new();
+            mocks.insert(
+                RpcRequest::Custom {
+                    method: "getPriorityFeeEstimate",
+                },
+                response,
+            );
+            Arc::new(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            ))
+        };
+        let prepared_transaction = prepared_transaction();
+
+        let estimator = HeliusPriorityFeeEstimator::new(helius_rpc_client(
+            json!({ "priorityFeeEstimate": 1234.5 }),
+        ));
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            1_235
+        );
+
+        let estimator = HeliusPriorityFeeEstimator::new(helius_rpc_client(
+            json!({ "priorityFeeEstimate": 1234.5 }),
+        ))
+        .with_bounds(0, 1_000);
+        assert_eq!(
+            estimator
+                .estimate_compute_unit_price(&prepared_transaction)
+                .await
+                .unwrap(),
+            1_000
+        );
+
+        let estimator =
+            HeliusPriorityFeeEstimator::new(helius_rpc_client(json!({ "error": "unknown" })));
+        assert!(estimator
+            .estimate_compute_unit_price(&prepared_transaction)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::state_assertions::MAX_MULTIPLE_ACCOUNTS;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use rand::Rng;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+pub const DEFAULT_BALANCE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Picks the fee payer of the next transaction out of the fee payers of the pool.
+pub trait FeePayerStrategy: Send + Sync {
+    /// Index of the fee payer within the (non-empty) list.
+    fn select<'a>(&'a self, fee_payers: &'a [Arc<Keypair>]) -> BoxFuture<'a, usize>;
+}
+
+/// Always the same fee payer, the first one of the pool when it is not in the pool.
+pub struct FixedFeePayer(pub Pubkey);
+
+impl FeePayerStrategy for FixedFeePayer {
+    fn select<'a>(&'a self, fee_payers: &'a [Arc<Keypair>]) -> BoxFuture<'a, usize> {
+        let index = fee_payers
+            .iter()
+            .position(|keypair| keypair.pubkey() == self.0)
+            .unwrap_or(0);
+        futures::future::ready(index).boxed()
+    }
+}
+
+#[derive(Default)]
+pub struct RoundRobinFeePayer {
+    next_index: AtomicUsize,
+}
+
+impl FeePayerStrategy for RoundRobinFeePayer {
+    fn select<'a>(&'a self, fee_payers: &'a [Arc<Keypair>]) -> BoxFuture<'a, usize> {
+        let index = self.next_index.fetch_add(1, Ordering::Relaxed) % fee_payers.len();
+        futures::future::ready(index).boxed()
+    }
+}
+
+/// The fee payer used the longest time ago, the uses outside of the executor
+/// are recorded with `LeastRecentlyUsedFeePayer::mark_used`.
+#[derive(Default)]
+pub struct LeastRecentlyUsedFeePayer {
+    last_used: Mutex<HashMap<Pubkey, Instant>>,
+}
+
+impl LeastRecentlyUsedFeePayer {
+    pub fn mark_used(&self, fee_payer: &Pubkey) {
+        self.last_used
+            .lock()
+            .expect("Fee payer strategy lock poisoned")
+            .insert(*fee_payer, Instant::now());
+    }
+}
+
+impl FeePayerStrategy for LeastRecentlyUsedFeePayer {
+    fn select<'a>(&'a self, fee_payers: &'a [Arc<Keypair>]) -> BoxFuture<'a, usize> {
+        let mut last_used = self
+            .last_used
+            .lock()
+            .expect("Fee payer strategy lock poisoned");
+        // never used ones (None) go first
+        let index = (0..fee_payers.len())
+            .min_by_key(|index| last_used.get(&fee_payers[*index].pubkey()).copied())
+            .unwrap_or(0);
+        last_used.insert(fee_payers[index].pubkey(), Instant::now());
+        futures::future::ready(index).boxed()
+    }
+}
+
+/// Picks the fee payers at random weighted by their balances, the highest-balance wallet
+/// takes most of the load. The balances are refreshed via RPC once they are older
+/// than the refresh interval, a failed refresh keeps the previous balances.
+pub struct BalanceWeightedFeePayer {
+    rpc_client: Arc<RpcClient>,
+    refresh_interval: Duration,
+    // balances and the time they were fetched at
+    balances: tokio::sync::Mutex<Option<(HashMap<Pubkey, u64>, Instant)>>,
+}
+
+impl BalanceWeightedFeePayer {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            refresh_interval: DEFAULT_BALANCE_REFRESH_INTERVAL,
+            balances: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    async fn fetch_balances(&self, fee_payers: &[Pubkey]) -> anyhow::Result<HashMap<Pubkey, u64>> {
+        let mut balances = HashMap::with_capacity(fee_payers.len());
+        for chunk in fee_payers.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = self.rpc_client.get_multiple_accounts(chunk).await?;
+            for (fee_payer, account) in chunk.iter().zip(accounts) {
+                balances.insert(*fee_payer, account.map_or(0, |account| account.lamports));
+            }
+        }
+        Ok(balances)
+    }
+}
+
+impl FeePayerStrategy for BalanceWeightedFeePayer {
+    fn select<'a>(&'a self, fee_payers: &'a [Arc<Keypair>]) -> BoxFuture<'a, usize> {
+        async move {
+            let pubkeys: Vec<Pubkey> = fee_payers.iter().map(|keypair| keypair.pubkey()).collect();
+            let mut balances = self.balances.lock().await;
+            let stale = balances.as_ref().map_or(true, |(fetched, fetched_at)| {
+                fetched_at.elapsed() >= self.refresh_interval
+                    || pubkeys.iter().any(|pubkey| !fetched.contains_key(pubkey))
+            });
+            if stale {
+                match self.fetch_balances(&pubkeys).await {
+                    Ok(fetched) => *balances = Some((fetched, Instant::now())),
+                    Err(err) => debug!("Failed to refresh the fee payer balances: {err:?}"),
+                }
+            }
+            let weights: Vec<u64> = pubkeys
+                .iter()
+                .map(|pubkey| {
+                    balances
+                        .as_ref()
+                        .and_then(|(fetched, _)| fetched.get(pubkey).copied())
+                        .unwrap_or(0)
+                })
+                .collect();
+            let total: u128 = weights.iter().map(|weight| u128::from(*weight)).sum();
+            if total == 0 {
+                return rand::thread_rng().gen_range(0..fee_payers.len());
+            }
+            let mut point = rand::thread_rng().gen_range(0..total);
+            for (index, weight) in weights.iter().enumerate() {
+                if point < u128::from(*weight) {
+                    return index;
+                }
+                point -= u128::from(*weight);
+            }
+            fee_payers.len() - 1
+        }
+        .boxed()
+    }
+}
+
+/// Fee payers the executor assigns to the transactions, the message fee payer
+/// is rewritten when the transaction is built. Spreads the rate limits and the write locks
+/// of a single hot wallet over more of them. It may be shared by more executions.
+pub struct FeePayerPool {
+    fee_payers: Vec<Arc<Keypair>>,
+    strategy: Arc<dyn FeePayerStrategy>,
+}
+
+impl FeePayerPool {
+    /// The fee payers are used round-robin unless a strategy is set.
+    pub fn new(fee_payers: Vec<Arc<Keypair>>) -> Self {
+        assert!(
+            !fee_payers.is_empty(),
+            "Fee payer pool requires at least one fee payer"
+        );
+        Self {
+            fee_payers,
+            strategy: Arc::new(RoundRobinFeePayer::default()),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: Arc<dyn FeePayerStrategy>) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn fee_payers(&self) -> Vec<Pubkey> {
+        self.fee_payers
+            .iter()
+            .map(|keypair| keypair.pubkey())
+            .collect()
+    }
+
+    /// Fee payer of the next transaction
+    pub async fn next_fee_payer(&self) -> Arc<Keypair> {
+        let index = self.strategy.select(&self.fee_payers).await;
+        self.fee_payers[index.min(self.fee_payers.len() - 1)].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+
+    fn fee_payers(count: usize) -> Vec<Arc<Keypair>> {
+        (0..count).map(|_| Arc::new(Keypair::new())).collect()
+    }
+
+    fn balances_response(balances: &[Option<u64>]) -> serde_json::Value {
+        let accounts: Vec<serde_json::Value> = balances
+            .iter()
+            .map(|balance| match balance {
+                Some(lamports) => json!({
+                    "lamports": lamports,
+                    "data": ["", "base64"],
+                    "owner": solana_sdk::system_program::id().to_string(),
+                    "executable": false,
+                    "rentEpoch": 0,
+                    "space": 0,
+                }),
+                None => serde_json::Value::Null,
+            })
+            .collect();
+        json!({ "context": { "slot": 1 }, "value": accounts })
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let fee_payers = fee_payers(3);
+        let strategy = RoundRobinFeePayer::default();
+        let mut indexes = Vec::new();
+        for _ in 0..5 {
+            indexes.push(strategy.select(&fee_payers).await);
+        }
+        assert_eq!(indexes, vec![0, 1, 2, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used() {
+        let fee_payers = fee_payers(3);
+        let strategy = LeastRecentlyUsedFeePayer::default();
+        strategy.mark_used(&fee_payers[0].pubkey());
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        // the never used ones go first
+        assert_eq!(strategy.select(&fee_payers).await, 1);
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        assert_eq!(strategy.select(&fee_payers).await, 2);
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        assert_eq!(strategy.select(&fee_payers).await, 0);
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        strategy.mark_used(&fee_payers[1].pubkey());
+        assert_eq!(strategy.select(&fee_payers).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_balance_weighted() {
+        let fee_payers = fee_payers(2);
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            balances_response(&[Some(0), Some(1_000_000)]),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks("fails".to_string(), mocks));
+        let strategy =
+            BalanceWeightedFeePayer::new(rpc_client).with_refresh_interval(Duration::ZERO);
+        assert_eq!(strategy.select(&fee_payers).await, 1);
+        // the stale balances are refreshed, the failed refresh keeps the previous ones
+        for _ in 0..10 {
+            assert_eq!(strategy.select(&fee_payers).await, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_balance_weighted_zero_total() {
+        let fee_payers = fee_payers(2);
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            balances_response(&[None, Some(0)]),
+        );
+        let rpc_client = Arc::new(RpcClient::new_mock_with_mocks("fails".to_string(), mocks));
+        let strategy = BalanceWeightedFeePayer::new(rpc_client);
+        let mut selected = [false; 2];
+        for _ in 0..100 {
+            selected[strategy.select(&fee_payers).await] = true;
+        }
+        // picked uniformly when no fee payer has any balance
+        assert_eq!(selected, [true, true]);
+    }
+}
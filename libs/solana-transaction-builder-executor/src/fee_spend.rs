@@ -0,0 +1,189 @@
+use crate::ExecutedTransaction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const NO_DESCRIPTION: &str = "<no description>";
+
+/// Lamports spent by a landed transaction, estimated from the transaction as it was sent,
+/// the fees are the actual ones when the transaction cost was fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransactionSpend {
+    pub base_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
+    pub tip_lamports: u64,
+}
+
+impl TransactionSpend {
+    pub fn total_lamports(&self) -> u64 {
+        self.base_fee_lamports
+            .saturating_add(self.priority_fee_lamports)
+            .saturating_add(self.tip_lamports)
+    }
+
+    fn add(&mut self, other: &TransactionSpend) {
+        self.base_fee_lamports = self
+            .base_fee_lamports
+            .saturating_add(other.base_fee_lamports);
+        self.priority_fee_lamports = self
+            .priority_fee_lamports
+            .saturating_add(other.priority_fee_lamports);
+        self.tip_lamports = self.tip_lamports.saturating_add(other.tip_lamports);
+    }
+}
+
+impl fmt::Display for TransactionSpend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lamports (base fees {}, priority fees {}, tips {})",
+            self.total_lamports(),
+            self.base_fee_lamports,
+            self.priority_fee_lamports,
+            self.tip_lamports
+        )
+    }
+}
+
+/// Lamports spent by the executed transactions of a run, see `ExecutionReport::fee_spend_summary`.
+/// A transaction is accounted under every one of its labels (as `key=value`).
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FeeSpendSummary {
+    pub transactions: usize,
+    pub total: TransactionSpend,
+    pub by_description: BTreeMap<String, TransactionSpend>,
+    pub by_label: BTreeMap<String, TransactionSpend>,
+}
+
+impl FeeSpendSummary {
+    pub fn new(executed: &[ExecutedTransaction]) -> Self {
+        let mut summary = FeeSpendSummary::default();
+        for executed_transaction in executed {
+            let Some(spend) = &executed_transaction.spend else {
+                continue;
+            };
+            summary.transactions += 1;
+            summary.total.add(spend);
+            summary
+                .by_description
+                .entry(
+                    executed_transaction
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| NO_DESCRIPTION.to_string()),
+                )
+                .or_default()
+                .add(spend);
+            for (key, value) in &executed_transaction.labels {
+                summary
+                    .by_label
+                    .entry(format!("{key}={value}"))
+                    .or_default()
+                    .add(spend);
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for FeeSpendSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Spent {} by {} transactions",
+            self.total, self.transactions
+        )?;
+        for (description, spend) in &self.by_description {
+            writeln!(f, "  {description}: {spend}")?;
+        }
+        for (label, spend) in &self.by_label {
+            writeln!(f, "  [{label}]: {spend}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+
+    fn executed(
+        description: Option<&str>,
+        labels: &[(&str, &str)],
+        spend: Option<TransactionSpend>,
+    ) -> ExecutedTransaction {
+        ExecutedTransaction {
+            tx_uuid: "tx".to_string(),
+            human_index: 1,
+            signature: Signature::new_unique(),
+            labels: labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            metadata: vec![],
+            description: description.map(str::to_string),
+            cost: None,
+            spend,
+        }
+    }
+
+    fn spend(
+        base_fee_lamports: u64,
+        priority_fee_lamports: u64,
+        tip_lamports: u64,
+    ) -> TransactionSpend {
+        TransactionSpend {
+            base_fee_lamports,
+            priority_fee_lamports,
+            tip_lamports,
+        }
+    }
+
+    #[test]
+    fn test_total_lamports() {
+        assert_eq!(spend(5_000, 1_000, 2_000).total_lamports(), 8_000);
+        assert_eq!(spend(u64::MAX, 1, 0).total_lamports(), u64::MAX);
+        assert_eq!(
+            spend(5_000, 1_000, 2_000).to_string(),
+            "8000 lamports (base fees 5000, priority fees 1000, tips 2000)"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = FeeSpendSummary::new(&[
+            executed(
+                Some("Update config"),
+                &[("operation", "rebalance")],
+                Some(spend(5_000, 1_000, 0)),
+            ),
+            executed(
+                None,
+                &[("operation", "rebalance"), ("validator", "a")],
+                Some(spend(10_000, 0, 2_000)),
+            ),
+            // not executed by the run
+            executed(Some("Update config"), &[], None),
+        ]);
+        assert_eq!(summary.transactions, 2);
+        assert_eq!(summary.total, spend(15_000, 1_000, 2_000));
+        assert_eq!(
+            summary.by_description["Update config"],
+            spend(5_000, 1_000, 0)
+        );
+        assert_eq!(
+            summary.by_description[NO_DESCRIPTION],
+            spend(10_000, 0, 2_000)
+        );
+        assert_eq!(
+            summary.by_label["operation=rebalance"],
+            spend(15_000, 1_000, 2_000)
+        );
+        assert_eq!(summary.by_label["validator=a"], spend(10_000, 0, 2_000));
+        assert!(summary
+            .to_string()
+            .starts_with("Spent 18000 lamports (base fees 15000, priority fees 1000, tips 2000) by 2 transactions\n"));
+    }
+}
@@ -0,0 +1,61 @@
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_builder::{RemoteSignatureFuture, RemoteSigner};
+use std::str::FromStr;
+use tracing::debug;
+
+/// Reference remote signer posting `{"pubkey": <base58>, "message": <base64>}` to the URL
+/// and expecting `{"signature": <base58>}` in the response.
+#[derive(Debug)]
+pub struct HttpRemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+    bearer_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl HttpRemoteSigner {
+    pub fn new<S: Into<String>>(url: S, pubkey: Pubkey) -> Self {
+        Self {
+            url: url.into(),
+            pubkey,
+            bearer_token: None,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_bearer_token<S: Into<String>>(mut self, bearer_token: S) -> Self {
+        self.bearer_token = Some(bearer_token.into());
+        self
+    }
+}
+
+impl RemoteSigner for HttpRemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> RemoteSignatureFuture<'a> {
+        Box::pin(async move {
+            let mut request = self.http_client.post(&self.url).json(&json!({
+                "pubkey": self.pubkey.to_string(),
+                "message": base64::encode(message),
+            }));
+            if let Some(bearer_token) = &self.bearer_token {
+                request = request.bearer_auth(bearer_token);
+            }
+            let response: Value = request.send().await?.error_for_status()?.json().await?;
+            let signature = response
+                .get("signature")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("No signature in the response: {response}"))?;
+            let signature = Signature::from_str(signature)?;
+            if !signature.verify(self.pubkey.as_ref(), message) {
+                anyhow::bail!("Remote signature of {} does not verify", self.pubkey);
+            }
+            debug!("Remote signer {} signed the message", self.pubkey);
+            Ok(signature)
+        })
+    }
+}
@@ -0,0 +1,207 @@
+use serde_json::Value;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+pub const JITO_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+
+/// Accounts receiving the Jito tips, the tip is sent to a random one to spread the write locks
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    pubkey!("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    pubkey!("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    pubkey!("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    pubkey!("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    pubkey!("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    pubkey!("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    pubkey!("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+const TIP_FLOOR_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Percentiles of the landed tips published by the tip floor API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipFloorPercentile {
+    P25,
+    P50,
+    P75,
+    P95,
+    P99,
+    /// Exponential moving average of the 50th percentile
+    Ema50,
+}
+
+impl TipFloorPercentile {
+    fn field(self) -> &'static str {
+        match self {
+            Self::P25 => "landed_tips_25th_percentile",
+            Self::P50 => "landed_tips_50th_percentile",
+            Self::P75 => "landed_tips_75th_percentile",
+            Self::P95 => "landed_tips_95th_percentile",
+            Self::P99 => "landed_tips_99th_percentile",
+            Self::Ema50 => "ema_landed_tips_50th_percentile",
+        }
+    }
+}
+
+/// Jito tip transfer injected into every built transaction (see `ExecutionConfig::jito_tip`),
+/// the amount follows the current tip floor percentile capped by the maximum.
+pub struct JitoTip {
+    http_client: reqwest::Client,
+    tip_floor_url: String,
+    percentile: TipFloorPercentile,
+    min_tip_lamports: u64,
+    max_tip_lamports: u64,
+    // tip floor response and its fetch time
+    cached: Mutex<Option<(Value, Instant)>>,
+}
+
+impl JitoTip {
+    pub fn new(percentile: TipFloorPercentile, max_tip_lamports: u64) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            tip_floor_url: JITO_TIP_FLOOR_URL.to_string(),
+            percentile,
+            min_tip_lamports: 1_000,
+            max_tip_lamports,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub fn with_tip_floor_url<S: Into<String>>(mut self, tip_floor_url: S) -> Self {
+        self.tip_floor_url = tip_floor_url.into();
+        self
+    }
+
+    /// Jito requires at least 1000 lamports tip.
+    pub fn with_min_tip_lamports(mut self, min_tip_lamports: u64) -> Self {
+        self.min_tip_lamports = min_tip_lamports;
+        self
+    }
+
+    pub async fn tip_lamports(&self) -> anyhow::Result<u64> {
+        let tip_floor = self.tip_floor().await?;
+        let tip_sol = tip_floor[0][self.percentile.field()]
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("Unexpected tip floor response: {tip_floor}"))?;
+        let tip_lamports = (tip_sol * LAMPORTS_PER_SOL as f64).ceil() as u64;
+        Ok(tip_lamports
+            .max(self.min_tip_lamports)
+            .min(self.max_tip_lamports))
+    }
+
+    /// Transfer of the tip from the payer to a random tip account.
+    pub fn tip_instruction(payer: &Pubkey, tip_lamports: u64) -> Instruction {
+        let tip_account = JITO_TIP_ACCOUNTS[rand::random::<usize>() % JITO_TIP_ACCOUNTS.len()];
+        system_instruction::transfer(payer, &tip_account, tip_lamports)
+    }
+
+    async fn tip_floor(&self) -> anyhow::Result<Value> {
+        let mut cached = self.cached.lock().await;
+        if let Some((tip_floor, fetched_at)) = cached.as_ref() {
+            if fetched_at.elapsed() < TIP_FLOOR_CACHE_TTL {
+                return Ok(tip_floor.clone());
+            }
+        }
+        let tip_floor: Value = self
+            .http_client
+            .get(&self.tip_floor_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        debug!("Fetched Jito tip floor: {tip_floor}");
+        *cached = Some((tip_floor.clone(), Instant::now()));
+        Ok(tip_floor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::instruction::AccountMeta;
+    use solana_sdk::system_instruction::SystemInstruction;
+
+    // the cached response is used, nothing is fetched
+    async fn with_tip_floor(jito_tip: JitoTip, tip_floor: Value) -> JitoTip {
+        *jito_tip.cached.lock().await = Some((tip_floor, Instant::now()));
+        jito_tip
+    }
+
+    // powers of two, exact in lamports as well
+    fn tip_floor() -> Value {
+        json!([{
+            "landed_tips_25th_percentile": 0.00000095367431640625,
+            "landed_tips_50th_percentile": 0.0000019073486328125,
+            "landed_tips_75th_percentile": 0.00002,
+            "landed_tips_95th_percentile": 0.0005,
+            "landed_tips_99th_percentile": 0.002,
+            "ema_landed_tips_50th_percentile": 0.000003814697265625,
+        }])
+    }
+
+    #[test]
+    fn test_tip_instruction() {
+        let payer = Pubkey::new_unique();
+        for _ in 0..20 {
+            let instruction = JitoTip::tip_instruction(&payer, 5_000);
+            assert_eq!(instruction.program_id, solana_sdk::system_program::id());
+            assert_eq!(instruction.accounts[0], AccountMeta::new(payer, true));
+            assert!(JITO_TIP_ACCOUNTS.contains(&instruction.accounts[1].pubkey));
+            assert!(instruction.accounts[1].is_writable);
+            assert_eq!(
+                bincode::deserialize::<SystemInstruction>(&instruction.data).unwrap(),
+                SystemInstruction::Transfer { lamports: 5_000 }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tip_lamports() {
+        let jito_tip = with_tip_floor(
+            JitoTip::new(TipFloorPercentile::P50, 1_000_000),
+            tip_floor(),
+        )
+        .await;
+        // rounded up to whole lamports
+        assert_eq!(jito_tip.tip_lamports().await.unwrap(), 1_908);
+
+        let jito_tip = with_tip_floor(
+            JitoTip::new(TipFloorPercentile::Ema50, 1_000_000),
+            tip_floor(),
+        )
+        .await;
+        assert_eq!(jito_tip.tip_lamports().await.unwrap(), 3_815);
+
+        // bounded by the minimum and the maximum
+        let jito_tip = with_tip_floor(
+            JitoTip::new(TipFloorPercentile::P25, 1_000_000).with_min_tip_lamports(2_000),
+            tip_floor(),
+        )
+        .await;
+        assert_eq!(jito_tip.tip_lamports().await.unwrap(), 2_000);
+        let jito_tip = with_tip_floor(
+            JitoTip::new(TipFloorPercentile::P99, 1_000_000),
+            tip_floor(),
+        )
+        .await;
+        assert_eq!(jito_tip.tip_lamports().await.unwrap(), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_tip_floor() {
+        let jito_tip = with_tip_floor(
+            JitoTip::new(TipFloorPercentile::P50, 1_000_000),
+            json!({ "error": "rate limited" }),
+        )
+        .await;
+        assert!(jito_tip.tip_lamports().await.is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::clock::{Slot, DEFAULT_MS_PER_SLOT};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+// additional slot leaders fetched at once to not look up the schedule for every transaction
+const LEADERS_FETCH_MARGIN: u64 = 64;
+
+/// Delays the submissions toward the upcoming slots of the preferred leaders
+/// (e.g. the validators known to accept our transactions or running the Jito relayer).
+/// A transaction is sent right away when no preferred leader is scheduled
+/// within `max_delay_slots` or when the leader schedule cannot be fetched.
+pub struct LeaderAwareTiming {
+    rpc_client: Arc<RpcClient>,
+    preferred_leaders: HashSet<Pubkey>,
+    max_delay_slots: u64,
+    lead_slots: u64,
+    slot_duration: Duration,
+    // first slot of the fetched leaders and the leaders
+    leaders: Mutex<Option<(Slot, Vec<Pubkey>)>>,
+}
+
+impl LeaderAwareTiming {
+    pub fn new(rpc_client: Arc<RpcClient>, preferred_leaders: HashSet<Pubkey>) -> Self {
+        Self {
+            rpc_client,
+            preferred_leaders,
+            max_delay_slots: 8,
+            lead_slots: 1,
+            slot_duration: Duration::from_millis(DEFAULT_MS_PER_SLOT),
+            leaders: Mutex::new(None),
+        }
+    }
+
+    /// Maximum number of slots a submission is delayed by, 8 by default
+    pub fn with_max_delay_slots(mut self, max_delay_slots: u64) -> Self {
+        self.max_delay_slots = max_delay_slots;
+        self
+    }
+
+    /// Number of slots the transaction is sent before the preferred leader slot
+    /// to reach it in time, 1 by default
+    pub fn with_lead_slots(mut self, lead_slots: u64) -> Self {
+        self.lead_slots = lead_slots;
+        self
+    }
+
+    /// Expected slot duration of the cluster, 400ms by default
+    pub fn with_slot_duration(mut self, slot_duration: Duration) -> Self {
+        self.slot_duration = slot_duration;
+        self
+    }
+
+    /// Waits until a preferred leader is about to produce a block
+    pub async fn wait_for_preferred_leader(&self) {
+        match self.delay_slots().await {
+            Ok(Some(delay_slots)) if delay_slots > 0 => {
+                debug!("Delaying the submission by {delay_slots} slots to a preferred leader");
+                tokio::time::sleep(self.slot_duration * delay_slots as u32).await;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                debug!("Failed to look up the leader schedule, sending right away: {err:?}")
+            }
+        }
+    }
+
+    // Slots to wait for the next preferred leader, none when it is not scheduled in reach
+    async fn delay_slots(&self) -> anyhow::Result<Option<u64>> {
+        let slot = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::processed())
+            .await?;
+        let reach = self.max_delay_slots + self.lead_slots + 1;
+        let leaders = self.upcoming_leaders(slot, reach).await?;
+        Ok(leaders
+            .iter()
+            .position(|leader| self.preferred_leaders.contains(leader))
+            .map(|index| (index as u64).saturating_sub(self.lead_slots)))
+    }
+
+    // Leaders of the `count` slots starting at the slot
+    async fn upcoming_leaders(&self, slot: Slot, count: u64) -> anyhow::Result<Vec<Pubkey>> {
+        let mut leaders = self.leaders.lock().await;
+        if let Some((first_slot, cached)) = leaders.as_ref() {
+            if *first_slot <= slot && slot + count <= first_slot + cached.len() as u64 {
+                let start = (slot - first_slot) as usize;
+                return Ok(cached[start..start + count as usize].to_vec());
+            }
+        }
+        let fetched = self
+            .rpc_client
+            .get_slot_leaders(slot, count + LEADERS_FETCH_MARGIN)
+            .await?;
+        let upcoming = fetched.iter().take(count as usize).copied().collect();
+        *leaders = Some((slot, fetched));
+        Ok(upcoming)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+    use tokio::time::Instant;
+
+    const SLOT: Slot = 100;
+
+    // the current slot is returned once only
+    fn rpc_client(leaders: Option<&[Pubkey]>) -> Arc<RpcClient> {
+        let mut mocks = Mocks::new();
+        mocks.insert(RpcRequest::GetSlot, json!(SLOT));
+        if let Some(leaders) = leaders {
+            let leaders: Vec<String> = leaders.iter().map(ToString::to_string).collect();
+            mocks.insert(RpcRequest::GetSlotLeaders, json!(leaders));
+        }
+        Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ))
+    }
+
+    // schedule of the slots from SLOT with the preferred leader at the index
+    fn leaders(preferred_leader: &Pubkey, index: usize) -> Vec<Pubkey> {
+        let mut leaders: Vec<Pubkey> = (0..80).map(|_| Pubkey::new_unique()).collect();
+        leaders[index] = *preferred_leader;
+        leaders
+    }
+
+    async fn timing_with_schedule(preferred_leader_index: usize) -> LeaderAwareTiming {
+        let preferred_leader = Pubkey::new_unique();
+        let timing = LeaderAwareTiming::new(rpc_client(None), HashSet::from([preferred_leader]));
+        *timing.leaders.lock().await =
+            Some((SLOT, leaders(&preferred_leader, preferred_leader_index)));
+        timing
+    }
+
+    #[tokio::test]
+    async fn test_delay_slots() {
+        // sent a lead slot before the preferred leader
+        let timing = timing_with_schedule(5).await;
+        assert_eq!(timing.delay_slots().await.unwrap(), Some(4));
+        let timing = timing_with_schedule(0).await;
+        assert_eq!(timing.delay_slots().await.unwrap(), Some(0));
+        // beyond the maximum delay
+        let timing = timing_with_schedule(10).await;
+        assert_eq!(timing.delay_slots().await.unwrap(), None);
+        let timing = timing_with_schedule(9).await;
+        assert_eq!(timing.delay_slots().await.unwrap(), Some(8));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_leaders() {
+        let preferred_leader = Pubkey::new_unique();
+        let schedule = leaders(&preferred_leader, 3);
+        let timing = LeaderAwareTiming::new(
+            rpc_client(Some(&schedule[..73])),
+            HashSet::from([preferred_leader]),
+        )
+        .with_lead_slots(0);
+        assert_eq!(timing.delay_slots().await.unwrap(), Some(3));
+        // the fetch margin is kept for the next lookups
+        let leaders = timing.leaders.lock().await;
+        let (first_slot, cached) = leaders.as_ref().unwrap();
+        assert_eq!(*first_slot, SLOT);
+        assert_eq!(cached[..], schedule[..73]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_for_preferred_leader() {
+        let timing = timing_with_schedule(5).await;
+        let started_at = Instant::now();
+        timing.wait_for_preferred_leader().await;
+        assert!(started_at.elapsed() >= Duration::from_millis(1_600));
+        assert!(started_at.elapsed() < Duration::from_millis(2_000));
+
+        // the schedule cannot be fetched, no wait
+        let timing = LeaderAwareTiming::new(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            HashSet::from([Pubkey::new_unique()]),
+        );
+        let started_at = Instant::now();
+        timing.wait_for_preferred_leader().await;
+        assert_eq!(started_at.elapsed(), Duration::ZERO);
+    }
+}
@@ -1,5 +1,129 @@
+#[cfg(feature = "async")]
+mod audit_log;
+#[cfg(feature = "async")]
+mod blockhash_cache;
+#[cfg(feature = "blocking")]
+mod blocking;
+#[cfg(feature = "async")]
+mod broadcaster;
+#[cfg(feature = "async")]
 mod builder_executor;
+#[cfg(feature = "async")]
+mod concurrency_limiter;
+mod endpoint_health;
 mod error;
+#[cfg(feature = "async")]
+mod execution_config;
+#[cfg(feature = "async")]
+mod execution_controller;
+#[cfg(feature = "async")]
+mod execution_observer;
+mod execution_report;
+#[cfg(feature = "async")]
+mod execution_run;
+#[cfg(feature = "async")]
+mod execution_schedule;
+#[cfg(feature = "async")]
+mod fee_escalation;
+#[cfg(feature = "async")]
+mod fee_estimator;
+#[cfg(feature = "async")]
+mod fee_payer_pool;
+mod fee_spend;
+#[cfg(feature = "async")]
+mod http_remote_signer;
+#[cfg(feature = "async")]
+mod jito_tip;
+#[cfg(feature = "async")]
+mod leader_timing;
+#[cfg(feature = "async")]
+mod lookup_table_cache;
+#[cfg(feature = "async")]
+mod lookup_table_manager;
+#[cfg(feature = "async")]
+mod metrics;
+#[cfg(feature = "async")]
+mod nonce_pool;
+#[cfg(feature = "program-test")]
+mod program_test;
+#[cfg(feature = "async")]
+mod rate_limiter;
+mod retry_policy;
+#[cfg(feature = "async")]
+mod shutdown;
+#[cfg(feature = "async")]
+mod sign_only;
+#[cfg(feature = "async")]
+mod simulation;
+#[cfg(feature = "async")]
+mod slot_pacer;
+#[cfg(feature = "async")]
+mod state_assertions;
+#[cfg(feature = "tpu")]
+mod tpu_broadcaster;
+#[cfg(feature = "async")]
+mod transaction_adjustments;
+mod transaction_cost;
+#[cfg(feature = "async")]
+mod transaction_execution;
 
+#[cfg(feature = "async")]
+pub use audit_log::*;
+#[cfg(feature = "async")]
+pub use blockhash_cache::*;
+#[cfg(feature = "blocking")]
+pub use blocking::*;
+#[cfg(feature = "async")]
+pub use broadcaster::*;
+#[cfg(feature = "async")]
 pub use builder_executor::*;
+#[cfg(feature = "async")]
+pub use concurrency_limiter::*;
+pub use endpoint_health::*;
 pub use error::*;
+#[cfg(feature = "async")]
+pub use execution_config::*;
+#[cfg(feature = "async")]
+pub use execution_controller::*;
+#[cfg(feature = "async")]
+pub use execution_observer::*;
+pub use execution_report::*;
+#[cfg(feature = "async")]
+pub use execution_schedule::*;
+#[cfg(feature = "async")]
+pub use fee_escalation::*;
+#[cfg(feature = "async")]
+pub use fee_estimator::*;
+#[cfg(feature = "async")]
+pub use fee_payer_pool::*;
+pub use fee_spend::*;
+#[cfg(feature = "async")]
+pub use http_remote_signer::*;
+#[cfg(feature = "async")]
+pub use jito_tip::*;
+#[cfg(feature = "async")]
+pub use leader_timing::*;
+#[cfg(feature = "async")]
+pub use lookup_table_cache::*;
+#[cfg(feature = "async")]
+pub use lookup_table_manager::*;
+#[cfg(feature = "async")]
+pub use nonce_pool::*;
+#[cfg(feature = "program-test")]
+pub use program_test::*;
+#[cfg(feature = "async")]
+pub use rate_limiter::*;
+pub use retry_policy::*;
+#[cfg(feature = "async")]
+pub use shutdown::*;
+#[cfg(feature = "async")]
+pub use sign_only::*;
+#[cfg(feature = "async")]
+pub use simulation::*;
+#[cfg(feature = "async")]
+pub use slot_pacer::*;
+#[cfg(feature = "tpu")]
+pub use tpu_broadcaster::*;
+pub use transaction_cost::*;
+#[cfg(feature = "async")]
+pub use transaction_execution::*;
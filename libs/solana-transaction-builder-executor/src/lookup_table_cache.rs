@@ -0,0 +1,213 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_builder::TransactionBuilder;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// About a minute of slots
+pub const DEFAULT_LOOKUP_TABLE_MAX_AGE_SLOTS: u64 = 150;
+
+/// Contents of address lookup tables fetched with the RPC client and reused
+/// until they are older than the configured number of slots.
+pub struct LookupTableCache {
+    rpc_client: Arc<RpcClient>,
+    max_age_slots: u64,
+    // lookup table and the slot it was fetched at
+    cached: Mutex<HashMap<Pubkey, (AddressLookupTableAccount, u64)>>,
+}
+
+impl LookupTableCache {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            max_age_slots: DEFAULT_LOOKUP_TABLE_MAX_AGE_SLOTS,
+            cached: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_max_age_slots(mut self, max_age_slots: u64) -> Self {
+        self.max_age_slots = max_age_slots;
+        self
+    }
+
+    /// Lookup tables of the addresses, tables not existing or deactivated are left out.
+    pub async fn get_lookup_tables(
+        &self,
+        addresses: &[Pubkey],
+    ) -> anyhow::Result<Vec<AddressLookupTableAccount>> {
+        let slot = self.rpc_client.get_slot().await?;
+        let mut cached = self.cached.lock().await;
+        let stale: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|address| {
+                cached.get(address).map_or(true, |(_, fetched_slot)| {
+                    fetched_slot.saturating_add(self.max_age_slots) < slot
+                })
+            })
+            .cloned()
+            .collect();
+        if !stale.is_empty() {
+            debug!("Fetching lookup tables {stale:?} at slot {slot}");
+            let accounts = self.rpc_client.get_multiple_accounts(&stale).await?;
+            for (key, account) in stale.into_iter().zip(accounts) {
+                let table = account
+                    .map(|account| {
+                        AddressLookupTable::deserialize(&account.data).map(|table| {
+                            (table.meta.deactivation_slot == u64::MAX)
+                                .then(|| table.addresses.to_vec())
+                        })
+                    })
+                    .transpose()?
+                    .flatten();
+                match table {
+                    Some(addresses) => {
+                        cached.insert(key, (AddressLookupTableAccount { key, addresses }, slot));
+                    }
+                    None => {
+                        cached.remove(&key);
+                    }
+                }
+            }
+        }
+        Ok(addresses
+            .iter()
+            .filter_map(|address| cached.get(address).map(|(table, _)| table.clone()))
+            .collect())
+    }
+
+    /// Resolves the address table lookups of the message and appends its instructions
+    /// to the builder as a new pack, e.g., to replay a transaction exported from a wallet.
+    pub async fn add_versioned_message(
+        &self,
+        builder: &mut TransactionBuilder,
+        message: &VersionedMessage,
+    ) -> anyhow::Result<()> {
+        let addresses: Vec<Pubkey> = message
+            .address_table_lookups()
+            .map_or_else(Vec::new, |lookups| {
+                lookups.iter().map(|lookup| lookup.account_key).collect()
+            });
+        let lookup_tables = if addresses.is_empty() {
+            vec![]
+        } else {
+            self.get_lookup_tables(&addresses).await?
+        };
+        builder.add_versioned_message(message, &lookup_tables)?;
+        Ok(())
+    }
+
+    /// Drops the cached table, it's fetched again on the next use.
+    pub async fn invalidate(&self, address: &Pubkey) {
+        self.cached.lock().await.remove(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use solana_client::mock_sender::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::address_lookup_table::state::LookupTableMeta;
+    use std::borrow::Cow;
+
+    fn lookup_table_account(addresses: &[Pubkey], deactivation_slot: u64) -> Value {
+        let data = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot,
+                ..LookupTableMeta::default()
+            },
+            addresses: Cow::Borrowed(addresses),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        json!({
+            "lamports": 1_000_000,
+            "data": [base64::encode(data), "base64"],
+            "owner": solana_sdk::address_lookup_table::program::id().to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_lookup_tables() {
+        let active = Pubkey::new_unique();
+        let deactivated = Pubkey::new_unique();
+        let missing = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            json!({
+                "context": { "slot": 1 },
+                "value": [
+                    lookup_table_account(&addresses, u64::MAX),
+                    lookup_table_account(&addresses, 10),
+                    Value::Null,
+                ],
+            }),
+        );
+        let cache = LookupTableCache::new(Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        )));
+
+        // the deactivated and the not existing tables are left out
+        let lookup_tables = cache
+            .get_lookup_tables(&[active, deactivated, missing])
+            .await
+            .unwrap();
+        assert_eq!(
+            lookup_tables,
+            vec![AddressLookupTableAccount {
+                key: active,
+                addresses: addresses.clone(),
+            }]
+        );
+        assert_eq!(cache.cached.lock().await.len(), 1);
+
+        // served from the cache, the mocked accounts are returned once only
+        assert_eq!(
+            cache.get_lookup_tables(&[active]).await.unwrap(),
+            lookup_tables
+        );
+
+        cache.invalidate(&active).await;
+        assert!(cache.cached.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_lookup_table() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            json!({
+                "context": { "slot": 1 },
+                "value": [{
+                    "lamports": 1_000_000,
+                    "data": [base64::encode([1u8, 2, 3]), "base64"],
+                    "owner": solana_sdk::address_lookup_table::program::id().to_string(),
+                    "executable": false,
+                    "rentEpoch": 0,
+                    "space": 3,
+                }],
+            }),
+        );
+        let cache = LookupTableCache::new(Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        )));
+        assert!(cache
+            .get_lookup_tables(&[Pubkey::new_unique()])
+            .await
+            .is_err());
+        assert!(cache.cached.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES};
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_builder::PreparedTransaction;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+// keeps the extend transaction within the packet size
+const MAX_EXTEND_ADDRESSES: usize = 20;
+// offset of the authority pubkey in the lookup table account (behind the Option tag)
+const LOOKUP_TABLE_AUTHORITY_OFFSET: usize = 22;
+const ACTIVATION_CHECK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Address lookup tables owned by the authority (usually the fee payer) used to fit
+/// transactions with too many accounts for a legacy message (see `ExecutionConfig::lookup_table_manager`).
+/// Missing addresses are added by extending a table with free capacity or by creating a new one.
+pub struct LookupTableManager {
+    rpc_client: Arc<RpcClient>,
+    authority: Arc<Keypair>,
+    tables: Mutex<Vec<AddressLookupTableAccount>>,
+}
+
+impl LookupTableManager {
+    pub fn new(rpc_client: Arc<RpcClient>, authority: Arc<Keypair>) -> Self {
+        Self {
+            rpc_client,
+            authority,
+            tables: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_lookup_tables(mut self, tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.tables.get_mut().extend(tables);
+        self
+    }
+
+    pub async fn lookup_tables(&self) -> Vec<AddressLookupTableAccount> {
+        self.tables.lock().await.clone()
+    }
+
+    /// Loads the active lookup tables of the authority existing on chain,
+    /// returns the number of newly tracked tables.
+    pub async fn discover(&self) -> anyhow::Result<usize> {
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(
+                &solana_sdk::address_lookup_table::program::id(),
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        LOOKUP_TABLE_AUTHORITY_OFFSET,
+                        self.authority.pubkey().as_ref(),
+                    ))]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let mut tables = self.tables.lock().await;
+        let mut discovered = 0;
+        for (key, account) in accounts {
+            let table = AddressLookupTable::deserialize(&account.data)?;
+            if table.meta.deactivation_slot != u64::MAX
+                || tables.iter().any(|tracked| tracked.key == key)
+            {
+                continue;
+            }
+            tables.push(AddressLookupTableAccount {
+                key,
+                addresses: table.addresses.to_vec(),
+            });
+            discovered += 1;
+        }
+        Ok(discovered)
+    }
+
+    /// Lookup tables containing the accounts of the transaction, the missing accounts
+    /// are added to the tables first and the call waits for them to become active.
+    pub async fn lookup_tables_for(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+    ) -> anyhow::Result<Vec<AddressLookupTableAccount>> {
+        let message = &prepared_transaction.transaction.message;
+        // signers are never loaded from lookup tables
+        let addresses: Vec<Pubkey> = message
+            .account_keys
+            .iter()
+            .skip(message.header.num_required_signatures as usize)
+            .cloned()
+            .collect();
+
+        let mut tables = self.tables.lock().await;
+        let missing: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|address| !tables.iter().any(|table| table.addresses.contains(address)))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            let index = match extendable_table_index(&tables, missing.len()) {
+                Some(index) => index,
+                None => {
+                    tables.push(self.create_lookup_table().await?);
+                    tables.len() - 1
+                }
+            };
+            self.extend_lookup_table(&mut tables[index], &missing)
+                .await?;
+            self.wait_for_activation().await?;
+        }
+        Ok(tables
+            .iter()
+            .filter(|table| {
+                addresses
+                    .iter()
+                    .any(|address| table.addresses.contains(address))
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn create_lookup_table(&self) -> anyhow::Result<AddressLookupTableAccount> {
+        let recent_slot = self.rpc_client.get_slot().await?;
+        let (instruction, key) = create_lookup_table(
+            self.authority.pubkey(),
+            self.authority.pubkey(),
+            recent_slot,
+        );
+        let signature = self.send_and_confirm(&[instruction]).await?;
+        debug!("Created lookup table {key} with signature {signature}");
+        Ok(AddressLookupTableAccount {
+            key,
+            addresses: vec![],
+        })
+    }
+
+    async fn extend_lookup_table(
+        &self,
+        table: &mut AddressLookupTableAccount,
+        addresses: &[Pubkey],
+    ) -> anyhow::Result<()> {
+        for chunk in addresses.chunks(MAX_EXTEND_ADDRESSES) {
+            let instruction = extend_lookup_table(
+                table.key,
+                self.authority.pubkey(),
+                Some(self.authority.pubkey()),
+                chunk.to_vec(),
+            );
+            let signature = self.send_and_confirm(&[instruction]).await?;
+            debug!(
+                "Extended lookup table {} by {} addresses with signature {signature}",
+                table.key,
+                chunk.len()
+            );
+            table.addresses.extend_from_slice(chunk);
+        }
+        Ok(())
+    }
+
+    // Addresses added to a lookup table are usable from the next slot
+    async fn wait_for_activation(&self) -> anyhow::Result<()> {
+        let extended_slot = self.rpc_client.get_slot().await?;
+        while self.rpc_client.get_slot().await? <= extended_slot {
+            tokio::time::sleep(ACTIVATION_CHECK_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    async fn send_and_confirm(&self, instructions: &[Instruction]) -> anyhow::Result<Signature> {
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.authority.pubkey()),
+            &[self.authority.as_ref()],
+            blockhash,
+        );
+        Ok(self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .await?)
+    }
+}
+
+// First of the tables with room for all the missing addresses
+fn extendable_table_index(tables: &[AddressLookupTableAccount], missing: usize) -> Option<usize> {
+    tables
+        .iter()
+        .position(|table| table.addresses.len() + missing <= LOOKUP_TABLE_MAX_ADDRESSES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+    use solana_transaction_builder::TransactionBuilder;
+
+    fn lookup_table(addresses: Vec<Pubkey>) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses,
+        }
+    }
+
+    fn prepared_transaction(program_id: Pubkey, account: Pubkey) -> PreparedTransaction {
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        builder
+            .add_instruction(Instruction::new_with_bytes(
+                program_id,
+                &[0],
+                vec![AccountMeta::new(account, false)],
+            ))
+            .unwrap();
+        builder.build_one()
+    }
+
+    #[test]
+    fn test_extendable_table_index() {
+        let full = lookup_table(vec![Pubkey::new_unique(); LOOKUP_TABLE_MAX_ADDRESSES]);
+        let almost_full = lookup_table(vec![Pubkey::new_unique(); LOOKUP_TABLE_MAX_ADDRESSES - 1]);
+        let empty = lookup_table(vec![]);
+        assert_eq!(extendable_table_index(&[], 1), None);
+        assert_eq!(extendable_table_index(&[full.clone()], 1), None);
+        assert_eq!(
+            extendable_table_index(&[full.clone(), almost_full.clone(), empty.clone()], 1),
+            Some(1)
+        );
+        assert_eq!(
+            extendable_table_index(&[full, almost_full, empty], 2),
+            Some(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_tables_for_tracked_addresses() {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let program_table = lookup_table(vec![program_id, Pubkey::new_unique()]);
+        let account_table = lookup_table(vec![account]);
+        let unrelated_table = lookup_table(vec![Pubkey::new_unique()]);
+        // nothing is missing, no request is made
+        let manager = LookupTableManager::new(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            Arc::new(Keypair::new()),
+        )
+        .with_lookup_tables(vec![
+            program_table.clone(),
+            unrelated_table,
+            account_table.clone(),
+        ]);
+
+        let lookup_tables = manager
+            .lookup_tables_for(&prepared_transaction(program_id, account))
+            .await
+            .unwrap();
+        assert_eq!(lookup_tables, vec![program_table, account_table]);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_tables_for_failed_extension() {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let manager = LookupTableManager::new(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            Arc::new(Keypair::new()),
+        )
+        .with_lookup_tables(vec![lookup_table(vec![program_id])]);
+
+        assert!(manager
+            .lookup_tables_for(&prepared_transaction(program_id, account))
+            .await
+            .is_err());
+        // the table is not extended by the not sent addresses
+        assert_eq!(manager.lookup_tables().await[0].addresses, vec![program_id]);
+    }
+
+    #[tokio::test]
+    async fn test_extend_lookup_table_in_chunks() {
+        let manager = LookupTableManager::new(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            Arc::new(Keypair::new()),
+        );
+        let mut table = lookup_table(vec![Pubkey::new_unique()]);
+        let addresses: Vec<Pubkey> = (0..MAX_EXTEND_ADDRESSES + 5)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+
+        manager
+            .extend_lookup_table(&mut table, &addresses)
+            .await
+            .unwrap();
+        assert_eq!(table.addresses.len(), addresses.len() + 1);
+        assert_eq!(table.addresses[1..], addresses[..]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Prometheus metrics of the execution, collected with the `metrics` feature
+//! into the prometheus default registry (see `prometheus::gather`).
+//! Transactions are labeled by a prefix of their first instruction description
+//! and by their user-defined labels of the `ExecutionConfig::metric_label_keys`
+//! formatted as `key=value,key=value`.
+
+use crate::TransactionBuilderExecutionData;
+use std::time::Duration;
+
+#[cfg(feature = "metrics")]
+mod registry {
+    use once_cell::sync::Lazy;
+    use prometheus::{
+        exponential_buckets, register_histogram_vec, register_int_counter_vec, HistogramVec,
+        IntCounterVec,
+    };
+
+    pub(super) static TRANSACTIONS_BUILT: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_transactions_built_total",
+            "Transactions built and signed",
+            &["description", "labels"]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static TRANSACTIONS_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_transactions_sent_total",
+            "Signed transactions sent over RPC or handed over to the transaction executor",
+            &["description", "labels"]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static TRANSACTIONS_CONFIRMED: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_transactions_confirmed_total",
+            "Transactions executed successfully",
+            &["description", "labels"]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static TRANSACTIONS_FAILED: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_transactions_failed_total",
+            "Transactions failed to be executed",
+            &["description", "labels"]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static CONFIRMATION_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+        register_histogram_vec!(
+            "solana_tx_builder_confirmation_latency_seconds",
+            "Time from the start of the transaction execution to its confirmation",
+            &["description", "labels"],
+            vec![0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static COMPUTE_UNIT_PRICE: Lazy<HistogramVec> = Lazy::new(|| {
+        register_histogram_vec!(
+            "solana_tx_builder_compute_unit_price_micro_lamports",
+            "Priority fee (compute unit price) the landed transactions were built with",
+            &["description", "labels"],
+            exponential_buckets(1.0, 10.0, 10).expect("Valid buckets")
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static BLOCKHASH_CACHE: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_blockhash_cache_total",
+            "Blockhash cache lookups by result (hit, miss)",
+            &["result"]
+        )
+        .expect("Metric registration failed")
+    });
+    pub(super) static RPC_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "solana_tx_builder_rpc_errors_total",
+            "Errors of the failed transactions and RPC calls by the RPC error code",
+            &["code"]
+        )
+        .expect("Metric registration failed")
+    });
+}
+
+#[cfg(feature = "metrics")]
+const DESCRIPTION_LABEL_MAX_LENGTH: usize = 32;
+
+#[cfg(feature = "metrics")]
+fn transaction_labels(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+) -> [String; 2] {
+    let description = execution_data
+        .prepared_transaction
+        .instruction_descriptions
+        .iter()
+        .flatten()
+        .next()
+        .map_or_else(
+            || "none".to_string(),
+            |description| {
+                description
+                    .chars()
+                    .take(DESCRIPTION_LABEL_MAX_LENGTH)
+                    .collect()
+            },
+        );
+    let labels = execution_data
+        .labels
+        .iter()
+        .filter(|(key, _)| label_keys.contains(key))
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    [description, labels]
+}
+
+#[cfg(feature = "metrics")]
+fn rpc_error_code(error: &anyhow::Error) -> String {
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use solana_client::rpc_request::RpcError;
+
+    match error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<ClientError>())
+        .map(|client_error| &client_error.kind)
+    {
+        Some(ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })) => {
+            code.to_string()
+        }
+        Some(ClientErrorKind::Reqwest(err)) => err
+            .status()
+            .map_or_else(|| "http".to_string(), |status| status.as_u16().to_string()),
+        Some(ClientErrorKind::TransactionError(_)) => "transaction".to_string(),
+        Some(_) => "client".to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transaction_built(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+) {
+    #[cfg(feature = "metrics")]
+    {
+        let [description, labels] = transaction_labels(execution_data, label_keys);
+        registry::TRANSACTIONS_BUILT
+            .with_label_values(&[&description, &labels])
+            .inc();
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transaction_sent(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+) {
+    #[cfg(feature = "metrics")]
+    {
+        let [description, labels] = transaction_labels(execution_data, label_keys);
+        registry::TRANSACTIONS_SENT
+            .with_label_values(&[&description, &labels])
+            .inc();
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transaction_confirmed(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+    latency: Duration,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        let [description, labels] = transaction_labels(execution_data, label_keys);
+        let label_values = [description.as_str(), labels.as_str()];
+        registry::TRANSACTIONS_CONFIRMED
+            .with_label_values(&label_values)
+            .inc();
+        registry::CONFIRMATION_LATENCY
+            .with_label_values(&label_values)
+            .observe(latency.as_secs_f64());
+    }
+}
+
+// The compute unit price the landed transaction was built with
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transaction_landed(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+    compute_unit_price: Option<u64>,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        let [description, labels] = transaction_labels(execution_data, label_keys);
+        registry::COMPUTE_UNIT_PRICE
+            .with_label_values(&[&description, &labels])
+            .observe(compute_unit_price.unwrap_or(0) as f64);
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transaction_failed(
+    execution_data: &TransactionBuilderExecutionData,
+    label_keys: &[String],
+    error: &anyhow::Error,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        let [description, labels] = transaction_labels(execution_data, label_keys);
+        registry::TRANSACTIONS_FAILED
+            .with_label_values(&[&description, &labels])
+            .inc();
+        rpc_error(error);
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn rpc_error(error: &anyhow::Error) {
+    #[cfg(feature = "metrics")]
+    registry::RPC_ERRORS
+        .with_label_values(&[&rpc_error_code(error)])
+        .inc();
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn blockhash_cache_lookup(hit: bool) {
+    #[cfg(feature = "metrics")]
+    registry::BLOCKHASH_CACHE
+        .with_label_values(&[if hit { "hit" } else { "miss" }])
+        .inc();
+}
@@ -0,0 +1,404 @@
+use crate::state_assertions::MAX_MULTIPLE_ACCOUNTS;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::nonce::state::{State, Versions};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use solana_transaction_builder::{PreparedTransaction, SignedTransaction, TransactionBuilder};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Transaction signed with a durable nonce instead of a recent blockhash,
+/// valid until the nonce account is advanced.
+#[derive(Debug, Clone)]
+pub struct NonceSignedTransaction {
+    pub nonce_account: Pubkey,
+    pub transaction: VersionedTransaction,
+}
+
+/// Durable nonce the transaction of an execution data is signed with instead of a recent blockhash,
+/// see `TransactionBuilderExecutionData::with_durable_nonce`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub nonce_account: Pubkey,
+    /// Signer of the nonce advance, usually the fee payer
+    pub authority: Pubkey,
+}
+
+impl DurableNonce {
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.nonce_account, &self.authority)
+    }
+}
+
+/// Pool of durable nonce accounts with the fee payer as their authority.
+/// Every transaction signed by the pool gets its own nonce account that is not assigned again
+/// until released, so any number of transactions can be signed ahead and submitted later.
+pub struct NoncePool {
+    rpc_client: Arc<RpcClient>,
+    authority: Arc<Keypair>,
+    // a single lock over the accounts and their assignments, no lock order to keep
+    state: Mutex<NoncePoolState>,
+}
+
+#[derive(Default)]
+struct NoncePoolState {
+    nonce_accounts: Vec<Pubkey>,
+    assigned: HashSet<Pubkey>,
+}
+
+impl NoncePoolState {
+    fn unassigned(&self) -> impl Iterator<Item = &Pubkey> {
+        self.nonce_accounts
+            .iter()
+            .filter(|nonce_account| !self.assigned.contains(nonce_account))
+    }
+}
+
+impl NoncePool {
+    pub fn new(rpc_client: Arc<RpcClient>, authority: Arc<Keypair>) -> Self {
+        Self {
+            rpc_client,
+            authority,
+            state: Mutex::new(NoncePoolState::default()),
+        }
+    }
+
+    /// Tracks already existing nonce accounts of the authority.
+    pub fn with_nonce_accounts(mut self, nonce_accounts: Vec<Pubkey>) -> Self {
+        self.state.get_mut().nonce_accounts.extend(nonce_accounts);
+        self
+    }
+
+    pub async fn nonce_accounts(&self) -> Vec<Pubkey> {
+        self.state.lock().await.nonce_accounts.clone()
+    }
+
+    /// Creates and funds (rent exempt) new nonce accounts until the pool has at least
+    /// the number of unassigned accounts, returns the created accounts.
+    pub async fn ensure_available(&self, count: usize) -> anyhow::Result<Vec<Pubkey>> {
+        let available = self.state.lock().await.unassigned().count();
+        let mut created = Vec::new();
+        for _ in available..count {
+            created.push(self.create_nonce_account().await?);
+        }
+        Ok(created)
+    }
+
+    pub async fn create_nonce_account(&self) -> anyhow::Result<Pubkey> {
+        let nonce_keypair = Keypair::new();
+        let lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(State::size())
+            .await?;
+        let instructions = system_instruction::create_nonce_account(
+            &self.authority.pubkey(),
+            &nonce_keypair.pubkey(),
+            &self.authority.pubkey(),
+            lamports,
+        );
+        let signature = self
+            .send_and_confirm(&instructions, &[&nonce_keypair])
+            .await?;
+        debug!(
+            "Created nonce account {} with signature {signature}",
+            nonce_keypair.pubkey()
+        );
+        self.state
+            .lock()
+            .await
+            .nonce_accounts
+            .push(nonce_keypair.pubkey());
+        Ok(nonce_keypair.pubkey())
+    }
+
+    /// Current durable nonce stored in the nonce account.
+    pub async fn fetch_nonce(&self, nonce_account: &Pubkey) -> anyhow::Result<Hash> {
+        fetch_nonce(&self.rpc_client, nonce_account).await
+    }
+
+    /// Assigns an unassigned nonce account to the transaction and signs it with its nonce,
+    /// the nonce advance instruction is prepended to the transaction.
+    /// The authority has to be a signer (usually the fee payer) of the transaction.
+    pub async fn sign(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+    ) -> anyhow::Result<NonceSignedTransaction> {
+        let nonce_account = self.assign().await?;
+        match self
+            .sign_with_nonce_account(prepared_transaction, &nonce_account)
+            .await
+        {
+            Ok(transaction) => Ok(NonceSignedTransaction {
+                nonce_account,
+                transaction,
+            }),
+            Err(err) => {
+                self.release(&nonce_account).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the nonce account back to the pool, the transaction signed with it
+    /// has either landed or it was invalidated by `advance`.
+    pub async fn release(&self, nonce_account: &Pubkey) {
+        self.state.lock().await.assigned.remove(nonce_account);
+    }
+
+    /// Advances the nonce, invalidating the transaction signed with it, and releases the account.
+    pub async fn advance(&self, nonce_account: &Pubkey) -> anyhow::Result<Signature> {
+        let signature = self
+            .send_and_confirm(
+                &[system_instruction::advance_nonce_account(
+                    nonce_account,
+                    &self.authority.pubkey(),
+                )],
+                &[],
+            )
+            .await?;
+        self.release(nonce_account).await;
+        Ok(signature)
+    }
+
+    /// Withdraws the whole balance of the nonce account to the authority, closing the account.
+    pub async fn close(&self, nonce_account: &Pubkey) -> anyhow::Result<Signature> {
+        let lamports = self.rpc_client.get_balance(nonce_account).await?;
+        let signature = self
+            .send_and_confirm(
+                &[system_instruction::withdraw_nonce_account(
+                    nonce_account,
+                    &self.authority.pubkey(),
+                    &self.authority.pubkey(),
+                    lamports,
+                )],
+                &[],
+            )
+            .await?;
+        let mut state = self.state.lock().await;
+        state
+            .nonce_accounts
+            .retain(|tracked| tracked != nonce_account);
+        state.assigned.remove(nonce_account);
+        Ok(signature)
+    }
+
+    /// Closes all the unassigned nonce accounts of the pool.
+    pub async fn close_unassigned(&self) -> anyhow::Result<Vec<Signature>> {
+        let unassigned: Vec<Pubkey> = self.state.lock().await.unassigned().cloned().collect();
+        let mut signatures = Vec::with_capacity(unassigned.len());
+        for nonce_account in unassigned {
+            signatures.push(self.close(&nonce_account).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Adds an instruction pack withdrawing the whole balance to the destination (closing
+    /// the account) for every unassigned nonce account, to reclaim the rent after a campaign
+    /// of durable nonce transactions. The accounts are kept assigned to not be used again,
+    /// `forget` them once the transactions landed. Returns the accounts being closed.
+    /// The builder is left as it was and the accounts are released when it fails.
+    pub async fn add_close_instruction_packs(
+        &self,
+        transaction_builder: &mut TransactionBuilder,
+        destination: &Pubkey,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        let unassigned: Vec<Pubkey> = {
+            let mut state = self.state.lock().await;
+            let unassigned: Vec<Pubkey> = state.unassigned().cloned().collect();
+            state.assigned.extend(unassigned.iter().cloned());
+            unassigned
+        };
+        let snapshot = transaction_builder.snapshot();
+        transaction_builder.add_signer_checked(&self.authority);
+        let result = add_close_nonce_account_packs(
+            &self.rpc_client,
+            transaction_builder,
+            &unassigned,
+            &self.authority.pubkey(),
+            destination,
+        )
+        .await;
+        if result.is_err() {
+            transaction_builder.restore(snapshot);
+            let mut state = self.state.lock().await;
+            for nonce_account in &unassigned {
+                state.assigned.remove(nonce_account);
+            }
+        }
+        result
+    }
+
+    /// Stops tracking the nonce accounts (e.g. closed by `add_close_instruction_packs`).
+    pub async fn forget(&self, nonce_accounts: &[Pubkey]) {
+        let mut state = self.state.lock().await;
+        state
+            .nonce_accounts
+            .retain(|tracked| !nonce_accounts.contains(tracked));
+        for nonce_account in nonce_accounts {
+            state.assigned.remove(nonce_account);
+        }
+    }
+
+    async fn assign(&self) -> anyhow::Result<Pubkey> {
+        let mut state = self.state.lock().await;
+        let nonce_account = state
+            .unassigned()
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No unassigned nonce account in the pool"))?;
+        state.assigned.insert(nonce_account);
+        Ok(nonce_account)
+    }
+
+    async fn sign_with_nonce_account(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+        nonce_account: &Pubkey,
+    ) -> anyhow::Result<VersionedTransaction> {
+        let nonce = self.fetch_nonce(nonce_account).await?;
+        let prepared_transaction = prepared_transaction.with_prepended_instruction(
+            system_instruction::advance_nonce_account(nonce_account, &self.authority.pubkey()),
+            Some(format!("Advance nonce {nonce_account}")),
+        );
+        if prepared_transaction.requires_remote_signatures() {
+            return prepared_transaction
+                .sign_message(prepared_transaction.legacy_message(nonce))
+                .await;
+        }
+        Ok(prepared_transaction.signed_versioned_transaction(nonce)?)
+    }
+
+    async fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        additional_signers: &[&Keypair],
+    ) -> anyhow::Result<Signature> {
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let mut signers: Vec<&Keypair> = vec![self.authority.as_ref()];
+        signers.extend(additional_signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.authority.pubkey()),
+            &signers,
+            blockhash,
+        );
+        Ok(self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .await?)
+    }
+}
+
+/// Current durable nonce stored in the nonce account.
+pub(crate) async fn fetch_nonce(
+    rpc_client: &RpcClient,
+    nonce_account: &Pubkey,
+) -> anyhow::Result<Hash> {
+    let account = rpc_client.get_account(nonce_account).await?;
+    let versions: Versions = bincode::deserialize(&account.data)?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.blockhash()),
+        State::Uninitialized => Err(anyhow::anyhow!(
+            "Nonce account {nonce_account} is not initialized"
+        )),
+    }
+}
+
+/// Adds an instruction pack withdrawing the whole balance of every nonce account to the destination,
+/// closing the accounts. The authority has to be a signer of the transaction builder,
+/// accounts that do not exist anymore are skipped. Returns the accounts being closed.
+/// The builder is left as it was when any of the accounts cannot be fetched.
+pub async fn add_close_nonce_account_packs(
+    rpc_client: &RpcClient,
+    transaction_builder: &mut TransactionBuilder,
+    nonce_accounts: &[Pubkey],
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> anyhow::Result<Vec<Pubkey>> {
+    let snapshot = transaction_builder.snapshot();
+    let result = add_close_nonce_account_packs_unchecked(
+        rpc_client,
+        transaction_builder,
+        nonce_accounts,
+        authority,
+        destination,
+    )
+    .await;
+    if result.is_err() {
+        transaction_builder.restore(snapshot);
+    }
+    result
+}
+
+async fn add_close_nonce_account_packs_unchecked(
+    rpc_client: &RpcClient,
+    transaction_builder: &mut TransactionBuilder,
+    nonce_accounts: &[Pubkey],
+    authority: &Pubkey,
+    destination: &Pubkey,
+) -> anyhow::Result<Vec<Pubkey>> {
+    let mut closed = Vec::with_capacity(nonce_accounts.len());
+    for chunk in nonce_accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc_client.get_multiple_accounts(chunk).await?;
+        for (nonce_account, account) in chunk.iter().zip(accounts) {
+            let Some(account) = account else {
+                debug!("Nonce account {nonce_account} does not exist, not closing it");
+                continue;
+            };
+            transaction_builder.add_instruction_with_description(
+                system_instruction::withdraw_nonce_account(
+                    nonce_account,
+                    authority,
+                    destination,
+                    account.lamports,
+                ),
+                format!("Close nonce account {nonce_account}"),
+            )?;
+            transaction_builder.finish_instruction_pack();
+            closed.push(*nonce_account);
+        }
+    }
+    Ok(closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_close_instruction_packs_failure_restores_builder() {
+        let authority = Arc::new(Keypair::new());
+        let nonce_accounts = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let pool = NoncePool::new(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            authority.clone(),
+        )
+        .with_nonce_accounts(nonce_accounts.clone());
+
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        builder
+            .add_instruction(system_instruction::transfer(
+                &builder.fee_payer(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        builder.finish_instruction_pack();
+
+        assert!(pool
+            .add_close_instruction_packs(&mut builder, &Pubkey::new_unique())
+            .await
+            .is_err());
+        assert_eq!(builder.packs_len(), 1);
+        assert!(builder.get_signer(&authority.pubkey()).is_none());
+        // the accounts are released to be assigned again
+        assert_eq!(pool.assign().await.unwrap(), nonce_accounts[0]);
+        assert_eq!(pool.assign().await.unwrap(), nonce_accounts[1]);
+    }
+}
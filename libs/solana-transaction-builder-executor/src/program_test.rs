@@ -0,0 +1,126 @@
+use crate::{ExecutionReport, SkipReason};
+use solana_program_test::BanksClient;
+use solana_sdk::signature::Signature;
+use solana_transaction_builder::{
+    get_prepared_transaction_iterator, PreparedTransaction, SignedTransaction, TransactionBuilder,
+};
+
+/// Executes the prepared transactions in sequence against the bank of `ProgramTest`
+/// (e.g. `ProgramTest::start_with_context`) instead of an RPC endpoint,
+/// a transaction is executed when it is processed by the bank without an error.
+pub async fn execute_prepared_transactions_with_banks_client(
+    banks_client: &mut BanksClient,
+    prepared_transactions: &[PreparedTransaction],
+    fail_on_first_error: bool,
+) -> ExecutionReport {
+    let mut report = ExecutionReport::default();
+    for (index, prepared_transaction) in prepared_transactions.iter().enumerate() {
+        let human_index = index + 1;
+        if fail_on_first_error && !report.errors.is_empty() {
+            report.add_prepared_skipped(
+                prepared_transaction,
+                human_index,
+                SkipReason::PreviousFailure,
+            );
+            continue;
+        }
+        let result = execute_with_banks_client(banks_client, prepared_transaction).await;
+        report.add_prepared_result(
+            prepared_transaction,
+            human_index,
+            result,
+            "Program test execution failed",
+        );
+    }
+    report
+}
+
+/// Builds all transactions of the builder and executes them against the bank,
+/// `is_one_by_one` as in `get_prepared_transaction_iterator`.
+pub async fn execute_builder_with_banks_client(
+    banks_client: &mut BanksClient,
+    transaction_builder: &mut TransactionBuilder,
+    is_one_by_one: bool,
+    fail_on_first_error: bool,
+) -> ExecutionReport {
+    let prepared_transactions: Vec<PreparedTransaction> =
+        get_prepared_transaction_iterator(transaction_builder, is_one_by_one).collect();
+    execute_prepared_transactions_with_banks_client(
+        banks_client,
+        &prepared_transactions,
+        fail_on_first_error,
+    )
+    .await
+}
+
+async fn execute_with_banks_client(
+    banks_client: &mut BanksClient,
+    prepared_transaction: &PreparedTransaction,
+) -> anyhow::Result<Signature> {
+    let blockhash = banks_client.get_latest_blockhash().await?;
+    let transaction = if prepared_transaction.requires_remote_signatures() {
+        prepared_transaction
+            .sign_message(prepared_transaction.legacy_message(blockhash))
+            .await?
+    } else {
+        prepared_transaction.signed_versioned_transaction(blockhash)?
+    };
+    let signature = transaction.signatures[0];
+    banks_client.process_transaction(transaction).await?;
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program_test::ProgramTest;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signer;
+    use solana_sdk::system_instruction;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_execute_builder_with_banks_client() {
+        let (mut banks_client, payer, _) = ProgramTest::default().start().await;
+        let payer = Arc::new(payer);
+        let recipient = Pubkey::new_unique();
+        let mut builder = TransactionBuilder::limited(payer.clone());
+        for lamports in [u64::MAX, 1_000_000, 2_000_000] {
+            builder
+                .add_instruction(system_instruction::transfer(
+                    &payer.pubkey(),
+                    &recipient,
+                    lamports,
+                ))
+                .unwrap();
+            builder.finish_instruction_pack();
+        }
+        let mut prepared_transactions: Vec<PreparedTransaction> =
+            get_prepared_transaction_iterator(&mut builder, true).collect();
+
+        // the transfer exceeding the balance fails, the rest is skipped
+        let report = execute_prepared_transactions_with_banks_client(
+            &mut banks_client,
+            &prepared_transactions,
+            true,
+        )
+        .await;
+        assert!(!report.is_success());
+        assert!(report.executed.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+
+        prepared_transactions.remove(0);
+        let report = execute_prepared_transactions_with_banks_client(
+            &mut banks_client,
+            &prepared_transactions,
+            true,
+        )
+        .await;
+        assert!(report.is_success());
+        assert_eq!(report.executed.len(), 2);
+        assert_eq!(
+            banks_client.get_balance(recipient).await.unwrap(),
+            3_000_000
+        );
+    }
+}
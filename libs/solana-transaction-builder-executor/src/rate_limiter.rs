@@ -0,0 +1,105 @@
+use std::sync::Mutex;
+use std::time::Duration;
+// follows the paused time of the tokio tests
+use tokio::time::Instant;
+
+/// Token bucket limiting the rate of transaction submissions.
+/// It may be shared by more executions sending to the same RPC provider.
+#[derive(Debug)]
+pub struct RateLimiter {
+    sends_per_second: f64,
+    burst: f64,
+    bucket: Mutex<TokenBucket>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(sends_per_second: u32) -> Self {
+        let sends_per_second = f64::from(sends_per_second.max(1));
+        Self {
+            sends_per_second,
+            burst: sends_per_second,
+            bucket: Mutex::new(TokenBucket {
+                tokens: sends_per_second,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Maximum number of sends permitted at once after an idle period
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = f64::from(burst.max(1));
+        let bucket = self.bucket.get_mut().expect("Rate limiter lock poisoned");
+        bucket.tokens = bucket.tokens.min(self.burst);
+        self
+    }
+
+    /// Waits until a send is permitted
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Takes a token when available, otherwise returns the time to wait for it
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().expect("Rate limiter lock poisoned");
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.sends_per_second).min(self.burst);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.sends_per_second,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_and_refill() {
+        let rate_limiter = RateLimiter::new(10).with_burst(3);
+        for _ in 0..3 {
+            assert_eq!(rate_limiter.try_acquire(), None);
+        }
+        let wait = rate_limiter.try_acquire().unwrap();
+        assert!(wait > Duration::from_millis(99) && wait <= Duration::from_millis(100));
+
+        // a token per 100ms
+        tokio::time::advance(Duration::from_millis(101)).await;
+        assert_eq!(rate_limiter.try_acquire(), None);
+        assert!(rate_limiter.try_acquire().is_some());
+
+        // the idle refill is capped by the burst
+        tokio::time::advance(Duration::from_secs(10)).await;
+        for _ in 0..3 {
+            assert_eq!(rate_limiter.try_acquire(), None);
+        }
+        assert!(rate_limiter.try_acquire().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_waits_for_token() {
+        let rate_limiter = RateLimiter::new(2);
+        let started_at = Instant::now();
+        for _ in 0..4 {
+            rate_limiter.acquire().await;
+        }
+        // the burst of two right away, then a send per 500ms
+        let elapsed = started_at.elapsed();
+        assert!(elapsed >= Duration::from_millis(1000));
+        assert!(elapsed < Duration::from_millis(1100));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::ExecutionErrorKind;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type RetryPredicate = Arc<dyn Fn(&anyhow::Error) -> bool + Send + Sync>;
+
+/// Retry of a failed transaction execution, an attempt covers the whole priority fee escalation
+/// of the wrapped transaction executor.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Number of attempts including the first one
+    pub max_attempts: usize,
+    /// Delay before the first retry, doubled with every next retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Portion of the delay that is randomized, from 0.0 (no jitter) to 1.0 (full jitter)
+    pub jitter: f64,
+    /// Decides if the error is worth retrying, by default the ones of `ExecutionErrorKind::is_retryable`
+    /// (a not confirmed transaction only once its blockhash expired, i.e. it cannot land anymore)
+    pub retry_on: RetryPredicate,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+    /// Failed transactions are not retried
+    pub fn none() -> Self {
+        Self::exponential(1, Duration::ZERO)
+    }
+
+    pub fn exponential(max_attempts: usize, base_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            max_delay: Duration::from_secs(60),
+            jitter: 0.5,
+            retry_on: Arc::new(|error| ExecutionErrorKind::classify(error).is_retryable()),
+        }
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_retry_on<F>(mut self, retry_on: F) -> Self
+    where
+        F: Fn(&anyhow::Error) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Arc::new(retry_on);
+        self
+    }
+
+    /// Delay before the retry following the failed attempt (attempts are numbered from 1)
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31) as u32;
+        let delay = self
+            .base_delay
+            .saturating_mul(2_u32.pow(exponent))
+            .min(self.max_delay);
+        // the public field is not clamped by with_jitter
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter > 0.0 {
+            let random: f64 = rand::thread_rng().gen();
+            delay.mul_f64(1.0 - jitter * random)
+        } else {
+            delay
+        }
+    }
+
+    pub(crate) fn should_retry(&self, attempt: usize, error: &anyhow::Error) -> bool {
+        attempt < self.max_attempts && (self.retry_on)(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_delay() {
+        let retry_policy = RetryPolicy::exponential(5, Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(3))
+            .with_jitter(0.0);
+        assert_eq!(retry_policy.delay(1), Duration::from_secs(1));
+        assert_eq!(retry_policy.delay(2), Duration::from_secs(2));
+        assert_eq!(retry_policy.delay(3), Duration::from_secs(3));
+        assert_eq!(retry_policy.delay(100), Duration::from_secs(3));
+
+        let mut retry_policy = RetryPolicy::exponential(5, Duration::from_secs(1));
+        retry_policy.jitter = 3.0;
+        for _ in 0..100 {
+            assert!(retry_policy.delay(1) <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let retry_policy = RetryPolicy::exponential(3, Duration::from_secs(1));
+        let throttled = anyhow!("HTTP status client error (429 Too Many Requests)");
+        let program_error = anyhow!("custom program error: 0x1");
+        let not_confirmed = anyhow!("Transaction not confirmed");
+        assert!(retry_policy.should_retry(1, &throttled));
+        assert!(!retry_policy.should_retry(3, &throttled));
+        assert!(!retry_policy.should_retry(1, &program_error));
+        assert!(!retry_policy.should_retry(1, &not_confirmed));
+        assert!(retry_policy.should_retry(
+            1,
+            &not_confirmed.context("Blockhash expired, the transaction did not land")
+        ));
+        assert!(!RetryPolicy::none().should_retry(1, &throttled));
+    }
+}
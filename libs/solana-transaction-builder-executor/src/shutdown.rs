@@ -0,0 +1,84 @@
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Cancels the token (to be set as `ExecutionConfig::cancellation_token`) on SIGINT or SIGTERM,
+/// the execution stops starting new transactions and returns the report of what was sent,
+/// the in-flight transactions are awaited up to `ExecutionConfig::shutdown_grace_period`.
+pub fn cancel_on_shutdown_signal(cancellation_token: CancellationToken) -> JoinHandle<()> {
+    cancel_on_shutdown(cancellation_token, shutdown_signal())
+}
+
+/// Cancels the token when the shutdown future (e.g. a custom signal or a channel) completes.
+pub fn cancel_on_shutdown<F>(cancellation_token: CancellationToken, shutdown: F) -> JoinHandle<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        tokio::select! {
+            () = shutdown => {
+                info!("Shutdown requested, no new transactions are started");
+                cancellation_token.cancel();
+            }
+            () = cancellation_token.cancelled() => {}
+        }
+    })
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for SIGINT: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!("Failed to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_cancel_on_shutdown() {
+        let cancellation_token = CancellationToken::new();
+        let (sender, receiver) = oneshot::channel::<()>();
+        let task = cancel_on_shutdown(cancellation_token.clone(), async move {
+            let _ = receiver.await;
+        });
+        tokio::task::yield_now().await;
+        assert!(!cancellation_token.is_cancelled());
+
+        sender.send(()).unwrap();
+        task.await.unwrap();
+        assert!(cancellation_token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_elsewhere() {
+        let cancellation_token = CancellationToken::new();
+        let task = cancel_on_shutdown(cancellation_token.clone(), std::future::pending());
+        // the task ends without the shutdown
+        cancellation_token.cancel();
+        task.await.unwrap();
+    }
+}
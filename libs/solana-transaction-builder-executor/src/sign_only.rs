@@ -0,0 +1,57 @@
+use crate::TransactionBuilderExecutionData;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::io::Write;
+
+/// Transaction signed without being broadcast, see `sign_transaction_data`.
+#[derive(Debug, Clone)]
+pub struct SignedTransactionData {
+    pub tx_uuid: String,
+    pub human_index: usize,
+    pub transaction: VersionedTransaction,
+}
+
+impl SignedTransactionData {
+    pub fn signature(&self) -> Signature {
+        self.transaction.signatures[0]
+    }
+
+    /// Base64 encoded wire format of the transaction (as accepted by `sendTransaction`).
+    pub fn to_base64(&self) -> anyhow::Result<String> {
+        Ok(base64::encode(bincode::serialize(&self.transaction)?))
+    }
+}
+
+/// Signs every prepared transaction without broadcasting it.
+/// When no blockhash is provided the latest blockhash of the execution data is used,
+/// pre-signed transactions are returned as they are.
+/// For a durable nonce transaction pass the nonce value as the blockhash
+/// (the transaction has to start with the nonce advance instruction),
+/// or configure the execution data with `with_durable_nonce`.
+pub async fn sign_transaction_data(
+    execution_data: &[TransactionBuilderExecutionData],
+    recent_blockhash: Option<Hash>,
+) -> anyhow::Result<Vec<SignedTransactionData>> {
+    let mut signed_transactions = Vec::with_capacity(execution_data.len());
+    for (index, data) in execution_data.iter().enumerate() {
+        signed_transactions.push(SignedTransactionData {
+            tx_uuid: data.tx_uuid.clone(),
+            human_index: index + 1,
+            transaction: data.sign(recent_blockhash).await?,
+        });
+    }
+    Ok(signed_transactions)
+}
+
+/// Writes the base64 encoded transactions, one per line.
+pub fn write_base64_transactions<W: Write>(
+    signed_transactions: &[SignedTransactionData],
+    mut writer: W,
+) -> anyhow::Result<()> {
+    for signed_transaction in signed_transactions {
+        writeln!(writer, "{}", signed_transaction.to_base64()?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
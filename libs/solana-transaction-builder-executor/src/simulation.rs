@@ -0,0 +1,204 @@
+use crate::{
+    execute_transaction_data_in_sequence_with_config, ExecutionConfig, ExecutionReport,
+    TransactionBuilderExecutionData,
+};
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_transaction_executor::TransactionExecutor;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+#[derive(Debug)]
+pub struct TransactionSimulation {
+    pub tx_uuid: String,
+    pub human_index: usize,
+    /// Error is returned when the simulation could not be run at all (e.g. RPC failure)
+    pub result: anyhow::Result<RpcSimulateTransactionResult>,
+}
+
+impl TransactionSimulation {
+    pub fn is_success(&self) -> bool {
+        matches!(&self.result, Ok(simulation) if simulation.err.is_none())
+    }
+}
+
+/// Outcome of the simulation of a list of transaction data.
+#[derive(Debug, Default)]
+pub struct SimulationReport {
+    pub simulations: Vec<TransactionSimulation>,
+}
+
+impl SimulationReport {
+    pub fn is_success(&self) -> bool {
+        self.simulations
+            .iter()
+            .all(TransactionSimulation::is_success)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &TransactionSimulation> {
+        self.simulations
+            .iter()
+            .filter(|simulation| !simulation.is_success())
+    }
+}
+
+/// Outcome of the simulate-then-send execution,
+/// `execution` is `None` when the simulation report was not approved.
+#[derive(Debug)]
+pub struct TwoPhaseExecutionReport {
+    pub simulation: SimulationReport,
+    pub execution: Option<ExecutionReport>,
+}
+
+/// Simulates the transactions one by one in their order.
+/// Every transaction is simulated against the current on-chain state,
+/// i.e., a transaction depending on the changes of a previous one may fail in the simulation.
+#[instrument(name = "simulation_run", skip_all, fields(transactions = execution_data.len()))]
+pub async fn simulate_transaction_data(
+    execution_data: &[TransactionBuilderExecutionData],
+) -> SimulationReport {
+    let mut report = SimulationReport::default();
+    for (index, data) in execution_data.iter().enumerate() {
+        let human_index = index + 1;
+        let result = simulate(data).await;
+        debug!(
+            "Simulated the transaction {human_index}/{}: {result:?}",
+            data.tx_uuid
+        );
+        report.simulations.push(TransactionSimulation {
+            tx_uuid: data.tx_uuid.clone(),
+            human_index,
+            result,
+        });
+    }
+    report
+}
+
+/// Simulates all the transactions first and passes the simulation report for the approval,
+/// only when approved the transactions are executed in sequence.
+pub async fn execute_transaction_data_simulated_first<F, Fut>(
+    transaction_executor: Arc<TransactionExecutor>,
+    execution_data: &[TransactionBuilderExecutionData],
+    config: &ExecutionConfig,
+    approve: F,
+) -> TwoPhaseExecutionReport
+where
+    F: FnOnce(&SimulationReport) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let simulation = simulate_transaction_data(execution_data).await;
+    if !approve(&simulation).await {
+        debug!("Simulation report was not approved, no transaction is sent");
+        return TwoPhaseExecutionReport {
+            simulation,
+            execution: None,
+        };
+    }
+    let execution = execute_transaction_data_in_sequence_with_config(
+        transaction_executor,
+        execution_data,
+        config,
+    )
+    .await;
+    TwoPhaseExecutionReport {
+        simulation,
+        execution: Some(execution),
+    }
+}
+
+async fn simulate(
+    execution_data: &TransactionBuilderExecutionData,
+) -> anyhow::Result<RpcSimulateTransactionResult> {
+    let transaction = execution_data.sign(None).await?;
+    let simulation = execution_data
+        .rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                replace_recent_blockhash: true,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await?;
+    Ok(simulation.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::mock_sender::Mocks;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_builder::TransactionBuilder;
+    use solana_transaction_executor::PriorityFeePolicy;
+
+    fn execution_data(rpc_client: RpcClient) -> TransactionBuilderExecutionData {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        TransactionBuilderExecutionData::new(
+            builder.build_one(),
+            Arc::new(rpc_client),
+            PriorityFeePolicy::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_simulate_transaction_data() {
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "err": "AccountNotFound",
+                    "logs": ["Program 11111111111111111111111111111111 failed"],
+                    "accounts": null,
+                    "unitsConsumed": 150,
+                    "returnData": null,
+                },
+            }),
+        );
+        let execution_data = vec![
+            execution_data(RpcClient::new_mock("succeeds".to_string())),
+            execution_data(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            )),
+            // the blockhash cannot be fetched, the simulation is not run
+            execution_data(RpcClient::new_mock("fails".to_string())),
+        ];
+
+        let report = simulate_transaction_data(&execution_data).await;
+        assert_eq!(report.simulations.len(), 3);
+        for (index, (simulation, data)) in
+            report.simulations.iter().zip(&execution_data).enumerate()
+        {
+            assert_eq!(simulation.human_index, index + 1);
+            assert_eq!(simulation.tx_uuid, data.tx_uuid);
+        }
+        assert!(report.simulations[0].is_success());
+        let failed = report.simulations[1].result.as_ref().unwrap();
+        assert_eq!(failed.err, Some(TransactionError::AccountNotFound));
+        assert_eq!(failed.units_consumed, Some(150));
+        assert!(report.simulations[2].result.is_err());
+
+        assert!(!report.is_success());
+        let failed_indexes: Vec<usize> = report
+            .failed()
+            .map(|simulation| simulation.human_index)
+            .collect();
+        assert_eq!(failed_indexes, vec![2, 3]);
+    }
+}
@@ -0,0 +1,98 @@
+use solana_sdk::clock::DEFAULT_MS_PER_SLOT;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Spreads the transaction submissions so at most a given number of them is sent
+/// within the duration of a slot, our own bursts do not compete for the block space
+/// and do not inflate the priority fees. It may be shared by more executions.
+#[derive(Debug)]
+pub struct SlotPacer {
+    max_per_slot: u32,
+    slot_duration: Duration,
+    window: Mutex<PacingWindow>,
+}
+
+#[derive(Debug)]
+struct PacingWindow {
+    started_at: Instant,
+    sent: u32,
+}
+
+impl SlotPacer {
+    pub fn new(max_per_slot: u32) -> Self {
+        Self {
+            max_per_slot: max_per_slot.max(1),
+            slot_duration: Duration::from_millis(DEFAULT_MS_PER_SLOT),
+            window: Mutex::new(PacingWindow {
+                started_at: Instant::now(),
+                sent: 0,
+            }),
+        }
+    }
+
+    /// Expected slot duration of the cluster, 400ms by default
+    pub fn with_slot_duration(mut self, slot_duration: Duration) -> Self {
+        self.slot_duration = slot_duration.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Waits until a send is permitted within the current slot
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Counts the send in the current slot window, otherwise returns the time to the next window
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut window = self.window.lock().expect("Slot pacer lock poisoned");
+        let elapsed = window.started_at.elapsed();
+        if elapsed >= self.slot_duration {
+            window.started_at = Instant::now();
+            window.sent = 0;
+        }
+        if window.sent < self.max_per_slot {
+            window.sent += 1;
+            None
+        } else {
+            Some(self.slot_duration.saturating_sub(elapsed))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slot_window() {
+        let slot_pacer = SlotPacer::new(2);
+        assert_eq!(slot_pacer.try_acquire(), None);
+        assert_eq!(slot_pacer.try_acquire(), None);
+        assert_eq!(slot_pacer.try_acquire(), Some(Duration::from_millis(400)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let wait = slot_pacer.try_acquire().unwrap();
+        assert!(wait <= Duration::from_millis(300));
+        assert!(wait > Duration::from_millis(290));
+
+        // the next slot window starts over
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(slot_pacer.try_acquire(), None);
+        assert_eq!(slot_pacer.try_acquire(), None);
+        assert!(slot_pacer.try_acquire().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_waits_for_next_slot() {
+        let slot_pacer = SlotPacer::new(0).with_slot_duration(Duration::from_secs(1));
+        let started_at = Instant::now();
+        // at least one send per slot
+        slot_pacer.acquire().await;
+        assert_eq!(started_at.elapsed(), Duration::ZERO);
+        slot_pacer.acquire().await;
+        slot_pacer.acquire().await;
+        assert!(started_at.elapsed() >= Duration::from_secs(2));
+        assert!(started_at.elapsed() < Duration::from_secs(3));
+    }
+}
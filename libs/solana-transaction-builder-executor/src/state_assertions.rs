@@ -0,0 +1,35 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_builder::{AccountAssertion, AccountAssertionFailed};
+
+// getMultipleAccounts limit of the RPC nodes
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// Accounts the assertions refer to, in the order of the assertions
+pub(crate) async fn fetch_assertion_accounts(
+    rpc_client: &RpcClient,
+    assertions: &[AccountAssertion],
+) -> anyhow::Result<Vec<Option<Account>>> {
+    let pubkeys: Vec<Pubkey> = assertions.iter().map(|a| *a.account()).collect();
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(rpc_client.get_multiple_accounts(chunk).await?);
+    }
+    Ok(accounts)
+}
+
+pub(crate) fn check_assertions(
+    assertions: &[AccountAssertion],
+    previous_accounts: Option<&[Option<Account>]>,
+    current_accounts: &[Option<Account>],
+) -> Result<(), AccountAssertionFailed> {
+    for (index, assertion) in assertions.iter().enumerate() {
+        let previous = previous_accounts
+            .and_then(|accounts| accounts.get(index))
+            .and_then(Option::as_ref);
+        let current = current_accounts.get(index).and_then(Option::as_ref);
+        assertion.check(previous, current)?;
+    }
+    Ok(())
+}
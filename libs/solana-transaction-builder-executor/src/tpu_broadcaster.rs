@@ -0,0 +1,50 @@
+use crate::TransactionBroadcaster;
+use futures::future::BoxFuture;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonblocking::tpu_client::TpuClient;
+use solana_client::tpu_client::TpuClientConfig;
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+
+/// Sends the transactions over QUIC directly to the TPU of the current and upcoming leaders.
+pub struct TpuBroadcaster {
+    tpu_client: TpuClient<QuicPool, QuicConnectionManager, QuicConfig>,
+}
+
+impl TpuBroadcaster {
+    /// The RPC client and the websocket url are used to track the leader schedule.
+    pub async fn new(rpc_client: Arc<RpcClient>, websocket_url: &str) -> anyhow::Result<Self> {
+        Self::new_with_config(rpc_client, websocket_url, TpuClientConfig::default()).await
+    }
+
+    pub async fn new_with_config(
+        rpc_client: Arc<RpcClient>,
+        websocket_url: &str,
+        config: TpuClientConfig,
+    ) -> anyhow::Result<Self> {
+        let tpu_client = TpuClient::new(
+            "solana-transaction-builder",
+            rpc_client,
+            websocket_url,
+            config,
+        )
+        .await?;
+        Ok(Self { tpu_client })
+    }
+}
+
+impl TransactionBroadcaster for TpuBroadcaster {
+    fn broadcast<'a>(
+        &'a self,
+        transaction: &'a VersionedTransaction,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let wire_transaction = bincode::serialize(transaction)?;
+            self.tpu_client
+                .try_send_wire_transaction(wire_transaction)
+                .await?;
+            Ok(())
+        })
+    }
+}
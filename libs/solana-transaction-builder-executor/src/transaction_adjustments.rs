@@ -0,0 +1,245 @@
+use crate::TransactionSpend;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Keypair;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_builder::{
+    compute_unit_price, estimate_fee_lamports, ComputeBudgetSetting, PreparedTransaction,
+    SignedTransaction, LAMPORTS_PER_SIGNATURE,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// Changes of the prepared transaction applied when the transaction is built
+#[derive(Debug, Default)]
+pub(crate) struct TransactionAdjustments {
+    pub(crate) compute_unit_price: Option<u64>,
+    // appended at the end of the transaction, e.g. a tip transfer
+    pub(crate) appended_instructions: Vec<Instruction>,
+    // lamports transferred by the appended tip instruction
+    pub(crate) tip_lamports: u64,
+    // the transaction is signed as a v0 one when there are lookup tables
+    pub(crate) lookup_tables: Vec<AddressLookupTableAccount>,
+    // prepended as the very first instruction of a durable nonce transaction
+    pub(crate) nonce_advance: Option<Instruction>,
+    // replaces the fee payer of the transaction, e.g. one of the fee payer pool
+    pub(crate) fee_payer: Option<Arc<Keypair>>,
+}
+
+impl TransactionAdjustments {
+    pub(crate) fn apply<'a>(
+        &self,
+        prepared_transaction: &'a PreparedTransaction,
+    ) -> Cow<'a, PreparedTransaction> {
+        let mut adjusted = Cow::Borrowed(prepared_transaction);
+        if let Some(micro_lamports) = self.compute_unit_price {
+            adjusted = Cow::Owned(adjusted.with_compute_budget_setting(
+                ComputeBudgetSetting::ComputeUnitPrice(micro_lamports),
+            ));
+        }
+        for instruction in &self.appended_instructions {
+            adjusted = Cow::Owned(adjusted.with_appended_instruction(instruction.clone(), None));
+        }
+        if let Some(nonce_advance) = &self.nonce_advance {
+            adjusted = Cow::Owned(adjusted.with_prepended_instruction(
+                nonce_advance.clone(),
+                Some("Advance nonce".to_string()),
+            ));
+        }
+        // swapped last, the previous fee payer is kept when it signs e.g. the nonce advance
+        if let Some(fee_payer) = &self.fee_payer {
+            adjusted = Cow::Owned(adjusted.with_fee_payer(fee_payer.clone()));
+        }
+        adjusted
+    }
+
+    pub(crate) async fn sign(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+        recent_blockhash: Hash,
+    ) -> anyhow::Result<VersionedTransaction> {
+        let adjusted = self.apply(prepared_transaction);
+        if adjusted.requires_remote_signatures() {
+            let message = if self.lookup_tables.is_empty() {
+                adjusted.legacy_message(recent_blockhash)
+            } else {
+                adjusted.v0_message(recent_blockhash, &self.lookup_tables)?
+            };
+            adjusted.sign_message(message).await
+        } else if self.lookup_tables.is_empty() {
+            Ok(adjusted.signed_versioned_transaction(recent_blockhash)?)
+        } else {
+            adjusted.signed_v0_transaction(recent_blockhash, &self.lookup_tables)
+        }
+    }
+
+    // Compute unit price of the adjusted transaction
+    pub(crate) fn compute_unit_price(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+    ) -> Option<u64> {
+        self.compute_unit_price
+            .or_else(|| compute_unit_price(&prepared_transaction.instructions()))
+    }
+
+    // Fees and the tip paid when the adjusted transaction lands
+    pub(crate) fn estimate_spend(
+        &self,
+        prepared_transaction: &PreparedTransaction,
+    ) -> TransactionSpend {
+        let adjusted = self.apply(prepared_transaction);
+        let base_fee_lamports =
+            u64::from(adjusted.transaction.message.header.num_required_signatures)
+                * LAMPORTS_PER_SIGNATURE;
+        TransactionSpend {
+            base_fee_lamports,
+            priority_fee_lamports: estimate_fee_lamports(&adjusted)
+                .saturating_sub(base_fee_lamports),
+            tip_lamports: self.tip_lamports,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DurableNonce, JitoTip};
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signer;
+    use solana_transaction_builder::TransactionBuilder;
+
+    #[test]
+    fn test_fee_payer_with_durable_nonce() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        let prepared_transaction = builder.build_one();
+
+        let durable_nonce = DurableNonce {
+            nonce_account: Pubkey::new_unique(),
+            authority: fee_payer.pubkey(),
+        };
+        let pool_fee_payer = Arc::new(Keypair::new());
+        let adjustments = TransactionAdjustments {
+            nonce_advance: Some(durable_nonce.advance_instruction()),
+            fee_payer: Some(pool_fee_payer.clone()),
+            ..Default::default()
+        };
+        let adjusted = adjustments.apply(&prepared_transaction);
+        assert_eq!(
+            adjusted.required_signers(),
+            vec![pool_fee_payer.pubkey(), fee_payer.pubkey()]
+        );
+        assert_eq!(
+            adjusted.instructions()[0],
+            durable_nonce.advance_instruction()
+        );
+        assert!(adjusted.signed_transaction(Hash::default()).is_ok());
+    }
+
+    #[test]
+    fn test_compute_unit_price() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(ComputeBudgetInstruction::set_compute_unit_price(5))
+            .unwrap();
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        let prepared_transaction = builder.build_one();
+
+        let adjustments = TransactionAdjustments::default();
+        assert_eq!(
+            adjustments.compute_unit_price(&prepared_transaction),
+            Some(5)
+        );
+        let adjustments = TransactionAdjustments {
+            compute_unit_price: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(
+            adjustments.compute_unit_price(&prepared_transaction),
+            Some(7)
+        );
+        assert_eq!(
+            compute_unit_price(&adjustments.apply(&prepared_transaction).instructions()),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_estimate_spend() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder
+            .add_instruction(ComputeBudgetInstruction::set_compute_unit_limit(100_000))
+            .unwrap();
+        builder
+            .add_instruction(solana_sdk::system_instruction::transfer(
+                &fee_payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            ))
+            .unwrap();
+        let prepared_transaction = builder.build_one();
+
+        assert_eq!(
+            TransactionAdjustments::default().estimate_spend(&prepared_transaction),
+            TransactionSpend {
+                base_fee_lamports: 5_000,
+                priority_fee_lamports: 0,
+                tip_lamports: 0,
+            }
+        );
+
+        // 100k compute units at 10k micro-lamports
+        let adjustments = TransactionAdjustments {
+            compute_unit_price: Some(10_000),
+            appended_instructions: vec![JitoTip::tip_instruction(&fee_payer.pubkey(), 2_000)],
+            tip_lamports: 2_000,
+            ..Default::default()
+        };
+        let spend = adjustments.estimate_spend(&prepared_transaction);
+        assert_eq!(
+            spend,
+            TransactionSpend {
+                base_fee_lamports: 5_000,
+                priority_fee_lamports: 1_000,
+                tip_lamports: 2_000,
+            }
+        );
+        assert_eq!(spend.total_lamports(), 8_000);
+
+        // the nonce authority signs along with the pool fee payer
+        let adjustments = TransactionAdjustments {
+            nonce_advance: Some(
+                DurableNonce {
+                    nonce_account: Pubkey::new_unique(),
+                    authority: fee_payer.pubkey(),
+                }
+                .advance_instruction(),
+            ),
+            fee_payer: Some(Arc::new(Keypair::new())),
+            ..Default::default()
+        };
+        assert_eq!(
+            adjustments
+                .estimate_spend(&prepared_transaction)
+                .base_fee_lamports,
+            10_000
+        );
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(feature = "async")]
+use solana_client::nonblocking::rpc_client::RpcClient;
+#[cfg(feature = "async")]
+use solana_client::rpc_config::RpcTransactionConfig;
+#[cfg(feature = "async")]
+use solana_sdk::commitment_config::CommitmentConfig;
+#[cfg(feature = "async")]
+use solana_sdk::signature::Signature;
+#[cfg(feature = "async")]
+use solana_transaction_status::UiTransactionEncoding;
+
+/// Cost of a confirmed transaction as recorded in its on-chain transaction meta,
+/// fetched when `ExecutionConfig::fetch_transaction_costs` is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransactionCost {
+    /// Unknown when the RPC node does not report it
+    pub compute_units_consumed: Option<u64>,
+    /// Base and priority fees paid by the fee payer
+    pub fee_lamports: u64,
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn fetch_transaction_cost(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+) -> anyhow::Result<TransactionCost> {
+    // processed transactions cannot be fetched
+    let commitment = if rpc_client.commitment().is_at_least_confirmed() {
+        rpc_client.commitment()
+    } else {
+        CommitmentConfig::confirmed()
+    };
+    let transaction = rpc_client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow::anyhow!("No meta of the transaction {signature}"))?;
+    Ok(TransactionCost {
+        compute_units_consumed: meta.compute_units_consumed.into(),
+        fee_lamports: meta.fee,
+    })
+}
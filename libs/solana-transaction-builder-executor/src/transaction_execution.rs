@@ -0,0 +1,204 @@
+use crate::{
+    execute_transaction_data_in_parallel_with_config,
+    execute_transaction_data_in_sequence_with_config, ExecutionConfig, ExecutionReport,
+    SignedTransactionData, SkipReason, TransactionBuilderExecutionData,
+};
+use futures::future::BoxFuture;
+use solana_sdk::hash::Hash;
+use solana_transaction_executor::TransactionExecutor;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Execution of the transaction data, implemented by the transaction executor
+/// and by `MockTransactionExecution` for unit tests of the code producing the transactions.
+pub trait TransactionExecution: Send + Sync {
+    fn execute_in_sequence<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport>;
+
+    fn execute_in_parallel<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport>;
+}
+
+impl TransactionExecution for Arc<TransactionExecutor> {
+    fn execute_in_sequence<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport> {
+        Box::pin(execute_transaction_data_in_sequence_with_config(
+            self.clone(),
+            execution_data,
+            config,
+        ))
+    }
+
+    fn execute_in_parallel<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport> {
+        Box::pin(execute_transaction_data_in_parallel_with_config(
+            self.clone(),
+            execution_data,
+            config,
+        ))
+    }
+}
+
+/// In-memory execution signing the transactions with a fixed blockhash and recording them
+/// instead of sending them, no RPC is contacted. Transactions of the failing tx_uuids are
+/// recorded as failed (stopping the sequence with `ExecutionConfig::fail_on_first_error`).
+#[derive(Debug, Default)]
+pub struct MockTransactionExecution {
+    recent_blockhash: Hash,
+    failing_tx_uuids: HashSet<String>,
+    sent: Mutex<Vec<SignedTransactionData>>,
+}
+
+impl MockTransactionExecution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_recent_blockhash(mut self, recent_blockhash: Hash) -> Self {
+        self.recent_blockhash = recent_blockhash;
+        self
+    }
+
+    pub fn with_failing_tx_uuid<S: Into<String>>(mut self, tx_uuid: S) -> Self {
+        self.failing_tx_uuids.insert(tx_uuid.into());
+        self
+    }
+
+    /// Transactions that would have been sent, in the order of execution.
+    pub fn sent_transactions(&self) -> Vec<SignedTransactionData> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+
+    async fn execute(
+        &self,
+        execution_data: &[TransactionBuilderExecutionData],
+        config: &ExecutionConfig,
+        is_sequence: bool,
+    ) -> ExecutionReport {
+        let mut report = ExecutionReport::default();
+        for (index, data) in execution_data.iter().enumerate() {
+            let human_index = index + 1;
+            if config.is_cancelled() {
+                report.add_skipped(data, human_index, SkipReason::Cancelled);
+                continue;
+            }
+            if is_sequence && config.stops_on(&report.errors) {
+                report.add_skipped(data, human_index, SkipReason::PreviousFailure);
+                continue;
+            }
+            let transaction = match data.sign(Some(self.recent_blockhash)).await {
+                Ok(transaction) => transaction,
+                Err(err) => {
+                    report.add_failed(
+                        data,
+                        human_index,
+                        err,
+                        "Mock execution failed to sign the transaction".to_string(),
+                    );
+                    continue;
+                }
+            };
+            let signed_transaction = SignedTransactionData {
+                tx_uuid: data.tx_uuid.clone(),
+                human_index,
+                transaction,
+            };
+            let signature = signed_transaction.signature();
+            self.sent.lock().unwrap().push(signed_transaction);
+            if self.failing_tx_uuids.contains(&data.tx_uuid) {
+                report.add_failed(
+                    data,
+                    human_index,
+                    anyhow::anyhow!("Mock failure of transaction {signature}"),
+                    "Mock execution failed the transaction".to_string(),
+                );
+            } else {
+                report.add_executed(data, human_index, signature);
+            }
+        }
+        report
+    }
+}
+
+impl TransactionExecution for MockTransactionExecution {
+    fn execute_in_sequence<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport> {
+        Box::pin(self.execute(execution_data, config, true))
+    }
+
+    fn execute_in_parallel<'a>(
+        &'a self,
+        execution_data: &'a [TransactionBuilderExecutionData],
+        config: &'a ExecutionConfig,
+    ) -> BoxFuture<'a, ExecutionReport> {
+        Box::pin(self.execute(execution_data, config, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use solana_transaction_builder::TransactionBuilder;
+    use solana_transaction_executor::PriorityFeePolicy;
+
+    #[tokio::test]
+    async fn test_mock_execution() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        for _ in 0..3 {
+            builder
+                .add_instruction(system_instruction::transfer(
+                    &fee_payer.pubkey(),
+                    &Keypair::new().pubkey(),
+                    1,
+                ))
+                .unwrap();
+            builder.finish_instruction_pack();
+        }
+        let rpc_client = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let execution_data: Vec<_> = builder
+            .sequence()
+            .map(|prepared_transaction| {
+                TransactionBuilderExecutionData::new(
+                    prepared_transaction,
+                    rpc_client.clone(),
+                    PriorityFeePolicy::default(),
+                )
+            })
+            .collect();
+        let mock =
+            MockTransactionExecution::new().with_failing_tx_uuid(execution_data[1].tx_uuid.clone());
+        let config = ExecutionConfig {
+            fail_on_first_error: true,
+            ..Default::default()
+        };
+
+        let report = mock.execute_in_sequence(&execution_data, &config).await;
+        assert_eq!(report.executed.len(), 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(mock.sent_transactions().len(), 2);
+    }
+}
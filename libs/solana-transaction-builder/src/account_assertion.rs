@@ -0,0 +1,149 @@
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+// amount field of the SPL token account layout
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// Expected state of an account, the executor verifies it against the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountAssertion {
+    LamportsAtLeast {
+        account: Pubkey,
+        lamports: u64,
+    },
+    LamportsAtMost {
+        account: Pubkey,
+        lamports: u64,
+    },
+    /// SPL token account holds at least the amount
+    TokenAmountAtLeast {
+        token_account: Pubkey,
+        amount: u64,
+    },
+    /// SPL token account amount is higher than before the transaction was sent
+    TokenAmountIncreased {
+        token_account: Pubkey,
+    },
+    /// Account does not exist (e.g. it was closed)
+    NotExists {
+        account: Pubkey,
+    },
+    Exists {
+        account: Pubkey,
+    },
+    OwnerIs {
+        account: Pubkey,
+        owner: Pubkey,
+    },
+    /// Account data starts with the bytes, e.g. an Anchor discriminator
+    DataStartsWith {
+        account: Pubkey,
+        prefix: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("Account assertion {assertion:?} failed: {reason}")]
+pub struct AccountAssertionFailed {
+    pub assertion: AccountAssertion,
+    pub reason: String,
+}
+
+impl AccountAssertion {
+    pub fn account(&self) -> &Pubkey {
+        match self {
+            Self::LamportsAtLeast { account, .. }
+            | Self::LamportsAtMost { account, .. }
+            | Self::NotExists { account }
+            | Self::Exists { account }
+            | Self::OwnerIs { account, .. }
+            | Self::DataStartsWith { account, .. } => account,
+            Self::TokenAmountAtLeast { token_account, .. }
+            | Self::TokenAmountIncreased { token_account } => token_account,
+        }
+    }
+
+    /// The assertion compares the account with its state before the transaction was sent.
+    pub fn needs_previous_state(&self) -> bool {
+        matches!(self, Self::TokenAmountIncreased { .. })
+    }
+
+    /// Checks the current state of the account, `previous` is its state
+    /// before the transaction was sent (when `needs_previous_state`).
+    pub fn check(
+        &self,
+        previous: Option<&Account>,
+        current: Option<&Account>,
+    ) -> Result<(), AccountAssertionFailed> {
+        let failed = |reason: String| {
+            Err(AccountAssertionFailed {
+                assertion: self.clone(),
+                reason,
+            })
+        };
+        match (self, current) {
+            (Self::NotExists { .. }, None) => Ok(()),
+            (Self::NotExists { .. }, Some(account)) => {
+                failed(format!("account exists with {} lamports", account.lamports))
+            }
+            (_, None) => failed("account does not exist".to_string()),
+            (Self::Exists { .. }, Some(_)) => Ok(()),
+            (Self::OwnerIs { owner, .. }, Some(account)) => {
+                if account.owner == *owner {
+                    Ok(())
+                } else {
+                    failed(format!("account is owned by {}", account.owner))
+                }
+            }
+            (Self::DataStartsWith { prefix, .. }, Some(account)) => {
+                if account.data.starts_with(prefix) {
+                    Ok(())
+                } else {
+                    failed(format!(
+                        "account data starts with {:?}",
+                        &account.data[..prefix.len().min(account.data.len())]
+                    ))
+                }
+            }
+            (Self::LamportsAtLeast { lamports, .. }, Some(account)) => {
+                if account.lamports >= *lamports {
+                    Ok(())
+                } else {
+                    failed(format!("account has {} lamports", account.lamports))
+                }
+            }
+            (Self::LamportsAtMost { lamports, .. }, Some(account)) => {
+                if account.lamports <= *lamports {
+                    Ok(())
+                } else {
+                    failed(format!("account has {} lamports", account.lamports))
+                }
+            }
+            (Self::TokenAmountAtLeast { amount, .. }, Some(account)) => {
+                match token_amount(account) {
+                    Some(current_amount) if current_amount >= *amount => Ok(()),
+                    Some(current_amount) => failed(format!("token amount is {current_amount}")),
+                    None => failed("not a token account".to_string()),
+                }
+            }
+            (Self::TokenAmountIncreased { .. }, Some(account)) => {
+                let previous_amount = previous.and_then(token_amount).unwrap_or(0);
+                match token_amount(account) {
+                    Some(current_amount) if current_amount > previous_amount => Ok(()),
+                    Some(current_amount) => failed(format!(
+                        "token amount {current_amount} did not increase from {previous_amount}"
+                    )),
+                    None => failed("not a token account".to_string()),
+                }
+            }
+        }
+    }
+}
+
+fn token_amount(account: &Account) -> Option<u64> {
+    let amount = account
+        .data
+        .get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)?;
+    Some(u64::from_le_bytes(amount.try_into().ok()?))
+}
@@ -0,0 +1,276 @@
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::Instruction;
+
+/// Maximum compute unit limit a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Compute units the runtime assigns to an instruction when no limit is requested
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Maximum heap frame a transaction may request
+pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+/// Maximum loaded accounts data size a transaction may request
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+
+const REQUEST_HEAP_FRAME_DISCRIMINATOR: u8 = 1;
+const SET_COMPUTE_UNIT_LIMIT_DISCRIMINATOR: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE_DISCRIMINATOR: u8 = 3;
+const SET_LOADED_ACCOUNTS_DATA_SIZE_LIMIT_DISCRIMINATOR: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetSetting {
+    ComputeUnitLimit(u32),
+    ComputeUnitPrice(u64),
+    /// Heap frame size in bytes
+    HeapFrame(u32),
+    /// Loaded accounts data size limit in bytes
+    LoadedAccountsDataSizeLimit(u32),
+}
+
+impl ComputeBudgetSetting {
+    pub fn from_instruction(instruction: &Instruction) -> Option<Self> {
+        if !compute_budget::check_id(&instruction.program_id) {
+            return None;
+        }
+        let (discriminator, data) = instruction.data.split_first()?;
+        match *discriminator {
+            SET_COMPUTE_UNIT_LIMIT_DISCRIMINATOR => Some(Self::ComputeUnitLimit(
+                u32::from_le_bytes(data.get(..4)?.try_into().ok()?),
+            )),
+            SET_COMPUTE_UNIT_PRICE_DISCRIMINATOR => Some(Self::ComputeUnitPrice(
+                u64::from_le_bytes(data.get(..8)?.try_into().ok()?),
+            )),
+            REQUEST_HEAP_FRAME_DISCRIMINATOR => Some(Self::HeapFrame(u32::from_le_bytes(
+                data.get(..4)?.try_into().ok()?,
+            ))),
+            SET_LOADED_ACCOUNTS_DATA_SIZE_LIMIT_DISCRIMINATOR => {
+                Some(Self::LoadedAccountsDataSizeLimit(u32::from_le_bytes(
+                    data.get(..4)?.try_into().ok()?,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_instruction(self) -> Instruction {
+        match self {
+            Self::ComputeUnitLimit(units) => {
+                ComputeBudgetInstruction::set_compute_unit_limit(units)
+            }
+            Self::ComputeUnitPrice(micro_lamports) => {
+                ComputeBudgetInstruction::set_compute_unit_price(micro_lamports)
+            }
+            Self::HeapFrame(bytes) => ComputeBudgetInstruction::request_heap_frame(bytes),
+            Self::LoadedAccountsDataSizeLimit(bytes) => {
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes)
+            }
+        }
+    }
+
+    /// Both settings are set by the same kind of compute-budget instruction.
+    pub fn is_same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Compute unit limit requested by the instructions, or the runtime default
+/// for the non compute-budget instructions when no limit is requested.
+pub fn estimate_compute_unit_limit(instructions: &[Instruction]) -> u32 {
+    let mut compute_unit_limit = None;
+    let mut instructions_count: u32 = 0;
+    for instruction in instructions {
+        match ComputeBudgetSetting::from_instruction(instruction) {
+            Some(ComputeBudgetSetting::ComputeUnitLimit(units)) => compute_unit_limit = Some(units),
+            Some(_) => {}
+            None => instructions_count += 1,
+        }
+    }
+    compute_unit_limit
+        .unwrap_or(instructions_count.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT))
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Compute unit price (in micro-lamports) requested by the instructions.
+pub fn compute_unit_price(instructions: &[Instruction]) -> Option<u64> {
+    instructions.iter().find_map(|instruction| {
+        match ComputeBudgetSetting::from_instruction(instruction) {
+            Some(ComputeBudgetSetting::ComputeUnitPrice(micro_lamports)) => Some(micro_lamports),
+            _ => None,
+        }
+    })
+}
+
+/// Flattens instruction packs (instructions with their context, e.g. description) into a single instruction list.
+/// When more packs are combined, their compute-budget instructions are merged into a single
+/// SetComputeUnitLimit/SetComputeUnitPrice pair placed at the beginning of the list:
+/// the limits are summed (a pack with no limit counts with the runtime default per instruction)
+/// and the highest price is taken. Likewise the loaded accounts data size limits are summed
+/// (a pack with no limit counts with the runtime maximum) and the largest heap frame is taken.
+pub(crate) fn combine_instruction_packs<C: Clone + Default>(
+    packs: &[Vec<(Instruction, C)>],
+) -> (Vec<Instruction>, Vec<C>) {
+    let has_compute_budget = packs
+        .iter()
+        .flatten()
+        .any(|(instruction, _)| ComputeBudgetSetting::from_instruction(instruction).is_some());
+    if packs.len() < 2 || !has_compute_budget {
+        return packs.iter().flatten().cloned().unzip();
+    }
+
+    let mut instructions = Vec::new();
+    let mut contexts = Vec::new();
+    let mut has_compute_unit_limit = false;
+    let mut compute_unit_limit: u32 = 0;
+    let mut compute_unit_price: Option<u64> = None;
+    let mut heap_frame: Option<u32> = None;
+    let mut has_loaded_accounts_data_size_limit = false;
+    let mut loaded_accounts_data_size_limit: u32 = 0;
+    for pack in packs {
+        let mut pack_compute_unit_limit: Option<u32> = None;
+        let mut pack_loaded_accounts_data_size_limit: Option<u32> = None;
+        let mut pack_instructions_count: u32 = 0;
+        for (instruction, context) in pack {
+            match ComputeBudgetSetting::from_instruction(instruction) {
+                Some(ComputeBudgetSetting::ComputeUnitLimit(units)) => {
+                    pack_compute_unit_limit = Some(units)
+                }
+                Some(ComputeBudgetSetting::ComputeUnitPrice(micro_lamports)) => {
+                    compute_unit_price = compute_unit_price.max(Some(micro_lamports))
+                }
+                Some(ComputeBudgetSetting::HeapFrame(bytes)) => {
+                    heap_frame = heap_frame.max(Some(bytes))
+                }
+                Some(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(bytes)) => {
+                    pack_loaded_accounts_data_size_limit = Some(bytes)
+                }
+                None => {
+                    pack_instructions_count += 1;
+                    instructions.push(instruction.clone());
+                    contexts.push(context.clone());
+                }
+            }
+        }
+        has_compute_unit_limit |= pack_compute_unit_limit.is_some();
+        compute_unit_limit = compute_unit_limit.saturating_add(pack_compute_unit_limit.unwrap_or(
+            pack_instructions_count.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT),
+        ));
+        has_loaded_accounts_data_size_limit |= pack_loaded_accounts_data_size_limit.is_some();
+        loaded_accounts_data_size_limit = loaded_accounts_data_size_limit.saturating_add(
+            pack_loaded_accounts_data_size_limit.unwrap_or(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+        );
+    }
+
+    let mut compute_budget_settings = Vec::new();
+    if has_compute_unit_limit {
+        compute_budget_settings.push(ComputeBudgetSetting::ComputeUnitLimit(
+            compute_unit_limit.min(MAX_COMPUTE_UNIT_LIMIT),
+        ));
+    }
+    if let Some(micro_lamports) = compute_unit_price {
+        compute_budget_settings.push(ComputeBudgetSetting::ComputeUnitPrice(micro_lamports));
+    }
+    if let Some(bytes) = heap_frame {
+        compute_budget_settings.push(ComputeBudgetSetting::HeapFrame(
+            bytes.min(MAX_HEAP_FRAME_BYTES),
+        ));
+    }
+    if has_loaded_accounts_data_size_limit {
+        compute_budget_settings.push(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(
+            loaded_accounts_data_size_limit.min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+        ));
+    }
+    let settings_count = compute_budget_settings.len();
+    instructions.splice(
+        0..0,
+        compute_budget_settings
+            .into_iter()
+            .map(ComputeBudgetSetting::to_instruction),
+    );
+    contexts.splice(0..0, std::iter::repeat(C::default()).take(settings_count));
+    (instructions, contexts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_combine_compute_budget_instructions() {
+        let ix = || {
+            (
+                Instruction::new_with_bytes(Pubkey::default(), &[], vec![]),
+                None::<String>,
+            )
+        };
+        let budget = |setting: ComputeBudgetSetting| (setting.to_instruction(), None::<String>);
+        let packs = vec![
+            vec![
+                budget(ComputeBudgetSetting::ComputeUnitLimit(300_000)),
+                budget(ComputeBudgetSetting::ComputeUnitPrice(10)),
+                budget(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(500)),
+                ix(),
+            ],
+            vec![
+                budget(ComputeBudgetSetting::ComputeUnitPrice(50)),
+                budget(ComputeBudgetSetting::HeapFrame(64 * 1024)),
+                budget(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(1_000)),
+                ix(),
+            ],
+            vec![
+                budget(ComputeBudgetSetting::ComputeUnitLimit(100_000)),
+                budget(ComputeBudgetSetting::HeapFrame(32 * 1024)),
+                budget(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(2_000)),
+                ix(),
+                ix(),
+            ],
+        ];
+        let (instructions, descriptions) = combine_instruction_packs(&packs);
+        assert_eq!(instructions.len(), 8);
+        assert_eq!(descriptions.len(), 8);
+        assert_eq!(
+            ComputeBudgetSetting::from_instruction(&instructions[0]),
+            Some(ComputeBudgetSetting::ComputeUnitLimit(600_000))
+        );
+        assert_eq!(
+            ComputeBudgetSetting::from_instruction(&instructions[1]),
+            Some(ComputeBudgetSetting::ComputeUnitPrice(50))
+        );
+        assert_eq!(
+            ComputeBudgetSetting::from_instruction(&instructions[2]),
+            Some(ComputeBudgetSetting::HeapFrame(64 * 1024))
+        );
+        assert_eq!(
+            ComputeBudgetSetting::from_instruction(&instructions[3]),
+            Some(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(3_500))
+        );
+        assert!(instructions[4..]
+            .iter()
+            .all(|instruction| ComputeBudgetSetting::from_instruction(instruction).is_none()));
+    }
+
+    #[test]
+    fn test_combine_loaded_accounts_data_size_limit_with_unset_pack() {
+        let ix = || {
+            (
+                Instruction::new_with_bytes(Pubkey::default(), &[], vec![]),
+                None::<String>,
+            )
+        };
+        let budget = |setting: ComputeBudgetSetting| (setting.to_instruction(), None::<String>);
+        let packs = vec![
+            vec![
+                budget(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(1_000)),
+                ix(),
+            ],
+            vec![ix()],
+        ];
+        let (instructions, _) = combine_instruction_packs(&packs);
+        assert_eq!(instructions.len(), 3);
+        // the pack without the setting runs under the runtime maximum
+        assert_eq!(
+            ComputeBudgetSetting::from_instruction(&instructions[0]),
+            Some(ComputeBudgetSetting::LoadedAccountsDataSizeLimit(
+                MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+            ))
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Structured user context of an instruction (e.g. operation id, account labels)
+/// carried from the builder into the execution reports and errors.
+#[derive(Clone)]
+pub struct InstructionMetadata(Arc<dyn MetadataValue>);
+
+trait MetadataValue: Any + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + fmt::Debug + Send + Sync> MetadataValue for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl InstructionMetadata {
+    pub fn new<M: Any + fmt::Debug + Send + Sync>(metadata: M) -> Self {
+        Self(Arc::new(metadata))
+    }
+
+    /// The metadata when it is of the type.
+    pub fn downcast_ref<M: Any>(&self) -> Option<&M> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl fmt::Debug for InstructionMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
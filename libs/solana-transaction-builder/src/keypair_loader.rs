@@ -0,0 +1,102 @@
+use anyhow::{anyhow, bail, Context};
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::signature::{keypair_from_seed_phrase_and_passphrase, read_keypair, Keypair};
+use solana_sdk::signer::keypair::{
+    generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed_and_derivation_path,
+};
+use std::path::Path;
+
+const KEYPAIR_LENGTH: usize = 64;
+
+// With the `zeroize` feature these are zeroized on drop:
+// - the file content, environment variable, decoded base58 bytes and seed phrase a keypair is loaded from,
+// - the base58 secrets of a builder session (`ExportedSecret`) and their decoded bytes on load.
+// Not covered by the feature:
+// - the `Keypair`s kept by `SignatureBuilder` and `PreparedTransaction`, the type is owned by solana-sdk
+//   and cannot be wrapped, their secret key is cleared on drop by the solana-sdk keypair itself
+//   (with or without the feature) once the last `Arc` is dropped,
+// - the intermediate copies solana-sdk makes while parsing (the JSON array of `read_keypair`,
+//   the seed derivation) or exporting (`Keypair::to_bytes`, `Keypair::to_base58_string`).
+#[cfg(feature = "zeroize")]
+pub(crate) type Secret<T> = zeroize::Zeroizing<T>;
+#[cfg(not(feature = "zeroize"))]
+pub(crate) type Secret<T> = T;
+
+#[cfg(feature = "zeroize")]
+pub(crate) fn secret<T: zeroize::Zeroize>(value: T) -> Secret<T> {
+    zeroize::Zeroizing::new(value)
+}
+
+#[cfg(not(feature = "zeroize"))]
+pub(crate) fn secret<T>(value: T) -> Secret<T> {
+    value
+}
+
+/// Keypair file in the JSON array format of `solana-keygen`.
+pub fn read_keypair_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Keypair> {
+    let path = path.as_ref();
+    let content = secret(
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read keypair file {}", path.display()))?,
+    );
+    read_keypair(&mut content.as_bytes())
+        .map_err(|err| anyhow!("Cannot parse keypair file {}: {err}", path.display()))
+}
+
+/// Keypair as a JSON array of the secret bytes, a base58 encoded secret or a path to a keypair file.
+pub fn parse_keypair(keypair: &str) -> anyhow::Result<Keypair> {
+    let keypair = keypair.trim();
+    if keypair.starts_with('[') {
+        read_keypair(&mut keypair.as_bytes())
+            .map_err(|err| anyhow!("Cannot parse keypair JSON array: {err}"))
+    } else if let Some(bytes) = bs58::decode(keypair)
+        .into_vec()
+        .ok()
+        .map(secret)
+        .filter(|bytes| bytes.len() == KEYPAIR_LENGTH)
+    {
+        Keypair::from_bytes(&bytes).map_err(|err| anyhow!("Invalid base58 keypair: {err}"))
+    } else {
+        read_keypair_from_file(keypair)
+    }
+}
+
+/// Keypair of the environment variable in any of the `parse_keypair` formats.
+pub fn read_keypair_from_env(variable: &str) -> anyhow::Result<Keypair> {
+    let value = secret(
+        std::env::var(variable)
+            .with_context(|| format!("Cannot read keypair environment variable {variable}"))?,
+    );
+    parse_keypair(&value).with_context(|| format!("Invalid keypair in {variable}"))
+}
+
+/// Keypair of the seed phrase as `solana-keygen recover` derives it.
+/// Without the derivation path the legacy (non-BIP44) derivation is used, the path is either
+/// absolute (`m/44'/501'/0'/0'`) or the account/change part only (`0/0`).
+pub fn keypair_from_seed_phrase(
+    seed_phrase: &str,
+    passphrase: &str,
+    derivation_path: Option<&str>,
+) -> anyhow::Result<Keypair> {
+    let seed_phrase = secret(seed_phrase.split_whitespace().collect::<Vec<_>>().join(" "));
+    if seed_phrase.is_empty() {
+        bail!("Seed phrase is empty");
+    }
+    match derivation_path {
+        None => keypair_from_seed_phrase_and_passphrase(&seed_phrase, passphrase),
+        Some(derivation_path) => {
+            let derivation_path = if derivation_path.starts_with('m') {
+                DerivationPath::from_absolute_path_str(derivation_path)
+            } else {
+                DerivationPath::from_key_str(derivation_path)
+            }
+            .map_err(|err| anyhow!("Invalid derivation path {derivation_path}: {err}"))?;
+            let seed = secret(generate_seed_from_seed_phrase_and_passphrase(
+                &seed_phrase,
+                passphrase,
+            ));
+            keypair_from_seed_and_derivation_path(&seed, Some(derivation_path))
+        }
+    }
+    .map_err(|err| anyhow!("Cannot derive keypair from the seed phrase: {err}"))
+}
@@ -1,9 +1,42 @@
+#[cfg(all(feature = "rayon", target_arch = "wasm32"))]
+compile_error!("The rayon feature is not supported on wasm32, threads cannot be spawned there");
+
+mod account_assertion;
+mod compute_budget;
+mod instruction_metadata;
+mod keypair_loader;
+mod lighthouse;
+mod memo;
+#[cfg(feature = "persistence")]
+mod persistence;
+mod plan;
+#[cfg(feature = "plan-loader")]
+mod plan_loader;
 mod prepared_transaction;
+mod program_names;
+mod remote_signer;
 mod signature_builder;
+mod signature_coordinator;
+mod size_breakdown;
 mod transaction_builder;
 mod transaction_instruction;
 
+pub use account_assertion::*;
+pub use compute_budget::*;
+pub use instruction_metadata::*;
+pub use keypair_loader::*;
+pub use lighthouse::*;
+pub use memo::*;
+#[cfg(feature = "persistence")]
+pub use persistence::*;
+pub use plan::*;
+#[cfg(feature = "plan-loader")]
+pub use plan_loader::*;
 pub use prepared_transaction::*;
+pub use program_names::*;
+pub use remote_signer::*;
 pub use signature_builder::*;
+pub use signature_coordinator::*;
+pub use size_breakdown::*;
 pub use transaction_builder::*;
 pub use transaction_instruction::*;
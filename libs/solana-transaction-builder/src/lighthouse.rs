@@ -0,0 +1,63 @@
+use crate::account_assertion::AccountAssertion;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// Lighthouse assertion program
+pub const LIGHTHOUSE_PROGRAM_ID: Pubkey = pubkey!("L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95");
+
+const ASSERT_ACCOUNT_INFO_DISCRIMINATOR: u8 = 4;
+const ASSERT_TOKEN_ACCOUNT_DISCRIMINATOR: u8 = 8;
+const LOG_LEVEL_SILENT: u8 = 0;
+
+const ACCOUNT_INFO_LAMPORTS: u8 = 0;
+const ACCOUNT_INFO_OWNER: u8 = 2;
+const TOKEN_ACCOUNT_AMOUNT: u8 = 2;
+
+const EQUAL: u8 = 0;
+const GREATER_THAN_OR_EQUAL: u8 = 4;
+const LESS_THAN_OR_EQUAL: u8 = 5;
+
+/// Lighthouse instruction failing the whole transaction when the assertion does not hold
+/// at the point of its execution. None for assertions Lighthouse cannot check on its own
+/// (comparisons with the state before the transaction, account existence).
+pub fn lighthouse_assertion_instruction(assertion: &AccountAssertion) -> Option<Instruction> {
+    let (discriminator, mut assertion_data) = match assertion {
+        AccountAssertion::LamportsAtLeast { lamports, .. } => (
+            ASSERT_ACCOUNT_INFO_DISCRIMINATOR,
+            integer_assertion(ACCOUNT_INFO_LAMPORTS, *lamports, GREATER_THAN_OR_EQUAL),
+        ),
+        AccountAssertion::LamportsAtMost { lamports, .. } => (
+            ASSERT_ACCOUNT_INFO_DISCRIMINATOR,
+            integer_assertion(ACCOUNT_INFO_LAMPORTS, *lamports, LESS_THAN_OR_EQUAL),
+        ),
+        AccountAssertion::OwnerIs { owner, .. } => {
+            let mut data = vec![ACCOUNT_INFO_OWNER];
+            data.extend_from_slice(owner.as_ref());
+            data.push(EQUAL);
+            (ASSERT_ACCOUNT_INFO_DISCRIMINATOR, data)
+        }
+        AccountAssertion::TokenAmountAtLeast { amount, .. } => (
+            ASSERT_TOKEN_ACCOUNT_DISCRIMINATOR,
+            integer_assertion(TOKEN_ACCOUNT_AMOUNT, *amount, GREATER_THAN_OR_EQUAL),
+        ),
+        AccountAssertion::TokenAmountIncreased { .. }
+        | AccountAssertion::NotExists { .. }
+        | AccountAssertion::Exists { .. }
+        | AccountAssertion::DataStartsWith { .. } => return None,
+    };
+    let mut data = vec![discriminator, LOG_LEVEL_SILENT];
+    data.append(&mut assertion_data);
+    Some(Instruction::new_with_bytes(
+        LIGHTHOUSE_PROGRAM_ID,
+        &data,
+        vec![AccountMeta::new_readonly(*assertion.account(), false)],
+    ))
+}
+
+fn integer_assertion(variant: u8, value: u64, operator: u8) -> Vec<u8> {
+    let mut data = vec![variant];
+    data.extend_from_slice(&value.to_le_bytes());
+    data.push(operator);
+    data
+}
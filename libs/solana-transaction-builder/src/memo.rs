@@ -0,0 +1,10 @@
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// SPL Memo program (v2)
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+pub fn memo_instruction(memo: &str) -> Instruction {
+    Instruction::new_with_bytes(MEMO_PROGRAM_ID, memo.as_bytes(), vec![])
+}
@@ -0,0 +1,315 @@
+use crate::keypair_loader::{secret, Secret};
+use crate::prepared_transaction::PreparedTransaction;
+use crate::signature_builder::SignatureBuilder;
+use crate::transaction_instruction::TransactionInstruction;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+
+const PERSISTED_TRANSACTION_VERSION: u8 = 1;
+
+/// Stable serialized form of a prepared transaction without the signers secrets,
+/// the pre- and post-assertions and the instruction metadata are not persisted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedTransaction {
+    pub version: u8,
+    /// Bincode serialized legacy message
+    #[serde(with = "crate::transaction_instruction::base64_string")]
+    pub message: Vec<u8>,
+    pub instruction_descriptions: Vec<Option<String>>,
+    #[serde(default)]
+    pub instruction_tags: Vec<HashMap<String, String>>,
+    /// Base58 pubkeys, the keypairs are resolved by the signature builder on load
+    pub required_signers: Vec<String>,
+    #[serde(default)]
+    pub external_signers: Vec<String>,
+}
+
+impl PreparedTransaction {
+    pub fn to_persisted(&self) -> anyhow::Result<PersistedTransaction> {
+        Ok(PersistedTransaction {
+            version: PERSISTED_TRANSACTION_VERSION,
+            message: bincode::serialize(&self.transaction.message)?,
+            instruction_descriptions: self.instruction_descriptions.clone(),
+            instruction_tags: self.instruction_tags.clone(),
+            required_signers: to_strings(&self.required_signers()),
+            external_signers: to_strings(&self.external_signers),
+        })
+    }
+
+    /// The keypairs of the required signers are taken from the signature builder,
+    /// the external signers are registered as placeholders.
+    pub fn from_persisted(
+        persisted: PersistedTransaction,
+        signature_builder: &SignatureBuilder,
+    ) -> anyhow::Result<Self> {
+        if persisted.version != PERSISTED_TRANSACTION_VERSION {
+            return Err(anyhow!(
+                "Unsupported persisted transaction version {}",
+                persisted.version
+            ));
+        }
+        let message: Message = bincode::deserialize(&persisted.message)?;
+        let transaction = Transaction::new_unsigned(message);
+        let external_signers = from_strings(&persisted.external_signers)?;
+        let mut signature_builder = signature_builder.clone();
+        for external_signer in external_signers {
+            signature_builder.add_placeholder_signer(external_signer);
+        }
+        let prepared_transaction = PreparedTransaction::new(
+            transaction,
+            &signature_builder,
+            persisted.instruction_descriptions,
+        )
+        .map_err(|pubkey| anyhow!("No signer of {pubkey} for the persisted transaction"))?
+        .with_instruction_tags(persisted.instruction_tags);
+        if to_strings(&prepared_transaction.required_signers()) != persisted.required_signers {
+            return Err(anyhow!(
+                "Required signers of the persisted transaction differ from its message"
+            ));
+        }
+        Ok(prepared_transaction)
+    }
+
+    /// Writes the persisted form as JSON, the file is replaced atomically
+    /// (checkpointing a plan does not leave a truncated file on a crash).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        write_json_atomically(path.as_ref(), &self.to_persisted()?)
+    }
+
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        signature_builder: &SignatureBuilder,
+    ) -> anyhow::Result<Self> {
+        Self::from_persisted(read_json(path.as_ref())?, signature_builder)
+    }
+}
+
+pub(crate) const BUILDER_SESSION_VERSION: u8 = 1;
+
+/// Persisted state of a `TransactionBuilder` to resume an interrupted planning,
+/// see `TransactionBuilder::save_session`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuilderSession {
+    pub version: u8,
+    pub fee_payer: String,
+    pub max_transaction_size: usize,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub drop_unused_signers: bool,
+    /// Base58 pubkeys of all the registered signers
+    pub signers: Vec<String>,
+    #[serde(default)]
+    pub placeholder_signers: Vec<String>,
+    /// Base58 secrets exported by the `KeypairExport` policy
+    #[serde(default)]
+    pub keypairs: Vec<ExportedSecret>,
+    pub packs: Vec<SessionPack>,
+    /// Instructions of the unfinished pack
+    #[serde(default)]
+    pub current_pack: SessionPack,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionPack {
+    pub instructions: Vec<TransactionInstruction>,
+    pub descriptions: Vec<Option<String>>,
+    #[serde(default)]
+    pub tags: Vec<HashMap<String, String>>,
+}
+
+/// Base58 secret of a keypair exported into a builder session, serialized as a plain string.
+/// With the `zeroize` feature the string is zeroized on drop. Debug does not print the secret.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExportedSecret(Secret<String>);
+
+impl ExportedSecret {
+    pub fn new(base58_secret: String) -> Self {
+        Self(secret(base58_secret))
+    }
+}
+
+impl Deref for ExportedSecret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ExportedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExportedSecret(..)")
+    }
+}
+
+impl Serialize for ExportedSecret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExportedSecret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Keypairs written into the session file, their secrets are stored in plain text.
+/// The keypairs not exported have to be provided by the signature builder on load.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum KeypairExport {
+    #[default]
+    None,
+    /// e.g. the ephemeral signers of `TransactionBuilder::generate_signer`
+    Only(Vec<Pubkey>),
+    All,
+}
+
+impl KeypairExport {
+    pub(crate) fn exports(&self, pubkey: &Pubkey) -> bool {
+        match self {
+            Self::None => false,
+            Self::Only(pubkeys) => pubkeys.contains(pubkey),
+            Self::All => true,
+        }
+    }
+}
+
+pub(crate) fn write_json_atomically<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    std::fs::write(&temporary_path, serde_json::to_vec_pretty(value)?)
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+    std::fs::rename(&temporary_path, path)
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(())
+}
+
+pub(crate) fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<T> {
+    let content = std::fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    serde_json::from_slice(&content).with_context(|| format!("Cannot parse {}", path.display()))
+}
+
+pub(crate) fn to_strings(pubkeys: &[Pubkey]) -> Vec<String> {
+    pubkeys.iter().map(Pubkey::to_string).collect()
+}
+
+pub(crate) fn from_strings(pubkeys: &[String]) -> anyhow::Result<Vec<Pubkey>> {
+    pubkeys
+        .iter()
+        .map(|pubkey| {
+            Pubkey::from_str(pubkey).map_err(|err| anyhow!("Invalid pubkey {pubkey}: {err}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TransactionBuilder;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::signature::Keypair;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_save_load() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        let cold_key = builder.add_placeholder_signer(Pubkey::new_unique());
+        builder
+            .add_instruction_with_description(
+                Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[1, 2, 3],
+                    vec![AccountMeta::new(cold_key, true)],
+                ),
+                "Update config".to_string(),
+            )
+            .unwrap();
+        let prepared_transaction = builder.build_one();
+        let path = std::env::temp_dir().join(format!("{}.json", Pubkey::new_unique()));
+
+        prepared_transaction.save(&path).unwrap();
+        let mut signature_builder = SignatureBuilder::default();
+        signature_builder.add_signer(fee_payer);
+        let loaded = PreparedTransaction::load(&path, &signature_builder).unwrap();
+        let without_fee_payer = PreparedTransaction::load(&path, &SignatureBuilder::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.transaction, prepared_transaction.transaction);
+        assert_eq!(
+            loaded.instruction_descriptions,
+            prepared_transaction.instruction_descriptions
+        );
+        assert_eq!(loaded.external_signers, vec![cold_key]);
+        assert_eq!(
+            loaded.legacy_message(Hash::default()),
+            prepared_transaction.legacy_message(Hash::default())
+        );
+        assert!(without_fee_payer.is_err());
+    }
+
+    #[test]
+    fn test_builder_session() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        builder.set_memo(Some("campaign".to_string()));
+        let ephemeral = builder.generate_signer();
+        builder
+            .add_instruction_with_description(
+                Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[1],
+                    vec![AccountMeta::new(ephemeral, true)],
+                ),
+                "First".to_string(),
+            )
+            .unwrap();
+        builder.finish_instruction_pack();
+        builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[2],
+                vec![],
+            ))
+            .unwrap();
+
+        let session = builder
+            .to_session(&KeypairExport::Only(vec![ephemeral]))
+            .unwrap();
+        assert_eq!(session.keypairs.len(), 1);
+        assert!(!format!("{session:?}").contains(&*session.keypairs[0]));
+        let json = serde_json::to_value(&session).unwrap();
+        assert_eq!(json["keypairs"][0], *session.keypairs[0]);
+        assert!(
+            TransactionBuilder::from_session(session.clone(), &SignatureBuilder::default())
+                .is_err()
+        );
+        let mut signature_builder = SignatureBuilder::default();
+        signature_builder.add_signer(fee_payer);
+        let mut resumed = TransactionBuilder::from_session(session, &signature_builder).unwrap();
+
+        assert_eq!(resumed.packs_len(), builder.packs_len());
+        assert!(resumed.get_signer(&ephemeral).is_some());
+        let expected: Vec<PreparedTransaction> = builder.sequence().collect();
+        let actual: Vec<PreparedTransaction> = resumed.sequence().collect();
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert_eq!(actual.transaction, expected.transaction);
+            assert_eq!(
+                actual.instruction_descriptions,
+                expected.instruction_descriptions
+            );
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::compute_budget::{compute_unit_price, estimate_compute_unit_limit};
+use crate::prepared_transaction::PreparedTransaction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+/// Base fee paid per transaction signature
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// Signature fees plus the priority fee of the estimated compute units of the transaction.
+pub fn estimate_fee_lamports(prepared_transaction: &PreparedTransaction) -> u64 {
+    let instructions = prepared_transaction.instructions();
+    fee_lamports(
+        prepared_transaction
+            .transaction
+            .message
+            .header
+            .num_required_signatures,
+        estimate_compute_unit_limit(&instructions),
+        compute_unit_price(&instructions).unwrap_or(0),
+    )
+}
+
+fn fee_lamports(signatures: u8, compute_units: u32, compute_unit_price: u64) -> u64 {
+    let priority_fee = (u128::from(compute_units) * u128::from(compute_unit_price))
+        .div_ceil(u128::from(MICRO_LAMPORTS_PER_LAMPORT));
+    (u64::from(signatures) * LAMPORTS_PER_SIGNATURE)
+        .saturating_add(u64::try_from(priority_fee).unwrap_or(u64::MAX))
+}
+
+/// Summary of the transactions a builder is going to build, see `TransactionBuilder::plan`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TransactionPlan {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_pubkey"))]
+    pub fee_payer: Pubkey,
+    pub instruction_packs: usize,
+    pub transactions: Vec<PlannedTransaction>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PlannedTransaction {
+    pub human_index: usize,
+    pub instructions: Vec<PlannedInstruction>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_pubkeys"))]
+    pub programs: Vec<Pubkey>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_pubkeys"))]
+    pub signers: Vec<Pubkey>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_pubkeys"))]
+    pub writable_accounts: Vec<Pubkey>,
+    /// Serialized transaction size in bytes
+    pub size: usize,
+    pub estimated_compute_units: u32,
+    /// Compute unit price in micro-lamports
+    pub compute_unit_price: u64,
+    /// Signature fees plus the priority fee of the estimated compute units
+    pub estimated_fee_lamports: u64,
+    /// See `PreparedTransaction::content_hash`
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_hash"))]
+    pub content_hash: Hash,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PlannedInstruction {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_pubkey"))]
+    pub program_id: Pubkey,
+    pub description: Option<String>,
+    pub accounts: usize,
+    pub data_size: usize,
+}
+
+impl TransactionPlan {
+    pub fn new(
+        fee_payer: Pubkey,
+        instruction_packs: usize,
+        prepared_transactions: &[PreparedTransaction],
+    ) -> Self {
+        Self {
+            fee_payer,
+            instruction_packs,
+            transactions: prepared_transactions
+                .iter()
+                .enumerate()
+                .map(|(index, prepared_transaction)| {
+                    PlannedTransaction::new(index + 1, prepared_transaction)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn estimated_fee_lamports(&self) -> u64 {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.estimated_fee_lamports)
+            .sum()
+    }
+}
+
+impl PlannedTransaction {
+    pub fn new(human_index: usize, prepared_transaction: &PreparedTransaction) -> Self {
+        let message = &prepared_transaction.transaction.message;
+        let instructions = prepared_transaction.instructions();
+        let estimated_compute_units = estimate_compute_unit_limit(&instructions);
+        let compute_unit_price = compute_unit_price(&instructions).unwrap_or(0);
+
+        let mut programs: Vec<Pubkey> = Vec::new();
+        for instruction in &instructions {
+            if !programs.contains(&instruction.program_id) {
+                programs.push(instruction.program_id);
+            }
+        }
+
+        Self {
+            human_index,
+            instructions: instructions
+                .iter()
+                .zip(
+                    prepared_transaction
+                        .instruction_descriptions
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::repeat(None)),
+                )
+                .map(|(instruction, description)| PlannedInstruction::new(instruction, description))
+                .collect(),
+            programs,
+            signers: message
+                .account_keys
+                .iter()
+                .take(message.header.num_required_signatures as usize)
+                .cloned()
+                .collect(),
+            writable_accounts: prepared_transaction.writable_accounts(),
+            size: bincode::serialize(&prepared_transaction.transaction)
+                .map_or(0, |serialized| serialized.len()),
+            estimated_compute_units,
+            compute_unit_price,
+            estimated_fee_lamports: fee_lamports(
+                message.header.num_required_signatures,
+                estimated_compute_units,
+                compute_unit_price,
+            ),
+            content_hash: prepared_transaction.content_hash(),
+        }
+    }
+}
+
+impl PlannedInstruction {
+    fn new(instruction: &Instruction, description: Option<String>) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            description,
+            accounts: instruction.accounts.len(),
+            data_size: instruction.data.len(),
+        }
+    }
+}
+
+impl fmt::Display for TransactionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Plan: {} transaction(s) from {} instruction pack(s), fee payer {}, estimated fee {} lamports",
+            self.transactions.len(),
+            self.instruction_packs,
+            self.fee_payer,
+            self.estimated_fee_lamports()
+        )?;
+        for transaction in &self.transactions {
+            write!(f, "{transaction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PlannedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "\nTransaction #{}: {} bytes, ~{} CU at {} micro-lamports/CU, ~{} lamports",
+            self.human_index,
+            self.size,
+            self.estimated_compute_units,
+            self.compute_unit_price,
+            self.estimated_fee_lamports
+        )?;
+        writeln!(f, "  content hash: {}", self.content_hash)?;
+        writeln!(f, "  signers: {}", join(&self.signers))?;
+        writeln!(f, "  programs: {}", join(&self.programs))?;
+        writeln!(f, "  writable: {}", join(&self.writable_accounts))?;
+        for instruction in &self.instructions {
+            writeln!(
+                f,
+                "  + {} ({} accounts, {} bytes){}",
+                instruction.program_id,
+                instruction.accounts,
+                instruction.data_size,
+                instruction
+                    .description
+                    .as_ref()
+                    .map_or_else(String::new, |description| format!(": {description}"))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn join(keys: &[Pubkey]) -> String {
+    keys.iter()
+        .map(Pubkey::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(feature = "serde")]
+fn serialize_pubkey<S: serde::Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(key)
+}
+
+#[cfg(feature = "serde")]
+fn serialize_pubkeys<S: serde::Serializer>(
+    keys: &[Pubkey],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(keys.iter().map(Pubkey::to_string))
+}
+
+#[cfg(feature = "serde")]
+fn serialize_hash<S: serde::Serializer>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(hash)
+}
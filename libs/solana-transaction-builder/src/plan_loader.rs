@@ -0,0 +1,292 @@
+use crate::transaction_builder::TransactionBuilder;
+use crate::transaction_instruction::{Encoding, TransactionAccount, FEE_PAYER_PLACEHOLDER};
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Declarative list of instruction packs loaded from a JSON or YAML file, e.g.
+/// ```yaml
+/// packs:
+///   - description: Update config
+///     instructions:
+///       - program_id: MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD
+///         accounts:
+///           - { pubkey: FeePayerP1aceho1der1111111111111111111111111, is_signer: true, is_writable: true }
+///         data: 0a0b0c
+///         data_encoding: hex
+/// ```
+/// The `FEE_PAYER_PLACEHOLDER` accounts are replaced by the fee payer of the builder.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstructionPlan {
+    pub packs: Vec<InstructionPlanPack>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstructionPlanPack {
+    /// Used for the instructions of the pack without their own description
+    #[serde(default)]
+    pub description: Option<String>,
+    pub instructions: Vec<InstructionPlanEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstructionPlanEntry {
+    pub program_id: String,
+    #[serde(default)]
+    pub accounts: Vec<TransactionAccount>,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub data_encoding: Encoding,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl InstructionPlan {
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    /// Files with the `yaml` or `yml` extension are parsed as YAML, others as JSON.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read instruction plan {}", path.display()))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => Self::from_yaml(&content),
+            _ => Self::from_json(&content),
+        }
+        .with_context(|| format!("Cannot parse instruction plan {}", path.display()))
+    }
+
+    /// Instructions of the packs with their descriptions.
+    pub fn instruction_packs(
+        &self,
+        fee_payer: &Pubkey,
+    ) -> anyhow::Result<Vec<Vec<(Instruction, Option<String>)>>> {
+        self.packs
+            .iter()
+            .enumerate()
+            .map(|(pack_index, pack)| {
+                pack.instructions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| {
+                        let instruction = entry.instruction(fee_payer).with_context(|| {
+                            format!("Invalid instruction {index} of the pack {pack_index}")
+                        })?;
+                        Ok((
+                            instruction,
+                            entry
+                                .description
+                                .clone()
+                                .or_else(|| pack.description.clone()),
+                        ))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Adds every pack of the plan as an instruction pack of the builder, an unfinished pack
+    /// of the builder is finished first. The whole plan is decoded first, the builder is left
+    /// as it was when any of the instructions cannot be decoded or added.
+    pub fn add_to_builder(&self, builder: &mut TransactionBuilder) -> anyhow::Result<()> {
+        let packs = self.instruction_packs(&builder.fee_payer())?;
+        let snapshot = builder.snapshot();
+        if !builder.is_current_pack_empty() {
+            builder.finish_instruction_pack();
+        }
+        let result = packs
+            .into_iter()
+            .enumerate()
+            .try_for_each(|(pack_index, pack)| {
+                for (index, (instruction, description)) in pack.into_iter().enumerate() {
+                    match description {
+                        Some(description) => {
+                            builder.add_instruction_with_description(instruction, description)
+                        }
+                        None => builder.add_instruction(instruction),
+                    }
+                    .with_context(|| {
+                        format!("Cannot add instruction {index} of the pack {pack_index}")
+                    })?;
+                }
+                builder.finish_instruction_pack();
+                Ok(())
+            });
+        if result.is_err() {
+            builder.restore(snapshot);
+        }
+        result
+    }
+}
+
+impl InstructionPlanEntry {
+    pub fn instruction(&self, fee_payer: &Pubkey) -> anyhow::Result<Instruction> {
+        Ok(Instruction {
+            program_id: Pubkey::from_str(&self.program_id)
+                .map_err(|err| anyhow!("Invalid program id {}: {err}", self.program_id))?,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| {
+                    let mut account_meta = AccountMeta::from(account);
+                    if account_meta.pubkey == FEE_PAYER_PLACEHOLDER {
+                        account_meta.pubkey = *fee_payer;
+                    }
+                    account_meta
+                })
+                .collect(),
+            data: self.data_encoding.decode(&self.data)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+    use std::sync::Arc;
+
+    const PROGRAM_ID: &str = "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD";
+
+    #[test]
+    fn test_from_json() {
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [{{"description": "Update config", "instructions": [
+                {{"program_id": "{PROGRAM_ID}", "data": "AQID"}},
+                {{"program_id": "{PROGRAM_ID}", "description": "Own", "accounts": [
+                    {{"pubkey": "{FEE_PAYER_PLACEHOLDER}", "is_signer": true, "is_writable": true}}
+                ]}}
+            ]}}]}}"#
+        ))
+        .unwrap();
+        let fee_payer = Pubkey::new_unique();
+        let packs = plan.instruction_packs(&fee_payer).unwrap();
+        assert_eq!(packs.len(), 1);
+        let (first, first_description) = &packs[0][0];
+        assert_eq!(first.data, vec![1, 2, 3]);
+        assert_eq!(first_description.as_deref(), Some("Update config"));
+        let (second, second_description) = &packs[0][1];
+        assert_eq!(second.accounts, vec![AccountMeta::new(fee_payer, true)]);
+        assert_eq!(second_description.as_deref(), Some("Own"));
+    }
+
+    #[test]
+    fn test_from_yaml() {
+        let plan = InstructionPlan::from_yaml(&format!(
+            "packs:
+  - instructions:
+      - program_id: {PROGRAM_ID}
+        data: "0x0a0b0c"
+        data_encoding: hex
+  - instructions:
+      - program_id: {PROGRAM_ID}
+        data: Ldp
+        data_encoding: base58
+"
+        ))
+        .unwrap();
+        let packs = plan.instruction_packs(&Pubkey::new_unique()).unwrap();
+        assert_eq!(packs.len(), 2);
+        assert_eq!(packs[0][0].0.data, vec![10, 11, 12]);
+        assert_eq!(packs[0][0].1, None);
+        assert_eq!(packs[1][0].0.data, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_data_encoding() {
+        assert_eq!(Encoding::Base64.decode(" AQID ").unwrap(), vec![1, 2, 3]);
+        assert_eq!(Encoding::Base58.decode("Ldp").unwrap(), vec![1, 2, 3]);
+        assert_eq!(Encoding::Hex.decode("010203").unwrap(), vec![1, 2, 3]);
+        assert_eq!(Encoding::Hex.decode("0x010203").unwrap(), vec![1, 2, 3]);
+        assert!(Encoding::Hex.decode("0g").is_err());
+        assert!(Encoding::Base58.decode("0OIl").is_err());
+        assert!(Encoding::Base64.decode("!").is_err());
+    }
+
+    #[test]
+    fn test_add_to_builder_atomic() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        let unknown_signer = Pubkey::new_unique();
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [
+                {{"instructions": [{{"program_id": "{PROGRAM_ID}", "data": "AQID"}}]}},
+                {{"instructions": [{{"program_id": "{PROGRAM_ID}", "accounts": [
+                    {{"pubkey": "{unknown_signer}", "is_signer": true, "is_writable": false}}
+                ]}}]}}
+            ]}}"#
+        ))
+        .unwrap();
+        assert!(plan.add_to_builder(&mut builder).is_err());
+        assert_eq!(builder.packs_len(), 0);
+        assert_eq!(builder.instructions_len(), 0);
+
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [
+                {{"instructions": [{{"program_id": "{PROGRAM_ID}", "data": "AQID"}}]}},
+                {{"instructions": [{{"program_id": "{PROGRAM_ID}", "data": "zz", "data_encoding": "hex"}}]}}
+            ]}}"#
+        ))
+        .unwrap();
+        assert!(plan.add_to_builder(&mut builder).is_err());
+        assert_eq!(builder.packs_len(), 0);
+
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [{{"instructions": [{{"program_id": "{PROGRAM_ID}", "accounts": [
+                {{"pubkey": "{}", "is_signer": true, "is_writable": true}}
+            ]}}]}}]}}"#,
+            fee_payer.pubkey()
+        ))
+        .unwrap();
+        plan.add_to_builder(&mut builder).unwrap();
+        assert_eq!(builder.packs_len(), 1);
+    }
+
+    #[test]
+    fn test_add_to_builder_finishes_current_pack() {
+        let mut builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+        builder
+            .add_instruction(Instruction::new_with_bytes(program_id, &[0], vec![]))
+            .unwrap();
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [{{"instructions": [{{"program_id": "{PROGRAM_ID}", "data": "AQID"}}]}}]}}"#
+        ))
+        .unwrap();
+        plan.add_to_builder(&mut builder).unwrap();
+        assert_eq!(builder.packs_len(), 2);
+        assert_eq!(
+            builder.build_next().unwrap().instructions()[0].data,
+            vec![0]
+        );
+        assert_eq!(
+            builder.build_next().unwrap().instructions()[0].data,
+            vec![1, 2, 3]
+        );
+
+        // the unfinished pack stays unfinished when the plan fails
+        builder
+            .add_instruction(Instruction::new_with_bytes(program_id, &[0], vec![]))
+            .unwrap();
+        let plan = InstructionPlan::from_json(&format!(
+            r#"{{"packs": [{{"instructions": [{{"program_id": "{PROGRAM_ID}", "accounts": [
+                {{"pubkey": "{}", "is_signer": true, "is_writable": false}}
+            ]}}]}}]}}"#,
+            Pubkey::new_unique()
+        ))
+        .unwrap();
+        assert!(plan.add_to_builder(&mut builder).is_err());
+        assert_eq!(builder.packs_len(), 1);
+        assert!(!builder.is_current_pack_empty());
+    }
+}
@@ -1,9 +1,25 @@
+use crate::account_assertion::AccountAssertion;
+use crate::compute_budget::ComputeBudgetSetting;
+use crate::instruction_metadata::InstructionMetadata;
+use crate::memo::memo_instruction;
+use crate::program_names::well_known_program_name;
+use crate::remote_signer::RemoteSigner;
 use crate::signature_builder::SignatureBuilder;
-use solana_sdk::hash::Hash;
+use crate::signature_coordinator::verify_signatures;
+use crate::size_breakdown::TransactionSizeBreakdown;
+use crate::transaction_builder::TransactionBuildError;
+use log::error;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::hash::{Hash, Hasher};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
-use solana_sdk::signer::SignerError;
+use solana_sdk::signer::{Signer, SignerError};
 use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::sync::Arc;
 
 pub trait SignedTransaction {
@@ -17,15 +33,77 @@ pub trait SignedTransaction {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PreparedTransaction {
     pub transaction: Transaction,
     pub signers: Vec<Arc<Keypair>>,
+    /// Signers of the async signing path, see `PreparedTransaction::sign_message`
+    pub remote_signers: Vec<Arc<dyn RemoteSigner>>,
+    /// Placeholder signers whose signatures are added externally (e.g. by `SignatureCoordinator`),
+    /// see `SignatureBuilder::add_placeholder_signer`
+    pub external_signers: Vec<Pubkey>,
     pub instruction_descriptions: Vec<Option<String>>,
+    /// User metadata of the instructions, see `TransactionBuilder::add_instruction_with_metadata`
+    pub instruction_metadata: Vec<Option<InstructionMetadata>>,
+    /// Key-value tags of the instructions, see `TransactionBuilder::add_instruction_with_tags`
+    pub instruction_tags: Vec<HashMap<String, String>>,
+    /// Verified by the executor right before the transaction is sent
+    pub pre_assertions: Vec<AccountAssertion>,
+    /// Verified by the executor once the transaction is confirmed
+    pub post_assertions: Vec<AccountAssertion>,
+    /// Strongest use of the fee payer by the instructions, the message makes the fee payer
+    /// a writable signer of all of them. `None` when no instruction references the fee payer
+    /// or when the use is not known, see `PreparedTransaction::with_fee_payer`.
+    pub fee_payer_meta: Option<AccountMeta>,
+}
+
+// signers are printed by their pubkeys only, never with the secret key bytes
+impl fmt::Debug for PreparedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedTransaction")
+            .field("transaction", &self.transaction)
+            .field(
+                "signers",
+                &self
+                    .signers
+                    .iter()
+                    .map(|signer| signer.pubkey())
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "remote_signers",
+                &self
+                    .remote_signers
+                    .iter()
+                    .map(|signer| signer.pubkey())
+                    .collect::<Vec<_>>(),
+            )
+            .field("external_signers", &self.external_signers)
+            .field("instruction_descriptions", &self.instruction_descriptions)
+            .field("instruction_metadata", &self.instruction_metadata)
+            .field("instruction_tags", &self.instruction_tags)
+            .field("pre_assertions", &self.pre_assertions)
+            .field("post_assertions", &self.post_assertions)
+            .field("fee_payer_meta", &self.fee_payer_meta)
+            .finish()
+    }
 }
 
 impl SignedTransaction for PreparedTransaction {
     fn signed_transaction(&self, recent_blockhash: Hash) -> Result<Transaction, SignerError> {
+        let keypair_pubkeys: Vec<Pubkey> = self.signers.iter().map(|arc| arc.pubkey()).collect();
+        let not_covered: Vec<Pubkey> = self
+            .required_signers()
+            .into_iter()
+            .filter(|pubkey| !keypair_pubkeys.contains(pubkey))
+            .collect();
+        if !not_covered.is_empty() {
+            error!(
+                "signed_transaction: no keypairs of the required signers {not_covered:?}, remote signers: {:?}",
+                self.remote_signers.iter().map(|signer| signer.pubkey()).collect::<Vec<_>>()
+            );
+            return Err(SignerError::NotEnoughSigners);
+        }
         let mut transaction = self.transaction.clone();
         transaction.try_sign(
             &self
@@ -46,13 +124,372 @@ impl PreparedTransaction {
         instruction_descriptions: Vec<Option<String>>,
     ) -> Result<Self, Pubkey> {
         let signers = signature_builder.signers_for_transaction(&transaction)?;
+        let remote_signers = signature_builder.remote_signers_for_transaction(&transaction);
+        let external_signers = signature_builder.placeholder_signers_for_transaction(&transaction);
         Ok(Self {
             transaction,
             signers,
+            remote_signers,
+            external_signers,
             instruction_descriptions,
+            instruction_metadata: Vec::new(),
+            instruction_tags: Vec::new(),
+            pre_assertions: Vec::new(),
+            post_assertions: Vec::new(),
+            fee_payer_meta: None,
+        })
+    }
+
+    /// Records how the instructions the transaction was compiled from use the fee payer.
+    pub fn with_fee_payer_meta(mut self, instructions: &[Instruction]) -> Self {
+        self.fee_payer_meta = self
+            .transaction
+            .message
+            .account_keys
+            .first()
+            .and_then(|fee_payer| strongest_account_meta(instructions, fee_payer));
+        self
+    }
+
+    pub fn with_instruction_metadata(
+        mut self,
+        instruction_metadata: Vec<Option<InstructionMetadata>>,
+    ) -> Self {
+        self.instruction_metadata = instruction_metadata;
+        self
+    }
+
+    /// Metadata attached to the instructions of the transaction.
+    pub fn metadata(&self) -> Vec<InstructionMetadata> {
+        self.instruction_metadata
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    pub fn with_instruction_tags(mut self, instruction_tags: Vec<HashMap<String, String>>) -> Self {
+        self.instruction_tags = instruction_tags;
+        self
+    }
+
+    /// Tags of all the instructions, differing values of the same key are joined by a comma.
+    pub fn tags(&self) -> BTreeMap<String, String> {
+        let mut tags: BTreeMap<String, String> = BTreeMap::new();
+        for (key, value) in self.instruction_tags.iter().flatten() {
+            match tags.get_mut(key) {
+                Some(values) => {
+                    if !values.split(',').any(|existing| existing == value) {
+                        values.push(',');
+                        values.push_str(value);
+                    }
+                }
+                None => {
+                    tags.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        tags
+    }
+
+    pub fn with_pre_assertions(mut self, pre_assertions: Vec<AccountAssertion>) -> Self {
+        self.pre_assertions = pre_assertions;
+        self
+    }
+
+    pub fn with_post_assertions(mut self, post_assertions: Vec<AccountAssertion>) -> Self {
+        self.post_assertions = post_assertions;
+        self
+    }
+
+    /// Signs the transaction as a v0 one, accounts found in the lookup tables
+    /// are loaded through them instead of being listed in the message.
+    pub fn signed_v0_transaction(
+        &self,
+        recent_blockhash: Hash,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<VersionedTransaction> {
+        let message = self.v0_message(recent_blockhash, lookup_tables)?;
+        let signers: Vec<&Keypair> = self.signers.iter().map(|arc| arc.as_ref()).collect();
+        Ok(VersionedTransaction::try_new(message, &signers)?)
+    }
+
+    pub fn legacy_message(&self, recent_blockhash: Hash) -> VersionedMessage {
+        let mut message = self.transaction.message.clone();
+        message.recent_blockhash = recent_blockhash;
+        VersionedMessage::Legacy(message)
+    }
+
+    pub fn v0_message(
+        &self,
+        recent_blockhash: Hash,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<VersionedMessage> {
+        let fee_payer = self
+            .transaction
+            .message
+            .account_keys
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Transaction has no fee payer"))?;
+        let message = v0::Message::try_compile(
+            fee_payer,
+            &self.instructions(),
+            lookup_tables,
+            recent_blockhash,
+        )?;
+        Ok(VersionedMessage::V0(message))
+    }
+
+    /// Hash of the fee payer and the program ids, account metas and data of the instructions.
+    /// It does not depend on the blockhash and the signatures, i.e., it stays the same
+    /// from the planning to the execution of the transaction.
+    pub fn content_hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(fee_payer) = self.transaction.message.account_keys.first() {
+            hasher.hash(fee_payer.as_ref());
+        }
+        hash_instructions(&mut hasher, &self.instructions());
+        hasher.result()
+    }
+
+    pub fn size_breakdown(&self) -> TransactionSizeBreakdown {
+        TransactionSizeBreakdown::new(&self.transaction, &self.instruction_descriptions)
+    }
+
+    /// Signers of the message in the order of their signatures, the fee payer first.
+    pub fn required_signers(&self) -> Vec<Pubkey> {
+        let message = &self.transaction.message;
+        message.account_keys[..message.header.num_required_signatures as usize].to_vec()
+    }
+
+    /// Required signers covered neither by the keypairs nor by the remote signers.
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.required_signers()
+            .into_iter()
+            .filter(|pubkey| {
+                !self
+                    .signers
+                    .iter()
+                    .any(|keypair| keypair.pubkey() == *pubkey)
+                    && !self
+                        .remote_signers
+                        .iter()
+                        .any(|remote_signer| remote_signer.pubkey() == *pubkey)
+            })
+            .collect()
+    }
+
+    /// Positions of the invalid signatures of the transaction produced from this one
+    /// with the blockhash (e.g. merged from external signatures in an offline flow).
+    pub fn verify(
+        &self,
+        recent_blockhash: Hash,
+        transaction: &VersionedTransaction,
+    ) -> anyhow::Result<Vec<usize>> {
+        if transaction.message != self.legacy_message(recent_blockhash) {
+            anyhow::bail!("Transaction message differs from the prepared one");
+        }
+        Ok(verify_signatures(transaction))
+    }
+
+    pub fn requires_remote_signatures(&self) -> bool {
+        !self.remote_signers.is_empty()
+    }
+
+    /// The transaction cannot be signed by the builder alone, the signatures
+    /// of the placeholder signers have to be collected externally.
+    pub fn requires_external_signatures(&self) -> bool {
+        !self.external_signers.is_empty()
+    }
+
+    /// Async signing path: the message is signed by the keypairs and the remote signers.
+    pub async fn sign_message(
+        &self,
+        message: VersionedMessage,
+    ) -> anyhow::Result<VersionedTransaction> {
+        let message_data = message.serialize();
+        let required_signatures = message.header().num_required_signatures as usize;
+        let mut signatures = Vec::with_capacity(required_signatures);
+        for key in &message.static_account_keys()[..required_signatures] {
+            let signature = if let Some(keypair) =
+                self.signers.iter().find(|keypair| keypair.pubkey() == *key)
+            {
+                keypair.try_sign_message(&message_data)?
+            } else if let Some(remote_signer) = self
+                .remote_signers
+                .iter()
+                .find(|remote_signer| remote_signer.pubkey() == *key)
+            {
+                remote_signer.sign_message(&message_data).await?
+            } else {
+                anyhow::bail!("No signer of {key} for the transaction");
+            };
+            signatures.push(signature);
+        }
+        Ok(VersionedTransaction {
+            signatures,
+            message,
         })
     }
 
+    /// Instructions of the transaction decompiled from its message.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        decompile_message(&self.transaction.message)
+    }
+
+    /// Returns a copy of the transaction with the memo instruction prepended.
+    /// Fails with `TransactionBuildError::TooBigTransaction` when the memo makes the legacy
+    /// transaction exceed the packet size (an already bigger one, e.g. to be compiled
+    /// with lookup tables, is not checked).
+    pub fn with_memo(&self, memo: &str) -> anyhow::Result<Self> {
+        let with_memo = self.with_prepended_instruction(memo_instruction(memo), None);
+        let size = bincode::serialize(&with_memo.transaction)?.len();
+        if size > PACKET_DATA_SIZE
+            && bincode::serialize(&self.transaction)?.len() <= PACKET_DATA_SIZE
+        {
+            error!(
+                "with_memo: too big transaction with the memo of {} bytes, size: {size}, max size: {PACKET_DATA_SIZE}",
+                memo.len()
+            );
+            return Err(anyhow::anyhow!(TransactionBuildError::TooBigTransaction));
+        }
+        Ok(with_memo)
+    }
+
+    /// Returns a copy of the transaction with the instruction prepended.
+    pub fn with_prepended_instruction(
+        &self,
+        instruction: Instruction,
+        description: Option<String>,
+    ) -> Self {
+        self.with_inserted_instruction(0, instruction, description)
+    }
+
+    /// Returns a copy of the transaction with the instruction appended.
+    pub fn with_appended_instruction(
+        &self,
+        instruction: Instruction,
+        description: Option<String>,
+    ) -> Self {
+        let index = self.transaction.message.instructions.len();
+        self.with_inserted_instruction(index, instruction, description)
+    }
+
+    /// Returns a copy of the transaction with the compute-budget instruction of the same kind
+    /// replaced by the setting, the instruction is prepended when the transaction has none.
+    pub fn with_compute_budget_setting(&self, setting: ComputeBudgetSetting) -> Self {
+        let mut instructions = self.instructions();
+        let same_kind = |instruction: &Instruction| {
+            ComputeBudgetSetting::from_instruction(instruction)
+                .map_or(false, |existing| existing.is_same_kind(&setting))
+        };
+        match instructions.iter().position(same_kind) {
+            Some(index) => {
+                instructions[index] = setting.to_instruction();
+                self.with_instructions(&instructions)
+            }
+            None => self.with_inserted_instruction(0, setting.to_instruction(), None),
+        }
+    }
+
+    /// Returns a copy of the transaction paid by the fee payer, the signer of the previous
+    /// fee payer is dropped when no instruction requires its signature.
+    /// The instructions referencing the previous fee payer get back the flags of `fee_payer_meta`,
+    /// when that is not known they keep it as a writable signer.
+    pub fn with_fee_payer(&self, fee_payer: Arc<Keypair>) -> Self {
+        let mut instructions = self.instructions();
+        if let (Some(previous_fee_payer), Some(fee_payer_meta)) = (
+            self.transaction.message.account_keys.first(),
+            &self.fee_payer_meta,
+        ) {
+            for account in instructions
+                .iter_mut()
+                .flat_map(|instruction| instruction.accounts.iter_mut())
+                .filter(|account| account.pubkey == *previous_fee_payer)
+            {
+                account.is_signer = fee_payer_meta.is_signer;
+                account.is_writable = fee_payer_meta.is_writable;
+            }
+        }
+        let mut rebuilt = Self {
+            transaction: Transaction::new_with_payer(&instructions, Some(&fee_payer.pubkey())),
+            fee_payer_meta: strongest_account_meta(&instructions, &fee_payer.pubkey()),
+            ..self.clone()
+        };
+        let required_signers = rebuilt.required_signers();
+        rebuilt
+            .signers
+            .retain(|signer| required_signers.contains(&signer.pubkey()));
+        rebuilt
+            .remote_signers
+            .retain(|signer| required_signers.contains(&signer.pubkey()));
+        rebuilt
+            .external_signers
+            .retain(|signer| required_signers.contains(signer));
+        if !rebuilt
+            .signers
+            .iter()
+            .any(|signer| signer.pubkey() == fee_payer.pubkey())
+        {
+            rebuilt.signers.insert(0, fee_payer);
+        }
+        rebuilt
+    }
+
+    /// Accounts the transaction locks for writing.
+    pub fn writable_accounts(&self) -> Vec<Pubkey> {
+        let message = &self.transaction.message;
+        message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| message.is_writable(*index))
+            .map(|(_, key)| *key)
+            .collect()
+    }
+
+    fn with_inserted_instruction(
+        &self,
+        index: usize,
+        instruction: Instruction,
+        description: Option<String>,
+    ) -> Self {
+        let mut instructions = self.instructions();
+        let fee_payer_meta = self.fee_payer_meta.clone().map(|mut fee_payer_meta| {
+            if let Some(inserted) =
+                strongest_account_meta(std::slice::from_ref(&instruction), &fee_payer_meta.pubkey)
+            {
+                fee_payer_meta.is_signer |= inserted.is_signer;
+                fee_payer_meta.is_writable |= inserted.is_writable;
+            }
+            fee_payer_meta
+        });
+        instructions.insert(index, instruction);
+        let mut inserted = self.with_instructions(&instructions);
+        inserted.fee_payer_meta = fee_payer_meta;
+        let previous_len = instructions.len() - 1;
+        inserted.instruction_descriptions.resize(previous_len, None);
+        inserted.instruction_descriptions.insert(index, description);
+        inserted.instruction_metadata.resize(previous_len, None);
+        inserted.instruction_metadata.insert(index, None);
+        inserted
+            .instruction_tags
+            .resize(previous_len, HashMap::new());
+        inserted.instruction_tags.insert(index, HashMap::new());
+        inserted
+    }
+
+    // Recompiles the transaction, the fee payer, the signers and the instruction context stay the same
+    fn with_instructions(&self, instructions: &[Instruction]) -> Self {
+        Self {
+            transaction: Transaction::new_with_payer(
+                instructions,
+                self.transaction.message.account_keys.first(),
+            ),
+            ..self.clone()
+        }
+    }
+
     pub fn single_description(&self) -> Option<String> {
         let mut descriptions = self.instruction_descriptions.to_vec();
         for (i, description) in descriptions.iter_mut().enumerate() {
@@ -65,6 +502,21 @@ impl PreparedTransaction {
             .filter_map(|d| d.map_or_else(|| None, Some))
             .collect::<Vec<String>>()
             .join("\n");
+        let tags = self.tags();
+        let descriptions = if tags.is_empty() {
+            descriptions
+        } else {
+            let tags = tags
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            if descriptions.is_empty() {
+                format!("tags: {tags}")
+            } else {
+                format!("{descriptions}\ntags: {tags}")
+            }
+        };
         if descriptions.is_empty() {
             None
         } else {
@@ -72,3 +524,196 @@ impl PreparedTransaction {
         }
     }
 }
+
+/// Instructions with their programs (well-known ones named), accounts, data and descriptions.
+impl fmt::Display for PreparedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let instructions = self.instructions();
+        writeln!(
+            f,
+            "Transaction of {} instruction(s), fee payer {}",
+            instructions.len(),
+            self.transaction
+                .message
+                .account_keys
+                .first()
+                .map_or_else(|| "<none>".to_string(), Pubkey::to_string)
+        )?;
+        for (index, instruction) in instructions.iter().enumerate() {
+            let program = match well_known_program_name(&instruction.program_id) {
+                Some(name) => format!("{name} ({})", instruction.program_id),
+                None => instruction.program_id.to_string(),
+            };
+            let description = self
+                .instruction_descriptions
+                .get(index)
+                .cloned()
+                .flatten()
+                .map_or_else(String::new, |description| format!(": {description}"));
+            writeln!(f, "  #{index} {program}{description}")?;
+            for account in &instruction.accounts {
+                let flags = match (account.is_signer, account.is_writable) {
+                    (true, true) => " [signer, writable]",
+                    (true, false) => " [signer]",
+                    (false, true) => " [writable]",
+                    (false, false) => "",
+                };
+                writeln!(f, "    - {}{flags}", account.pubkey)?;
+            }
+            writeln!(
+                f,
+                "    data ({} bytes): {}",
+                instruction.data.len(),
+                base64::encode(&instruction.data)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Program ids, account metas and data of the instructions, see `content_hash`
+pub(crate) fn hash_instructions(hasher: &mut Hasher, instructions: &[Instruction]) {
+    for instruction in instructions {
+        hasher.hash(instruction.program_id.as_ref());
+        hasher.hash(&(instruction.accounts.len() as u64).to_le_bytes());
+        for account in &instruction.accounts {
+            hasher.hash(account.pubkey.as_ref());
+            hasher.hash(&[account.is_signer as u8, account.is_writable as u8]);
+        }
+        hasher.hash(&(instruction.data.len() as u64).to_le_bytes());
+        hasher.hash(&instruction.data);
+    }
+}
+
+/// Instructions of the message with the compiled account indices resolved back into account metas.
+// Account meta of the pubkey with the flags of its strongest use by the instructions
+fn strongest_account_meta(instructions: &[Instruction], pubkey: &Pubkey) -> Option<AccountMeta> {
+    instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .filter(|account| account.pubkey == *pubkey)
+        .fold(None, |strongest: Option<AccountMeta>, account| {
+            Some(AccountMeta {
+                pubkey: *pubkey,
+                is_signer: account.is_signer
+                    || strongest.as_ref().map_or(false, |meta| meta.is_signer),
+                is_writable: account.is_writable
+                    || strongest.as_ref().map_or(false, |meta| meta.is_writable),
+            })
+        })
+}
+
+pub(crate) fn decompile_message(message: &Message) -> Vec<Instruction> {
+    message
+        .instructions
+        .iter()
+        .map(|compiled| Instruction {
+            program_id: message.account_keys[compiled.program_id_index as usize],
+            accounts: compiled
+                .accounts
+                .iter()
+                .map(|index| {
+                    let index = *index as usize;
+                    AccountMeta {
+                        pubkey: message.account_keys[index],
+                        is_signer: message.is_signer(index),
+                        is_writable: is_writable_index(message, index),
+                    }
+                })
+                .collect(),
+            data: compiled.data.clone(),
+        })
+        .collect()
+}
+
+/// Instructions of the versioned message, the v0 address table lookups are resolved from the passed tables.
+pub(crate) fn decompile_versioned_message(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Instruction>, TransactionBuildError> {
+    let message = match message {
+        VersionedMessage::Legacy(message) => return Ok(decompile_message(message)),
+        VersionedMessage::V0(message) => message,
+    };
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in &message.address_table_lookups {
+        let table = lookup_tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+            .ok_or(TransactionBuildError::MissingLookupTable(
+                lookup.account_key,
+            ))?;
+        let resolve = |index: &u8| {
+            table.addresses.get(*index as usize).copied().ok_or(
+                TransactionBuildError::LookupTableIndexOutOfBounds {
+                    table: table.key,
+                    index: *index,
+                },
+            )
+        };
+        for index in &lookup.writable_indexes {
+            writable.push(resolve(index)?);
+        }
+        for index in &lookup.readonly_indexes {
+            readonly.push(resolve(index)?);
+        }
+    }
+
+    let header = &message.header;
+    let num_static = message.account_keys.len();
+    let num_required_signatures = header.num_required_signatures as usize;
+    let account_keys: Vec<Pubkey> = message
+        .account_keys
+        .iter()
+        .chain(&writable)
+        .chain(&readonly)
+        .copied()
+        .collect();
+    let is_writable = |index: usize| {
+        if index < num_required_signatures {
+            index < num_required_signatures - header.num_readonly_signed_accounts as usize
+        } else if index < num_static {
+            index < num_static - header.num_readonly_unsigned_accounts as usize
+        } else {
+            index < num_static + writable.len()
+        }
+    };
+    message
+        .instructions
+        .iter()
+        .map(|compiled| {
+            let key = |index: u8| {
+                account_keys.get(index as usize).copied().ok_or(
+                    TransactionBuildError::InstructionIndexOutOfBounds(index as usize),
+                )
+            };
+            Ok(Instruction {
+                program_id: key(compiled.program_id_index)?,
+                accounts: compiled
+                    .accounts
+                    .iter()
+                    .map(|index| {
+                        Ok(AccountMeta {
+                            pubkey: key(*index)?,
+                            is_signer: (*index as usize) < num_required_signatures,
+                            is_writable: is_writable(*index as usize),
+                        })
+                    })
+                    .collect::<Result<_, TransactionBuildError>>()?,
+                data: compiled.data.clone(),
+            })
+        })
+        .collect()
+}
+
+// Writable flag as compiled in the message, not demoted for program ids and reserved accounts
+fn is_writable_index(message: &Message, index: usize) -> bool {
+    let header = &message.header;
+    let num_required_signatures = header.num_required_signatures as usize;
+    if index < num_required_signatures {
+        index < num_required_signatures - header.num_readonly_signed_accounts as usize
+    } else {
+        index < message.account_keys.len() - header.num_readonly_unsigned_accounts as usize
+    }
+}
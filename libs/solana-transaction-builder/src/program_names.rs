@@ -0,0 +1,32 @@
+use crate::lighthouse::LIGHTHOUSE_PROGRAM_ID;
+use crate::memo::MEMO_PROGRAM_ID;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{
+    address_lookup_table, bpf_loader_upgradeable, compute_budget, pubkey, stake, system_program,
+    vote,
+};
+
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+/// Human readable name of the well-known programs, e.g. for logging the transactions.
+pub fn well_known_program_name(program_id: &Pubkey) -> Option<&'static str> {
+    let name = match *program_id {
+        id if id == system_program::id() => "System",
+        id if id == compute_budget::id() => "ComputeBudget",
+        id if id == TOKEN_PROGRAM_ID => "Token",
+        id if id == TOKEN_2022_PROGRAM_ID => "Token-2022",
+        id if id == ASSOCIATED_TOKEN_PROGRAM_ID => "AssociatedToken",
+        id if id == MEMO_PROGRAM_ID => "Memo",
+        id if id == stake::program::id() => "Stake",
+        id if id == vote::program::id() => "Vote",
+        id if id == address_lookup_table::program::id() => "AddressLookupTable",
+        id if id == bpf_loader_upgradeable::id() => "BPFLoaderUpgradeable",
+        id if id == LIGHTHOUSE_PROGRAM_ID => "Lighthouse",
+        id if id == MARINADE_PROGRAM_ID => "MarinadeFinance",
+        _ => return None,
+    };
+    Some(name)
+}
@@ -0,0 +1,18 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type RemoteSignatureFuture<'a> =
+    Pin<Box<dyn Future<Output = anyhow::Result<Signature>> + Send + 'a>>;
+
+/// Signer holding its key outside of the process (e.g. a custody service).
+/// Transactions requiring its signature are signed by the async signing path,
+/// see `PreparedTransaction::sign_message`.
+pub trait RemoteSigner: Debug + Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs the serialized transaction message.
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> RemoteSignatureFuture<'a>;
+}
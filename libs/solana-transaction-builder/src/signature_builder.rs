@@ -1,3 +1,7 @@
+use crate::keypair_loader::{
+    keypair_from_seed_phrase, read_keypair_from_env, read_keypair_from_file,
+};
+use crate::remote_signer::RemoteSigner;
 use log::error;
 use solana_sdk::{
     pubkey::Pubkey,
@@ -5,38 +9,155 @@ use solana_sdk::{
     signers::Signers,
     transaction::Transaction,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
 use std::sync::Arc;
 
-#[derive(Debug, Clone, Default)]
-pub struct SignatureBuilder(HashMap<Pubkey, Arc<Keypair>>);
+/// The keypairs are shared by the clones, their secret is zeroized by ed25519-dalek
+/// when the last clone (including the ones in the prepared transactions) is dropped.
+/// Debug prints the pubkeys only.
+#[derive(Clone, Default)]
+pub struct SignatureBuilder {
+    keypairs: HashMap<Pubkey, Arc<Keypair>>,
+    remote_signers: HashMap<Pubkey, Arc<dyn RemoteSigner>>,
+    // signatures added outside of the builder, e.g. by a multisig or a cold key
+    placeholder_signers: HashSet<Pubkey>,
+}
 
 impl SignatureBuilder {
     pub fn add_signer(&mut self, signer: Arc<Keypair>) -> Pubkey {
         let pubkey = signer.pubkey();
-        self.0.insert(pubkey, signer);
+        self.keypairs.insert(pubkey, signer);
         pubkey
     }
 
+    /// Transactions requiring the signature of the remote signer can be built,
+    /// they have to be signed by the async signing path.
+    pub fn add_remote_signer(&mut self, signer: Arc<dyn RemoteSigner>) -> Pubkey {
+        let pubkey = signer.pubkey();
+        self.remote_signers.insert(pubkey, signer);
+        pubkey
+    }
+
+    /// Transactions requiring the signature of the pubkey can be built without its keypair,
+    /// the signature has to be added externally (see `SignatureCoordinator`).
+    pub fn add_placeholder_signer(&mut self, pubkey: Pubkey) -> Pubkey {
+        self.placeholder_signers.insert(pubkey);
+        pubkey
+    }
+
+    pub fn is_placeholder_signer(&self, key: &Pubkey) -> bool {
+        self.placeholder_signers.contains(key)
+    }
+
+    pub fn get_remote_signer(&self, key: &Pubkey) -> Option<Arc<dyn RemoteSigner>> {
+        self.remote_signers.get(key).cloned()
+    }
+
     pub fn new_signer(&mut self) -> Pubkey {
         let keypair = Arc::new(Keypair::new());
         self.add_signer(keypair)
     }
 
+    pub fn add_signer_from_file<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<Pubkey> {
+        Ok(self.add_signer(Arc::new(read_keypair_from_file(path)?)))
+    }
+
+    /// The variable holds a JSON array, a base58 secret or a path to a keypair file.
+    pub fn add_signer_from_env(&mut self, variable: &str) -> anyhow::Result<Pubkey> {
+        Ok(self.add_signer(Arc::new(read_keypair_from_env(variable)?)))
+    }
+
+    /// See `keypair_from_seed_phrase` for the derivation path format.
+    pub fn add_signer_from_seed_phrase(
+        &mut self,
+        seed_phrase: &str,
+        passphrase: &str,
+        derivation_path: Option<&str>,
+    ) -> anyhow::Result<Pubkey> {
+        Ok(self.add_signer(Arc::new(keypair_from_seed_phrase(
+            seed_phrase,
+            passphrase,
+            derivation_path,
+        )?)))
+    }
+
+    /// Removes the keypair or the remote signer, returns whether it was registered.
+    pub fn remove_signer(&mut self, key: &Pubkey) -> bool {
+        let removed_keypair = self.keypairs.remove(key).is_some();
+        let removed_remote_signer = self.remote_signers.remove(key).is_some();
+        let removed_placeholder_signer = self.placeholder_signers.remove(key);
+        removed_keypair || removed_remote_signer || removed_placeholder_signer
+    }
+
+    pub fn clear(&mut self) {
+        self.keypairs.clear();
+        self.remote_signers.clear();
+        self.placeholder_signers.clear();
+    }
+
+    /// Adds the signers of the other builder, replacing the ones with the same pubkey.
+    pub fn merge(&mut self, other: SignatureBuilder) {
+        self.keypairs.extend(other.keypairs);
+        self.remote_signers.extend(other.remote_signers);
+        self.placeholder_signers.extend(other.placeholder_signers);
+    }
+
+    /// Number of the registered keypairs, remote signers and placeholder signers.
+    pub fn len(&self) -> usize {
+        self.keypairs.len() + self.remote_signers.len() + self.placeholder_signers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keypairs.is_empty()
+            && self.remote_signers.is_empty()
+            && self.placeholder_signers.is_empty()
+    }
+
+    /// Pubkeys of the registered keypairs, remote signers and placeholder signers.
+    pub fn keys(&self) -> impl Iterator<Item = &Pubkey> {
+        self.keypairs
+            .keys()
+            .chain(self.remote_signers.keys())
+            .chain(self.placeholder_signers.iter())
+    }
+
     pub fn contains_key(&self, key: &Pubkey) -> bool {
-        self.0.contains_key(key)
+        self.keypairs.contains_key(key)
+            || self.remote_signers.contains_key(key)
+            || self.placeholder_signers.contains(key)
     }
 
     pub fn get_signer(&self, key: &Pubkey) -> Option<Arc<Keypair>> {
-        self.0.get(key).cloned()
+        self.keypairs.get(key).cloned()
     }
 
     pub fn signers(&self) -> Vec<Arc<Keypair>> {
-        self.0.values().cloned().collect()
+        self.keypairs.values().cloned().collect()
     }
 
     pub fn into_signers(self) -> Vec<Arc<Keypair>> {
-        self.0.into_values().collect()
+        self.keypairs.into_values().collect()
+    }
+
+    /// Keeps only the signers required by the transactions (e.g. the ones still to be built),
+    /// the secret of a dropped keypair is zeroized once no prepared transaction holds it.
+    pub fn retain_for(&mut self, transactions: &[Transaction]) {
+        let required: HashSet<Pubkey> = transactions
+            .iter()
+            .flat_map(|transaction| {
+                transaction.message().account_keys
+                    [0..transaction.message().header.num_required_signatures as usize]
+                    .iter()
+                    .cloned()
+            })
+            .collect();
+        self.keypairs.retain(|pubkey, _| required.contains(pubkey));
+        self.remote_signers
+            .retain(|pubkey, _| required.contains(pubkey));
+        self.placeholder_signers
+            .retain(|pubkey| required.contains(pubkey));
     }
 
     pub fn sign_transaction(&self, transaction: &mut Transaction) -> Result<(), SignerError> {
@@ -56,6 +177,8 @@ impl SignatureBuilder {
         Ok(())
     }
 
+    /// Keypairs signing the transaction, signatures of the remote and the placeholder signers
+    /// are not required.
     pub fn signers_for_transaction(
         &self,
         transaction: &Transaction,
@@ -63,14 +186,50 @@ impl SignatureBuilder {
         transaction.message().account_keys
             [0..transaction.message().header.num_required_signatures as usize]
             .iter()
+            .filter(|key| {
+                !self.remote_signers.contains_key(key) && !self.placeholder_signers.contains(key)
+            })
             .map(|key| self.get_signer(key).ok_or(*key))
             .collect()
     }
+
+    pub fn remote_signers_for_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Vec<Arc<dyn RemoteSigner>> {
+        transaction.message().account_keys
+            [0..transaction.message().header.num_required_signatures as usize]
+            .iter()
+            .filter_map(|key| self.get_remote_signer(key))
+            .collect()
+    }
+
+    pub fn placeholder_signers_for_transaction(&self, transaction: &Transaction) -> Vec<Pubkey> {
+        transaction.message().account_keys
+            [0..transaction.message().header.num_required_signatures as usize]
+            .iter()
+            .filter(|key| self.placeholder_signers.contains(key))
+            .cloned()
+            .collect()
+    }
+}
+
+impl fmt::Debug for SignatureBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignatureBuilder")
+            .field("keypairs", &self.keypairs.keys().collect::<Vec<_>>())
+            .field(
+                "remote_signers",
+                &self.remote_signers.keys().collect::<Vec<_>>(),
+            )
+            .field("placeholder_signers", &self.placeholder_signers)
+            .finish()
+    }
 }
 
 impl Signers for SignatureBuilder {
     fn pubkeys(&self) -> Vec<Pubkey> {
-        self.0.keys().cloned().collect()
+        self.keypairs.keys().cloned().collect()
     }
 
     fn try_pubkeys(&self) -> Result<Vec<Pubkey>, SignerError> {
@@ -78,14 +237,14 @@ impl Signers for SignatureBuilder {
     }
 
     fn sign_message(&self, message: &[u8]) -> Vec<Signature> {
-        self.0
+        self.keypairs
             .values()
             .map(|signer| signer.sign_message(message))
             .collect()
     }
 
     fn try_sign_message(&self, message: &[u8]) -> Result<Vec<Signature>, SignerError> {
-        self.0
+        self.keypairs
             .values()
             .map(|signer| signer.try_sign_message(message))
             .collect()
@@ -0,0 +1,138 @@
+use crate::prepared_transaction::PreparedTransaction;
+use crate::remote_signer::RemoteSigner;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer, SignerError};
+use solana_sdk::transaction::VersionedTransaction;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+pub enum SignatureCollectionError {
+    #[error("{0} is not a signer of the transaction")]
+    NotRequiredSigner(Pubkey),
+    #[error("Signature of {0} does not verify")]
+    InvalidSignature(Pubkey),
+    #[error("Missing signatures of {0:?}")]
+    MissingSignatures(Vec<Pubkey>),
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+}
+
+/// Collects the signatures of a transaction message from more parties
+/// (local keypairs, presigners, remote signers or signatures passed around out of band).
+#[derive(Debug, Clone)]
+pub struct SignatureCoordinator {
+    message: VersionedMessage,
+    message_data: Vec<u8>,
+    signatures: Vec<Option<Signature>>,
+}
+
+impl SignatureCoordinator {
+    pub fn new(message: VersionedMessage) -> Self {
+        let message_data = message.serialize();
+        let required_signatures = message.header().num_required_signatures as usize;
+        Self {
+            message,
+            message_data,
+            signatures: vec![None; required_signatures],
+        }
+    }
+
+    /// Legacy message of the prepared transaction already signed by its keypairs.
+    pub fn from_prepared_transaction(
+        prepared_transaction: &PreparedTransaction,
+        recent_blockhash: Hash,
+    ) -> Result<Self, SignatureCollectionError> {
+        let mut coordinator = Self::new(prepared_transaction.legacy_message(recent_blockhash));
+        for keypair in &prepared_transaction.signers {
+            coordinator.sign(keypair.as_ref())?;
+        }
+        Ok(coordinator)
+    }
+
+    /// Serialized message the parties sign.
+    pub fn message_data(&self) -> &[u8] {
+        &self.message_data
+    }
+
+    pub fn required_signers(&self) -> &[Pubkey] {
+        &self.message.static_account_keys()[..self.signatures.len()]
+    }
+
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.required_signers()
+            .iter()
+            .zip(self.signatures.iter())
+            .filter(|(_, signature)| signature.is_none())
+            .map(|(pubkey, _)| *pubkey)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.signatures.iter().all(Option::is_some)
+    }
+
+    /// Adds the signature after it is verified against the message.
+    pub fn add_signature(
+        &mut self,
+        pubkey: &Pubkey,
+        signature: Signature,
+    ) -> Result<(), SignatureCollectionError> {
+        let position = self
+            .required_signers()
+            .iter()
+            .position(|signer| signer == pubkey)
+            .ok_or(SignatureCollectionError::NotRequiredSigner(*pubkey))?;
+        if !signature.verify(pubkey.as_ref(), &self.message_data) {
+            return Err(SignatureCollectionError::InvalidSignature(*pubkey));
+        }
+        self.signatures[position] = Some(signature);
+        Ok(())
+    }
+
+    /// Signs by a local signer (e.g. a keypair or a presigner).
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<(), SignatureCollectionError> {
+        let signature = signer.try_sign_message(&self.message_data)?;
+        self.add_signature(&signer.try_pubkey()?, signature)
+    }
+
+    pub async fn sign_remote(&mut self, remote_signer: &dyn RemoteSigner) -> anyhow::Result<()> {
+        let signature = remote_signer.sign_message(&self.message_data).await?;
+        Ok(self.add_signature(&remote_signer.pubkey(), signature)?)
+    }
+
+    /// The fully signed transaction, fails when some signatures are missing.
+    pub fn into_transaction(self) -> Result<VersionedTransaction, SignatureCollectionError> {
+        let missing_signers = self.missing_signers();
+        if !missing_signers.is_empty() {
+            return Err(SignatureCollectionError::MissingSignatures(missing_signers));
+        }
+        Ok(VersionedTransaction {
+            signatures: self.signatures.into_iter().flatten().collect(),
+            message: self.message,
+        })
+    }
+}
+
+/// Positions of the signatures that are missing or do not verify against the serialized message.
+pub fn verify_signatures(transaction: &VersionedTransaction) -> Vec<usize> {
+    let message_data = transaction.message.serialize();
+    let required_signatures = transaction.message.header().num_required_signatures as usize;
+    transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .take(required_signatures)
+        .enumerate()
+        .filter(|(position, pubkey)| {
+            !transaction
+                .signatures
+                .get(*position)
+                .map_or(false, |signature| {
+                    signature.verify(pubkey.as_ref(), &message_data)
+                })
+        })
+        .map(|(position, _)| position)
+        .collect()
+}
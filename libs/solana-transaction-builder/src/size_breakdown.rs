@@ -0,0 +1,121 @@
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashSet;
+use std::fmt;
+
+const SIGNATURE_SIZE: usize = 64;
+const PUBKEY_SIZE: usize = 32;
+
+/// Bytes of the serialized legacy transaction contributed by every instruction,
+/// the rest (signatures, header, fee payer, blockhash, length prefixes) is the message overhead.
+#[derive(Debug, Clone)]
+pub struct TransactionSizeBreakdown {
+    pub total: usize,
+    pub overhead: usize,
+    pub instructions: Vec<InstructionSize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstructionSize {
+    pub index: usize,
+    pub program_id: Pubkey,
+    pub description: Option<String>,
+    /// Account keys first referenced by the instruction (including its program id)
+    pub new_account_keys: usize,
+    /// Compiled account indexes with their length prefix
+    pub accounts_size: usize,
+    /// Instruction data with its length prefix
+    pub data_size: usize,
+    /// Everything above plus the program id index and the signatures of the new signer keys
+    pub total: usize,
+}
+
+impl TransactionSizeBreakdown {
+    pub fn new(transaction: &Transaction, descriptions: &[Option<String>]) -> Self {
+        let message = &transaction.message;
+        let num_signers = message.header.num_required_signatures as usize;
+        // the fee payer is a part of the overhead
+        let mut seen_keys: HashSet<u8> = HashSet::from([0]);
+        let instructions: Vec<InstructionSize> = message
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, compiled)| {
+                let mut new_account_keys = 0;
+                let mut new_signatures = 0;
+                for key_index in
+                    std::iter::once(&compiled.program_id_index).chain(compiled.accounts.iter())
+                {
+                    if seen_keys.insert(*key_index) {
+                        new_account_keys += 1;
+                        if (*key_index as usize) < num_signers {
+                            new_signatures += 1;
+                        }
+                    }
+                }
+                let accounts_size = short_vec_size(compiled.accounts.len());
+                let data_size = short_vec_size(compiled.data.len());
+                InstructionSize {
+                    index,
+                    program_id: message.account_keys[compiled.program_id_index as usize],
+                    description: descriptions.get(index).cloned().flatten(),
+                    new_account_keys,
+                    accounts_size,
+                    data_size,
+                    total: 1
+                        + accounts_size
+                        + data_size
+                        + new_account_keys * PUBKEY_SIZE
+                        + new_signatures * SIGNATURE_SIZE,
+                }
+            })
+            .collect();
+        let total = bincode::serialized_size(transaction).map_or(0, |size| size as usize);
+        Self {
+            total,
+            overhead: total.saturating_sub(instructions.iter().map(|size| size.total).sum()),
+            instructions,
+        }
+    }
+
+    /// The instruction contributing the most bytes.
+    pub fn largest_instruction(&self) -> Option<&InstructionSize> {
+        self.instructions.iter().max_by_key(|size| size.total)
+    }
+}
+
+impl fmt::Display for TransactionSizeBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Transaction size {} bytes, message overhead {} bytes",
+            self.total, self.overhead
+        )?;
+        for size in &self.instructions {
+            writeln!(
+                f,
+                "  #{} {}: {} bytes ({} new account keys, accounts {} bytes, data {} bytes){}",
+                size.index,
+                size.program_id,
+                size.total,
+                size.new_account_keys,
+                size.accounts_size,
+                size.data_size,
+                size.description
+                    .as_ref()
+                    .map_or_else(String::new, |description| format!(": {description}"))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Serialized size of the length prefixed (compact-u16) list of bytes
+fn short_vec_size(len: usize) -> usize {
+    let prefix = match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    };
+    prefix + len
+}
@@ -1,14 +1,41 @@
-use crate::prepared_transaction::PreparedTransaction;
+use crate::account_assertion::AccountAssertion;
+use crate::compute_budget::{combine_instruction_packs, ComputeBudgetSetting};
+use crate::instruction_metadata::InstructionMetadata;
+#[cfg(feature = "persistence")]
+use crate::keypair_loader::secret;
+use crate::lighthouse::lighthouse_assertion_instruction;
+use crate::memo::memo_instruction;
+#[cfg(feature = "persistence")]
+use crate::persistence::{
+    read_json, write_json_atomically, BuilderSession, ExportedSecret, KeypairExport, SessionPack,
+    BUILDER_SESSION_VERSION,
+};
+use crate::plan::TransactionPlan;
+use crate::prepared_transaction::{
+    decompile_message, decompile_versioned_message, hash_instructions, PreparedTransaction,
+};
+use crate::remote_signer::RemoteSigner;
 use crate::signature_builder::SignatureBuilder;
+use crate::size_breakdown::TransactionSizeBreakdown;
+#[cfg(feature = "persistence")]
+use crate::transaction_instruction::TransactionInstruction;
 use anyhow::anyhow;
-use log::error;
+use log::{debug, error};
 use once_cell::sync::OnceCell;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::hash::{Hash, Hasher};
+use solana_sdk::message::VersionedMessage;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signers::Signers;
 use solana_sdk::{
-    instruction::Instruction, packet::PACKET_DATA_SIZE, pubkey::Pubkey, signature::Signer,
-    transaction::Transaction,
+    instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    signature::Signer, transaction::Transaction,
 };
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+#[cfg(feature = "persistence")]
+use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -18,16 +45,52 @@ pub enum TransactionBuildError {
     UnknownSigner(Pubkey),
     #[error("Too big transaction")]
     TooBigTransaction,
+    #[error("Instruction index {0} out of bounds")]
+    InstructionIndexOutOfBounds(usize),
+    #[error("Assertion {0:?} is not supported by Lighthouse")]
+    UnsupportedLighthouseAssertion(AccountAssertion),
+    #[error("Fee payer {0} cannot be removed")]
+    FeePayerRemoval(Pubkey),
+    #[error("Lookup table {0} is not available")]
+    MissingLookupTable(Pubkey),
+    #[error("Index {index} out of bounds of lookup table {table}")]
+    LookupTableIndexOutOfBounds { table: Pubkey, index: u8 },
 }
 
-#[derive(Debug, Clone)]
+/// Debug summarizes the packs and prints the signer pubkeys only (safe for production logs).
+#[derive(Clone)]
 pub struct TransactionBuilder {
     fee_payer: Pubkey,
     signature_builder: SignatureBuilder, // invariant: has signers for all instructions
-    // instruction pack contains a list of instruction with optional description to them
-    instruction_packs: Vec<Vec<(Instruction, Option<String>)>>,
-    current_instruction_pack: OnceCell<Vec<(Instruction, Option<String>)>>,
+    // instruction pack contains a list of instruction with optional description and metadata to them
+    instruction_packs: Vec<Vec<(Instruction, InstructionContext)>>,
+    current_instruction_pack: OnceCell<Vec<(Instruction, InstructionContext)>>,
+    // assertions of the instruction packs, kept in sync with the packs
+    pack_assertions: Vec<PackAssertions>,
+    current_pack_assertions: PackAssertions,
     max_transaction_size: usize,
+    // memo prepended to every built transaction
+    memo: Option<String>,
+    // signers not required by the remaining packs are dropped when transactions are built
+    drop_unused_signers: bool,
+    // a finished pack with the same content as a queued one is dropped
+    dedup_packs: bool,
+    // content hashes of the queued packs with their index, built on demand
+    // and reset whenever the queued packs are taken or changed
+    queued_pack_hashes: Option<HashMap<Hash, usize>>,
+}
+
+/// State of the builder captured by `TransactionBuilder::snapshot`.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilderSnapshot {
+    signature_builder: SignatureBuilder,
+    instruction_packs: Vec<Vec<(Instruction, InstructionContext)>>,
+    current_instruction_pack: Option<Vec<(Instruction, InstructionContext)>>,
+    pack_assertions: Vec<PackAssertions>,
+    current_pack_assertions: PackAssertions,
+    memo: Option<String>,
+    drop_unused_signers: bool,
+    dedup_packs: bool,
 }
 
 impl TransactionBuilder {
@@ -38,12 +101,33 @@ impl TransactionBuilder {
             signature_builder,
             instruction_packs: Vec::new(),
             current_instruction_pack: OnceCell::new(),
+            pack_assertions: Vec::new(),
+            current_pack_assertions: PackAssertions::default(),
             max_transaction_size,
+            memo: None,
+            drop_unused_signers: false,
+            dedup_packs: false,
+            queued_pack_hashes: None,
         };
         builder.current_instruction_pack.set(Vec::new()).unwrap();
         builder
     }
 
+    /// Builder holding the instructions split into packs that each fit into `max_transaction_size`.
+    /// Instructions signed by other keys than the fee payer are rejected, see [`Self::add_instructions_split`].
+    pub fn from_instructions<I>(
+        fee_payer: Arc<Keypair>,
+        instructions: I,
+        max_transaction_size: usize,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = Instruction>,
+    {
+        let mut builder = Self::new(fee_payer, max_transaction_size);
+        builder.add_instructions_split(instructions)?;
+        Ok(builder)
+    }
+
     pub fn fee_payer(&self) -> Pubkey {
         self.fee_payer
     }
@@ -66,10 +150,74 @@ impl TransactionBuilder {
         Self::new(fee_payer, 0)
     }
 
+    /// Memo instruction to be prepended to every built transaction
+    pub fn set_memo(&mut self, memo: Option<String>) -> &mut Self {
+        self.memo = memo;
+        self
+    }
+
+    pub fn memo(&self) -> Option<&str> {
+        self.memo.as_deref()
+    }
+
+    /// Signers not referenced by the remaining instruction packs are dropped whenever
+    /// transactions are built (see `prune_signers`), long-running bots do not hold
+    /// the secrets of the already built transactions.
+    pub fn set_drop_unused_signers(&mut self, drop_unused_signers: bool) -> &mut Self {
+        self.drop_unused_signers = drop_unused_signers;
+        self
+    }
+
+    /// Removes the signers (e.g. the ones of `generate_signer` for already built transactions)
+    /// not referenced by any remaining instruction pack, the fee payer is kept.
+    /// Instructions added afterwards require their signers to be added again.
+    pub fn prune_signers(&mut self) {
+        let remaining = self.new_transaction(&self.instructions());
+        self.signature_builder.retain_for(&[remaining]);
+    }
+
     pub fn add_signer(&mut self, signer: Arc<Keypair>) -> Pubkey {
         self.signature_builder.add_signer(signer)
     }
 
+    /// Transactions signed by the remote signer have to be signed by `PreparedTransaction::sign_message`.
+    pub fn add_remote_signer(&mut self, signer: Arc<dyn RemoteSigner>) -> Pubkey {
+        self.signature_builder.add_remote_signer(signer)
+    }
+
+    /// Registers a signer whose signature is added externally (e.g. a multisig authority
+    /// or a cold key), the built transactions list it in `PreparedTransaction::external_signers`.
+    pub fn add_placeholder_signer(&mut self, pubkey: Pubkey) -> Pubkey {
+        self.signature_builder.add_placeholder_signer(pubkey)
+    }
+
+    /// Removes the signer, returns whether it was registered. The fee payer cannot be removed,
+    /// instructions requiring the signer cannot be signed afterwards.
+    pub fn remove_signer(&mut self, key: &Pubkey) -> Result<bool, TransactionBuildError> {
+        if *key == self.fee_payer {
+            return Err(TransactionBuildError::FeePayerRemoval(*key));
+        }
+        Ok(self.signature_builder.remove_signer(key))
+    }
+
+    /// Removes all the signers but the fee payer.
+    pub fn clear_signers(&mut self) {
+        let fee_payer = self.fee_payer_signer();
+        self.signature_builder.clear();
+        self.signature_builder.add_signer(fee_payer);
+    }
+
+    /// Adds the signers of the other builder, the fee payer is kept.
+    pub fn merge_signers(&mut self, signature_builder: SignatureBuilder) {
+        let fee_payer = self.fee_payer_signer();
+        self.signature_builder.merge(signature_builder);
+        self.signature_builder.add_signer(fee_payer);
+    }
+
+    pub fn signature_builder(&self) -> &SignatureBuilder {
+        &self.signature_builder
+    }
+
     pub fn generate_signer(&mut self) -> Pubkey {
         self.signature_builder.new_signer()
     }
@@ -95,14 +243,42 @@ impl TransactionBuilder {
         Ok(())
     }
 
+    /// Finished packs with the same instructions (program ids, account metas and data
+    /// as they were added) as an already queued pack are dropped, e.g. the same operation
+    /// enqueued twice in a planning loop. The assertions of a dropped pack move to the queued one.
+    pub fn set_dedup_packs(&mut self, dedup_packs: bool) -> &mut Self {
+        self.dedup_packs = dedup_packs;
+        self.queued_pack_hashes = None;
+        self
+    }
+
     #[inline]
     pub fn finish_instruction_pack(&mut self) {
-        self.instruction_packs.push(
-            self.current_instruction_pack
-                .take()
-                .expect("Finish must be called when an instruction pack is defined"),
-        );
+        let pack = self
+            .current_instruction_pack
+            .take()
+            .expect("Finish must be called when an instruction pack is defined");
         self.current_instruction_pack.set(Vec::new()).unwrap();
+        let assertions = std::mem::take(&mut self.current_pack_assertions);
+        if self.dedup_packs && !pack.is_empty() {
+            let instruction_packs = &self.instruction_packs;
+            let queued_pack_hashes = self.queued_pack_hashes.get_or_insert_with(|| {
+                instruction_packs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, queued)| (pack_content_hash(queued), index))
+                    .collect()
+            });
+            let pack_hash = pack_content_hash(&pack);
+            if let Some(&queued_index) = queued_pack_hashes.get(&pack_hash) {
+                debug!("Dropping the instruction pack duplicating a queued one ({pack_hash})");
+                self.pack_assertions[queued_index].extend(assertions);
+                return;
+            }
+            queued_pack_hashes.insert(pack_hash, self.instruction_packs.len());
+        }
+        self.instruction_packs.push(pack);
+        self.pack_assertions.push(assertions);
     }
 
     #[inline]
@@ -110,6 +286,38 @@ impl TransactionBuilder {
         self.current_instruction_pack
             .take()
             .expect("Abort must be called when an instruction pack is defined");
+        self.current_pack_assertions = PackAssertions::default();
+    }
+
+    /// Attaches an assertion on the chain state to the current instruction pack,
+    /// the executor verifies it right before the transaction containing the pack is sent.
+    pub fn add_pre_assertion(&mut self, assertion: AccountAssertion) -> &mut Self {
+        self.current_pack_assertions.pre.push(assertion);
+        self
+    }
+
+    /// Attaches an assertion on the chain state to the current instruction pack,
+    /// the executor verifies it once the transaction containing the pack is confirmed.
+    pub fn add_post_assertion(&mut self, assertion: AccountAssertion) -> &mut Self {
+        self.current_pack_assertions.post.push(assertion);
+        self
+    }
+
+    /// Appends a Lighthouse instruction to the current instruction pack, the transaction
+    /// containing the pack reverts on chain when the assertion does not hold.
+    pub fn add_lighthouse_assertion(
+        &mut self,
+        assertion: AccountAssertion,
+    ) -> anyhow::Result<&mut Self> {
+        let instruction = lighthouse_assertion_instruction(&assertion).ok_or_else(|| {
+            anyhow!(TransactionBuildError::UnsupportedLighthouseAssertion(
+                assertion.clone()
+            ))
+        })?;
+        self.add_instruction_with_description(
+            instruction,
+            format!("Lighthouse assertion {assertion:?}"),
+        )
     }
 
     #[inline]
@@ -117,8 +325,66 @@ impl TransactionBuilder {
         self.is_current_pack_empty() && self.instruction_packs.is_empty()
     }
 
+    /// Captures the packs, the signers and the settings, a speculative sequence of adds
+    /// can be rolled back by `restore`.
     #[inline]
-    fn is_current_pack_empty(&self) -> bool {
+    pub fn snapshot(&self) -> TransactionBuilderSnapshot {
+        TransactionBuilderSnapshot {
+            signature_builder: self.signature_builder.clone(),
+            instruction_packs: self.instruction_packs.clone(),
+            current_instruction_pack: self.current_instruction_pack.get().cloned(),
+            pack_assertions: self.pack_assertions.clone(),
+            current_pack_assertions: self.current_pack_assertions.clone(),
+            memo: self.memo.clone(),
+            drop_unused_signers: self.drop_unused_signers,
+            dedup_packs: self.dedup_packs,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: TransactionBuilderSnapshot) {
+        self.signature_builder = snapshot.signature_builder;
+        self.instruction_packs = snapshot.instruction_packs;
+        self.current_instruction_pack = OnceCell::new();
+        if let Some(current_instruction_pack) = snapshot.current_instruction_pack {
+            self.current_instruction_pack
+                .set(current_instruction_pack)
+                .unwrap();
+        }
+        self.pack_assertions = snapshot.pack_assertions;
+        self.current_pack_assertions = snapshot.current_pack_assertions;
+        self.memo = snapshot.memo;
+        self.drop_unused_signers = snapshot.drop_unused_signers;
+        self.dedup_packs = snapshot.dedup_packs;
+        self.queued_pack_hashes = None;
+    }
+
+    /// Finished instruction packs plus the current one when it is not empty.
+    pub fn packs_len(&self) -> usize {
+        self.instruction_packs.len() + usize::from(!self.is_current_pack_empty())
+    }
+
+    pub fn instructions_len(&self) -> usize {
+        self.instruction_packs.iter().map(Vec::len).sum::<usize>()
+            + self.current_instruction_pack.get().map_or(0, Vec::len)
+    }
+
+    /// Number of transactions the builder is built into, the builder is not changed.
+    pub fn estimated_transaction_count(&self, combined: bool) -> usize {
+        if combined {
+            self.clone().sequence_combined().count()
+        } else {
+            self.packs_len()
+        }
+    }
+
+    /// Serialized size of the transaction of the current instruction pack.
+    pub fn current_pack_size_bytes(&self) -> usize {
+        self.current_pack_transaction_size()
+            .expect("Serialization into memory cannot fail")
+    }
+
+    /// No instruction was added since the last finished pack.
+    pub fn is_current_pack_empty(&self) -> bool {
         if let Some(current_instruction_pack) = self.current_instruction_pack.get() {
             current_instruction_pack.is_empty()
         } else {
@@ -136,6 +402,68 @@ impl TransactionBuilder {
         Ok(self)
     }
 
+    /// Adds the instructions in order, finishing the current pack whenever the next instruction
+    /// does not fit into it. The last pack is finished as well.
+    pub fn add_instructions_split<I>(&mut self, instructions: I) -> anyhow::Result<&mut Self>
+    where
+        I: IntoIterator<Item = Instruction>,
+    {
+        for instruction in instructions {
+            if let Err(err) = self.add_instruction(instruction.clone()) {
+                let too_big = matches!(
+                    err.downcast_ref::<TransactionBuildError>(),
+                    Some(TransactionBuildError::TooBigTransaction)
+                );
+                if !too_big || self.is_current_pack_empty() {
+                    return Err(err);
+                }
+                self.finish_instruction_pack();
+                self.add_instruction(instruction)?;
+            }
+        }
+        if !self.is_current_pack_empty() {
+            self.finish_instruction_pack();
+        }
+        Ok(self)
+    }
+
+    /// Decompiles the (unsigned) transaction back into instructions and appends them as a new pack.
+    /// Signatures of the transaction are ignored, the signers have to be known to the builder.
+    pub fn add_transaction(&mut self, transaction: &Transaction) -> anyhow::Result<&mut Self> {
+        self.add_message(&transaction.message)
+    }
+
+    /// Decompiles the message back into instructions and appends them as a new pack.
+    /// The message fee payer is not carried over, the builder fee payer pays for the pack.
+    pub fn add_message(&mut self, message: &Message) -> anyhow::Result<&mut Self> {
+        self.add_decompiled_pack(decompile_message(message))
+    }
+
+    /// Decompiles the versioned message back into instructions and appends them as a new pack.
+    /// Address table lookups of a v0 message are resolved from the passed lookup tables.
+    pub fn add_versioned_message(
+        &mut self,
+        message: &VersionedMessage,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<&mut Self> {
+        let instructions = decompile_versioned_message(message, lookup_tables)?;
+        self.add_decompiled_pack(instructions)
+    }
+
+    fn add_decompiled_pack(&mut self, instructions: Vec<Instruction>) -> anyhow::Result<&mut Self> {
+        if !self.is_current_pack_empty() {
+            self.finish_instruction_pack();
+        }
+        for instruction in instructions {
+            if let Err(err) = self.add_instruction(instruction) {
+                self.current_instruction_pack.get_mut().unwrap().clear();
+                return Err(err);
+            }
+        }
+        self.finish_instruction_pack();
+        Ok(self)
+    }
+
     pub fn add_instructions_with_description<I>(
         &mut self,
         instructions_with_description: I,
@@ -150,7 +478,7 @@ impl TransactionBuilder {
     }
 
     pub fn add_instruction(&mut self, instruction: Instruction) -> anyhow::Result<&mut Self> {
-        self.add_instruction_internal(instruction, None)
+        self.add_instruction_internal(instruction, InstructionContext::default())
     }
 
     pub fn add_instruction_with_description(
@@ -158,37 +486,354 @@ impl TransactionBuilder {
         instruction: Instruction,
         description: String,
     ) -> anyhow::Result<&mut Self> {
-        self.add_instruction_internal(instruction, Some(description))
+        self.add_instruction_internal(instruction, InstructionContext::described(description))
+    }
+
+    /// Adds the instruction with user metadata that is carried into the prepared transaction
+    /// and the execution reports, see `InstructionMetadata::downcast_ref`.
+    pub fn add_instruction_with_metadata<M: Any + Debug + Send + Sync>(
+        &mut self,
+        instruction: Instruction,
+        metadata: M,
+    ) -> anyhow::Result<&mut Self> {
+        self.add_instruction_internal(
+            instruction,
+            InstructionContext {
+                metadata: Some(InstructionMetadata::new(metadata)),
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn add_instruction_with_description_and_metadata<M: Any + Debug + Send + Sync>(
+        &mut self,
+        instruction: Instruction,
+        description: String,
+        metadata: M,
+    ) -> anyhow::Result<&mut Self> {
+        self.add_instruction_internal(
+            instruction,
+            InstructionContext {
+                description: Some(description),
+                metadata: Some(InstructionMetadata::new(metadata)),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Adds the instruction with key-value tags, the tags of all the instructions of a transaction
+    /// are aggregated (see `PreparedTransaction::tags`) and become labels of its execution.
+    pub fn add_instruction_with_tags(
+        &mut self,
+        instruction: Instruction,
+        tags: HashMap<String, String>,
+    ) -> anyhow::Result<&mut Self> {
+        self.add_instruction_internal(
+            instruction,
+            InstructionContext {
+                tags,
+                ..Default::default()
+            },
+        )
     }
 
     fn add_instruction_internal(
         &mut self,
         instruction: Instruction,
-        description: Option<String>,
+        context: InstructionContext,
+    ) -> anyhow::Result<&mut Self> {
+        let index = self.current_instruction_pack.get().map_or(0, Vec::len);
+        self.insert_instruction_internal(index, instruction, context)
+    }
+
+    /// Inserts the instruction at the position within the current instruction pack.
+    pub fn insert_instruction(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+    ) -> anyhow::Result<&mut Self> {
+        self.insert_instruction_internal(index, instruction, InstructionContext::default())
+    }
+
+    pub fn insert_instruction_with_description(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+        description: String,
+    ) -> anyhow::Result<&mut Self> {
+        self.insert_instruction_internal(
+            index,
+            instruction,
+            InstructionContext::described(description),
+        )
+    }
+
+    fn insert_instruction_internal(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+        context: InstructionContext,
     ) -> anyhow::Result<&mut Self> {
         self.check_signers(&instruction)?;
         let current = self.current_instruction_pack.get_mut().unwrap();
+        if index > current.len() {
+            return Err(anyhow!(TransactionBuildError::InstructionIndexOutOfBounds(
+                index
+            )));
+        }
 
-        current.push((instruction, description));
-        let transaction_candidate = Transaction::new_with_payer(
-            &current.iter().cloned().unzip::<_, _, Vec<_>, Vec<_>>().0,
-            Some(&self.fee_payer),
-        );
-        let tx_size_candidate = bincode::serialize(&transaction_candidate)?.len();
+        current.insert(index, (instruction, context));
+        let tx_size_candidate = self.current_pack_transaction_size()?;
         if self.max_transaction_size > 0 && tx_size_candidate > self.max_transaction_size {
-            // Transaction is too big to add new instruction, remove the last one
-            current.pop();
-            let transaction_current = bincode::serialize(&transaction_candidate)?.len();
-            let tx_size_current = bincode::serialize(&transaction_current)?.len();
+            let breakdown = self.current_pack_size_breakdown();
+            // Transaction is too big to add new instruction, remove it
+            self.current_instruction_pack
+                .get_mut()
+                .unwrap()
+                .remove(index);
             error!(
-                "add_instruction: too big transaction, tx size with added transaction: {}, original tx size: {},  max size: {}",
-                tx_size_candidate,  tx_size_current, self.max_transaction_size);
+                "add_instruction: too big transaction with the added instruction #{index}, max size: {}, {breakdown}",
+                self.max_transaction_size
+            );
             return Err(anyhow!(TransactionBuildError::TooBigTransaction));
         }
 
         Ok(self)
     }
 
+    /// Replaces the instruction at the position within the current instruction pack.
+    /// Returns the replaced instruction.
+    pub fn replace_instruction(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+    ) -> anyhow::Result<Instruction> {
+        self.replace_instruction_internal(index, instruction, InstructionContext::default())
+    }
+
+    pub fn replace_instruction_with_description(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+        description: String,
+    ) -> anyhow::Result<Instruction> {
+        self.replace_instruction_internal(
+            index,
+            instruction,
+            InstructionContext::described(description),
+        )
+    }
+
+    fn replace_instruction_internal(
+        &mut self,
+        index: usize,
+        instruction: Instruction,
+        context: InstructionContext,
+    ) -> anyhow::Result<Instruction> {
+        self.check_signers(&instruction)?;
+        let current = self.current_instruction_pack.get_mut().unwrap();
+        let replaced = std::mem::replace(
+            current
+                .get_mut(index)
+                .ok_or(TransactionBuildError::InstructionIndexOutOfBounds(index))?,
+            (instruction, context),
+        );
+
+        let tx_size_candidate = self.current_pack_transaction_size()?;
+        if self.max_transaction_size > 0 && tx_size_candidate > self.max_transaction_size {
+            let breakdown = self.current_pack_size_breakdown();
+            // Transaction is too big with the new instruction, put the original one back
+            self.current_instruction_pack.get_mut().unwrap()[index] = replaced;
+            error!(
+                "replace_instruction: too big transaction with the replaced instruction #{index}, max size: {}, {breakdown}",
+                self.max_transaction_size
+            );
+            return Err(anyhow!(TransactionBuildError::TooBigTransaction));
+        }
+
+        Ok(replaced.0)
+    }
+
+    /// Sets the compute-budget instruction of the current instruction pack, replacing
+    /// an instruction of the same kind or inserting it at the beginning of the pack.
+    pub fn set_compute_budget_setting(
+        &mut self,
+        setting: ComputeBudgetSetting,
+    ) -> anyhow::Result<&mut Self> {
+        let existing = self.current_instruction_pack.get().and_then(|current| {
+            current.iter().position(|(instruction, _)| {
+                ComputeBudgetSetting::from_instruction(instruction)
+                    .map_or(false, |existing| existing.is_same_kind(&setting))
+            })
+        });
+        match existing {
+            Some(index) => {
+                self.replace_instruction(index, setting.to_instruction())?;
+                Ok(self)
+            }
+            None => self.insert_instruction(0, setting.to_instruction()),
+        }
+    }
+
+    /// Removes the last instruction of the current instruction pack.
+    pub fn remove_last_instruction(&mut self) -> Option<Instruction> {
+        self.current_instruction_pack
+            .get_mut()
+            .and_then(|current| current.pop())
+            .map(|(instruction, _)| instruction)
+    }
+
+    fn current_pack_transaction_size(&self) -> anyhow::Result<usize> {
+        let instructions: Vec<Instruction> =
+            self.current_instruction_pack
+                .get()
+                .map_or_else(Vec::new, |current| {
+                    current
+                        .iter()
+                        .map(|(instruction, _)| instruction.clone())
+                        .collect()
+                });
+        let transaction = self.new_transaction(&instructions);
+        Ok(bincode::serialize(&transaction)?.len())
+    }
+
+    /// Bytes contributed by the instructions of the current pack, see `TransactionSizeBreakdown`.
+    pub fn current_pack_size_breakdown(&self) -> TransactionSizeBreakdown {
+        let (instructions, descriptions): (Vec<Instruction>, Vec<Option<String>>) = self
+            .current_instruction_pack
+            .get()
+            .map_or_else(Vec::new, |current| {
+                current
+                    .iter()
+                    .map(|(instruction, context)| {
+                        (instruction.clone(), context.description.clone())
+                    })
+                    .collect()
+            })
+            .into_iter()
+            .unzip();
+        let descriptions = if self.memo.is_some() {
+            std::iter::once(Some("memo".to_string()))
+                .chain(descriptions)
+                .collect()
+        } else {
+            descriptions
+        };
+        TransactionSizeBreakdown::new(&self.new_transaction(&instructions), &descriptions)
+    }
+
+    fn retain_signers_of_remaining_packs(&mut self) {
+        if self.drop_unused_signers {
+            self.prune_signers();
+        }
+    }
+
+    fn new_transaction(&self, instructions: &[Instruction]) -> Transaction {
+        if let Some(memo) = &self.memo {
+            let mut instructions_with_memo = Vec::with_capacity(instructions.len() + 1);
+            instructions_with_memo.push(memo_instruction(memo));
+            instructions_with_memo.extend_from_slice(instructions);
+            Transaction::new_with_payer(&instructions_with_memo, Some(&self.fee_payer))
+        } else {
+            Transaction::new_with_payer(instructions, Some(&self.fee_payer))
+        }
+    }
+
+    fn new_prepared_transaction(
+        &self,
+        instructions: &[Instruction],
+        mut contexts: Vec<InstructionContext>,
+    ) -> PreparedTransaction {
+        if self.memo.is_some() {
+            contexts.insert(0, InstructionContext::default());
+        }
+        let mut descriptions = Vec::with_capacity(contexts.len());
+        let mut metadata = Vec::with_capacity(contexts.len());
+        let mut tags = Vec::with_capacity(contexts.len());
+        for context in contexts {
+            descriptions.push(context.description);
+            metadata.push(context.metadata);
+            tags.push(context.tags);
+        }
+        PreparedTransaction::new(
+            self.new_transaction(instructions),
+            &self.signature_builder,
+            descriptions,
+        )
+        .expect("Signature keys must be checked when instruction added")
+        .with_fee_payer_meta(instructions)
+        .with_instruction_metadata(metadata)
+        .with_instruction_tags(tags)
+    }
+
+    /// Removes instructions considered by the predicate as duplicates of an instruction
+    /// added before them (in the same or in an earlier pack), e.g. repeated idempotent
+    /// ATA creation. The compute-budget instructions are never removed as every pack
+    /// may be built alone. Packs left with no other instructions are dropped, their assertions
+    /// move to the latest pack holding the instructions they duplicated.
+    /// Returns the number of removed instructions.
+    pub fn dedup_instructions<F>(&mut self, is_duplicate: F) -> usize
+    where
+        F: Fn(&Instruction, &Instruction) -> bool,
+    {
+        // kept instructions with the index of their pack
+        let mut kept: Vec<(Instruction, usize)> = Vec::new();
+        // pack emptied by the dedup and the pack its assertions move to
+        let mut emptied: Vec<(usize, usize)> = Vec::new();
+        let mut removed = 0;
+        for (pack_index, pack) in self
+            .instruction_packs
+            .iter_mut()
+            .chain(self.current_instruction_pack.get_mut())
+            .enumerate()
+        {
+            let mut merged_into = None;
+            pack.retain(|(instruction, _)| {
+                if ComputeBudgetSetting::from_instruction(instruction).is_some() {
+                    return true;
+                }
+                match kept
+                    .iter()
+                    .find(|(previous, _)| is_duplicate(previous, instruction))
+                {
+                    Some((_, kept_pack_index)) => {
+                        removed += 1;
+                        merged_into = merged_into.max(Some(*kept_pack_index));
+                        false
+                    }
+                    None => {
+                        kept.push((instruction.clone(), pack_index));
+                        true
+                    }
+                }
+            });
+            let only_compute_budget = pack.iter().all(|(instruction, _)| {
+                ComputeBudgetSetting::from_instruction(instruction).is_some()
+            });
+            if let (Some(merged_into), true) = (merged_into, only_compute_budget) {
+                pack.clear();
+                emptied.push((pack_index, merged_into));
+            }
+        }
+        for (pack_index, merged_into) in emptied {
+            let assertions = match self.pack_assertions.get_mut(pack_index) {
+                Some(assertions) => std::mem::take(assertions),
+                None => std::mem::take(&mut self.current_pack_assertions),
+            };
+            self.pack_assertions[merged_into].extend(assertions);
+        }
+        self.queued_pack_hashes = None;
+        let mut pack_assertions = std::mem::take(&mut self.pack_assertions).into_iter();
+        self.instruction_packs.retain(|pack| {
+            let assertions = pack_assertions.next().unwrap_or_default();
+            if !pack.is_empty() {
+                self.pack_assertions.push(assertions);
+            }
+            !pack.is_empty()
+        });
+        removed
+    }
+
     /// This method removes the transactions from the returned transaction pack from the builder.
     /// Next call returns the next pack of transactions.
     pub fn build_next(&mut self) -> Option<PreparedTransaction> {
@@ -199,18 +844,45 @@ impl TransactionBuilder {
             return None;
         }
         if !self.instruction_packs.is_empty() {
-            let (instructions, descriptions): (Vec<Instruction>, Vec<Option<String>>) =
+            self.queued_pack_hashes = None;
+            let (instructions, contexts): (Vec<Instruction>, Vec<InstructionContext>) =
                 self.instruction_packs.remove(0).into_iter().unzip();
-            let transaction = Transaction::new_with_payer(&instructions, Some(&self.fee_payer));
-            Some(
-                PreparedTransaction::new(transaction, &self.signature_builder, descriptions)
-                    .expect("Signature keys must be checked when instruction added"),
-            )
+            let assertions = self.pack_assertions.remove(0);
+            let prepared_transaction =
+                assertions.apply(self.new_prepared_transaction(&instructions, contexts));
+            self.retain_signers_of_remaining_packs();
+            Some(prepared_transaction)
         } else {
             None
         }
     }
 
+    /// Builds all the instruction packs (one transaction per pack) on the rayon thread pool,
+    /// the transactions are returned in the order of the packs and the builder is emptied.
+    #[cfg(feature = "rayon")]
+    pub fn build_all_parallel(&mut self) -> Vec<PreparedTransaction> {
+        use rayon::prelude::*;
+
+        if !self.is_current_pack_empty() {
+            self.finish_instruction_pack()
+        }
+        let instruction_packs = std::mem::take(&mut self.instruction_packs);
+        let pack_assertions = std::mem::take(&mut self.pack_assertions);
+        self.queued_pack_hashes = None;
+        let builder: &Self = self;
+        let prepared_transactions = instruction_packs
+            .into_par_iter()
+            .zip(pack_assertions.into_par_iter())
+            .map(|(instruction_pack, assertions)| {
+                let (instructions, contexts): (Vec<Instruction>, Vec<InstructionContext>) =
+                    instruction_pack.into_iter().unzip();
+                assertions.apply(builder.new_prepared_transaction(&instructions, contexts))
+            })
+            .collect();
+        self.retain_signers_of_remaining_packs();
+        prepared_transactions
+    }
+
     pub fn build_one(&mut self) -> PreparedTransaction {
         if let Some(transaction) = self.build_next() {
             assert!(self.instruction_packs.is_empty());
@@ -221,6 +893,7 @@ impl TransactionBuilder {
     }
 
     /// Next transaction from builder. It merges multiple transaction packs together (as much as fits into tx).
+    /// Compute-budget instructions of the merged packs are combined into a single pair.
     /// This method removes the transactions from the returned transaction pack from the builder.
     /// Next call returns the next pack of transactions.
     pub fn build_next_combined(&mut self) -> Option<PreparedTransaction> {
@@ -231,47 +904,43 @@ impl TransactionBuilder {
             return None;
         }
 
-        let (transaction, descriptions) = if self.max_transaction_size == 0 {
-            let (instructions, descriptions): (Vec<Instruction>, Vec<Option<String>>) =
-                self.instruction_packs.drain(..).flatten().unzip();
-            (
-                Transaction::new_with_payer(&instructions, Some(&self.fee_payer)),
-                descriptions,
-            )
+        self.queued_pack_hashes = None;
+        let mut assertions = PackAssertions::default();
+        let (instructions, contexts) = if self.max_transaction_size == 0 {
+            for pack_assertions in std::mem::take(&mut self.pack_assertions) {
+                assertions.extend(pack_assertions);
+            }
+            combine_instruction_packs(&std::mem::take(&mut self.instruction_packs))
         } else {
             // One pack must fit transaction anyway
-            let (mut instructions, mut descriptions): (Vec<Instruction>, Vec<Option<String>>) =
-                self.instruction_packs.remove(0).into_iter().unzip();
-            let mut transaction = Transaction::new_with_payer(&instructions, Some(&self.fee_payer));
+            let mut packs = vec![self.instruction_packs.remove(0)];
+            assertions.extend(self.pack_assertions.remove(0));
+            let mut combined = combine_instruction_packs(&packs);
             while let Some(next_pack) = self.instruction_packs.first() {
-                let (next_instructions, next_descriptions): (
-                    Vec<Instruction>,
-                    Vec<Option<String>>,
-                ) = next_pack.iter().cloned().unzip();
                 // Try to add next pack
-                instructions.extend(next_instructions.into_iter());
-                descriptions.extend(next_descriptions.into_iter());
-                let transaction_candidate =
-                    Transaction::new_with_payer(&instructions, Some(&self.fee_payer));
+                packs.push(next_pack.clone());
+                let candidate = combine_instruction_packs(&packs);
+                let transaction_candidate = self.new_transaction(&candidate.0);
 
                 if bincode::serialize(&transaction_candidate).unwrap().len()
                     <= self.max_transaction_size
                 {
                     // Accept it
-                    transaction = transaction_candidate;
+                    combined = candidate;
                     // and move to the next pack
                     self.instruction_packs.remove(0);
+                    assertions.extend(self.pack_assertions.remove(0));
                 } else {
                     // Stop trying
                     break;
                 }
             }
-            (transaction, descriptions)
+            combined
         };
-        Some(
-            PreparedTransaction::new(transaction, &self.signature_builder, descriptions)
-                .expect("Signature keys must be checked when instruction added"),
-        )
+        let prepared_transaction =
+            assertions.apply(self.new_prepared_transaction(&instructions, contexts));
+        self.retain_signers_of_remaining_packs();
+        Some(prepared_transaction)
     }
 
     pub fn build_single_combined(&mut self) -> Option<PreparedTransaction> {
@@ -291,9 +960,22 @@ impl TransactionBuilder {
         CombinedSequence { builder: self }
     }
 
+    /// Summary of the transactions to be built (by `build_next` or `build_next_combined`),
+    /// the builder is left untouched.
+    pub fn plan(&self, combined: bool) -> TransactionPlan {
+        let mut builder = self.clone();
+        let instruction_packs = builder.packs_len();
+        let prepared_transactions: Vec<PreparedTransaction> = if combined {
+            builder.sequence_combined().collect()
+        } else {
+            builder.sequence().collect()
+        };
+        TransactionPlan::new(self.fee_payer, instruction_packs, &prepared_transactions)
+    }
+
     pub fn fits_single_transaction(&self) -> bool {
         let instructions: Vec<Instruction> = self.instructions();
-        let transaction = Transaction::new_with_payer(&instructions, Some(&self.fee_payer));
+        let transaction = self.new_transaction(&instructions);
         bincode::serialize(&transaction).unwrap().len() <= self.max_transaction_size
     }
 
@@ -312,6 +994,265 @@ impl TransactionBuilder {
     }
 }
 
+#[cfg(feature = "persistence")]
+impl TransactionBuilder {
+    /// Instruction packs, the memo and the registered signers (pubkeys, the secrets
+    /// by the export policy) to resume the planning with `from_session`.
+    /// Packs with assertions or instruction metadata cannot be persisted.
+    pub fn to_session(&self, keypair_export: &KeypairExport) -> anyhow::Result<BuilderSession> {
+        if self
+            .pack_assertions
+            .iter()
+            .chain(std::iter::once(&self.current_pack_assertions))
+            .any(|assertions| !assertions.pre.is_empty() || !assertions.post.is_empty())
+        {
+            return Err(anyhow!(
+                "Instruction packs with assertions cannot be persisted"
+            ));
+        }
+        let session_pack = |pack: &[(Instruction, InstructionContext)]| {
+            if pack.iter().any(|(_, context)| context.metadata.is_some()) {
+                return Err(anyhow!("Instructions with metadata cannot be persisted"));
+            }
+            Ok(SessionPack {
+                instructions: pack
+                    .iter()
+                    .map(|(instruction, _)| TransactionInstruction::from(instruction))
+                    .collect(),
+                descriptions: pack
+                    .iter()
+                    .map(|(_, context)| context.description.clone())
+                    .collect(),
+                tags: pack
+                    .iter()
+                    .map(|(_, context)| context.tags.clone())
+                    .collect(),
+            })
+        };
+        let signers: Vec<Pubkey> = self.signature_builder.keys().cloned().collect();
+        Ok(BuilderSession {
+            version: BUILDER_SESSION_VERSION,
+            fee_payer: self.fee_payer.to_string(),
+            max_transaction_size: self.max_transaction_size,
+            memo: self.memo.clone(),
+            drop_unused_signers: self.drop_unused_signers,
+            signers: signers.iter().map(Pubkey::to_string).collect(),
+            placeholder_signers: signers
+                .iter()
+                .filter(|pubkey| self.signature_builder.is_placeholder_signer(pubkey))
+                .map(Pubkey::to_string)
+                .collect(),
+            keypairs: signers
+                .iter()
+                .filter(|pubkey| keypair_export.exports(pubkey))
+                .filter_map(|pubkey| self.signature_builder.get_signer(pubkey))
+                .map(|keypair| ExportedSecret::new(keypair.to_base58_string()))
+                .collect(),
+            packs: self
+                .instruction_packs
+                .iter()
+                .map(|pack| session_pack(pack.as_slice()))
+                .collect::<anyhow::Result<_>>()?,
+            current_pack: session_pack(
+                self.current_instruction_pack
+                    .get()
+                    .map_or(&[][..], Vec::as_slice),
+            )?,
+        })
+    }
+
+    /// Signers not exported into the session are taken from the signature builder.
+    pub fn from_session(
+        session: BuilderSession,
+        signature_builder: &SignatureBuilder,
+    ) -> anyhow::Result<Self> {
+        if session.version != BUILDER_SESSION_VERSION {
+            return Err(anyhow!(
+                "Unsupported builder session version {}",
+                session.version
+            ));
+        }
+        let mut exported = HashMap::new();
+        for secret in &session.keypairs {
+            let keypair = bs58::decode(secret.as_bytes())
+                .into_vec()
+                .map(secret)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| Ok(Keypair::from_bytes(&bytes)?))
+                .map_err(|err| anyhow!("Invalid keypair of the builder session: {err}"))?;
+            exported.insert(keypair.pubkey(), Arc::new(keypair));
+        }
+        let keypair = |pubkey: &Pubkey| {
+            exported
+                .get(pubkey)
+                .cloned()
+                .or_else(|| signature_builder.get_signer(pubkey))
+        };
+        let fee_payer = Pubkey::from_str(&session.fee_payer)?;
+        let fee_payer_signer = keypair(&fee_payer).ok_or_else(|| {
+            anyhow!("No keypair of the fee payer {fee_payer} to resume the session")
+        })?;
+        let mut builder = Self::new(fee_payer_signer, session.max_transaction_size);
+        builder.set_memo(session.memo);
+        builder.set_drop_unused_signers(session.drop_unused_signers);
+        let placeholder_signers: Vec<String> = session.placeholder_signers;
+        for signer in &session.signers {
+            let pubkey = Pubkey::from_str(signer)?;
+            if pubkey == fee_payer {
+                continue;
+            }
+            if placeholder_signers.contains(signer) {
+                builder.add_placeholder_signer(pubkey);
+            } else if let Some(keypair) = keypair(&pubkey) {
+                builder.add_signer(keypair);
+            } else if let Some(remote_signer) = signature_builder.get_remote_signer(&pubkey) {
+                builder.add_remote_signer(remote_signer);
+            } else {
+                return Err(anyhow!("No signer of {pubkey} to resume the session"));
+            }
+        }
+        for pack in session.packs {
+            builder.add_session_pack(pack)?;
+            builder.finish_instruction_pack();
+        }
+        builder.add_session_pack(session.current_pack)?;
+        Ok(builder)
+    }
+
+    /// Writes the session as JSON, the file is replaced atomically.
+    pub fn save_session<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        keypair_export: &KeypairExport,
+    ) -> anyhow::Result<()> {
+        write_json_atomically(path.as_ref(), &self.to_session(keypair_export)?)
+    }
+
+    pub fn load_session<P: AsRef<std::path::Path>>(
+        path: P,
+        signature_builder: &SignatureBuilder,
+    ) -> anyhow::Result<Self> {
+        Self::from_session(read_json(path.as_ref())?, signature_builder)
+    }
+
+    fn add_session_pack(&mut self, pack: SessionPack) -> anyhow::Result<()> {
+        let mut descriptions = pack.descriptions.into_iter();
+        let mut tags = pack.tags.into_iter();
+        for instruction in &pack.instructions {
+            self.add_instruction_internal(
+                Instruction::from(instruction),
+                InstructionContext {
+                    description: descriptions.next().flatten(),
+                    metadata: None,
+                    tags: tags.next().unwrap_or_default(),
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn pack_content_hash(pack: &[(Instruction, InstructionContext)]) -> Hash {
+    let instructions: Vec<Instruction> = pack
+        .iter()
+        .map(|(instruction, _)| instruction.clone())
+        .collect();
+    let mut hasher = Hasher::default();
+    hash_instructions(&mut hasher, &instructions);
+    hasher.result()
+}
+
+impl Debug for TransactionBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransactionBuilder")
+            .field("fee_payer", &self.fee_payer)
+            .field("signers", &self.signature_builder)
+            .field(
+                "instruction_packs",
+                &self
+                    .instruction_packs
+                    .iter()
+                    .map(|pack| PackSummary(pack.as_slice()))
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "current_instruction_pack",
+                &PackSummary(
+                    self.current_instruction_pack
+                        .get()
+                        .map_or(&[][..], Vec::as_slice),
+                ),
+            )
+            .field("instructions", &self.instructions_len())
+            .field("max_transaction_size", &self.max_transaction_size)
+            .field("memo", &self.memo)
+            .finish()
+    }
+}
+
+// Instruction count, programs and descriptions of a pack
+struct PackSummary<'a>(&'a [(Instruction, InstructionContext)]);
+
+impl Debug for PackSummary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pack")
+            .field("instructions", &self.0.len())
+            .field(
+                "programs",
+                &self
+                    .0
+                    .iter()
+                    .map(|(instruction, _)| instruction.program_id)
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "descriptions",
+                &self
+                    .0
+                    .iter()
+                    .filter_map(|(_, context)| context.description.as_deref())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+// Context of an instruction within an instruction pack
+#[derive(Debug, Clone, Default)]
+struct InstructionContext {
+    description: Option<String>,
+    metadata: Option<InstructionMetadata>,
+    tags: HashMap<String, String>,
+}
+
+impl InstructionContext {
+    fn described(description: String) -> Self {
+        Self {
+            description: Some(description),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PackAssertions {
+    pre: Vec<AccountAssertion>,
+    post: Vec<AccountAssertion>,
+}
+
+impl PackAssertions {
+    fn extend(&mut self, other: PackAssertions) {
+        self.pre.extend(other.pre);
+        self.post.extend(other.post);
+    }
+
+    fn apply(self, prepared_transaction: PreparedTransaction) -> PreparedTransaction {
+        prepared_transaction
+            .with_pre_assertions(self.pre)
+            .with_post_assertions(self.post)
+    }
+}
+
 pub struct Sequence<'a> {
     builder: &'a mut TransactionBuilder,
 }
@@ -351,6 +1292,7 @@ pub fn get_prepared_transaction_iterator<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, LAMPORTS_PER_SIGNATURE};
     use solana_sdk::instruction::AccountMeta;
     use solana_sdk::signature::Keypair;
 
@@ -391,6 +1333,79 @@ mod tests {
         assert!(tx_builder.check_signers(&ix).is_ok());
     }
 
+    #[test]
+    fn test_post_assertions_follow_packs() {
+        let mut tx_builder = TransactionBuilder::unlimited(Arc::new(Keypair::new()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        let assertion = |account: Pubkey| AccountAssertion::NotExists { account };
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        tx_builder.add_instruction(ix(1)).unwrap();
+        tx_builder.add_post_assertion(assertion(first));
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instruction(ix(1)).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instruction(ix(2)).unwrap();
+        tx_builder.add_post_assertion(assertion(second));
+        tx_builder.finish_instruction_pack();
+
+        assert_eq!(tx_builder.dedup_instructions(|a, b| a == b), 1);
+        let transactions: Vec<PreparedTransaction> = tx_builder.sequence().collect();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].post_assertions, vec![assertion(first)]);
+        assert_eq!(transactions[1].post_assertions, vec![assertion(second)]);
+    }
+
+    #[test]
+    fn test_instruction_metadata() {
+        #[derive(Debug, PartialEq)]
+        struct Operation(u32);
+
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        tx_builder.set_memo(Some("memo".to_string()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        tx_builder.add_instruction(ix(1)).unwrap();
+        tx_builder
+            .add_instruction_with_metadata(ix(2), Operation(7))
+            .unwrap();
+
+        let transaction = tx_builder.build_one();
+        assert_eq!(transaction.instruction_metadata.len(), 3);
+        assert!(transaction.instruction_metadata[..2]
+            .iter()
+            .all(Option::is_none));
+        let metadata = transaction.metadata();
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].downcast_ref::<Operation>(), Some(&Operation(7)));
+        assert!(metadata[0].downcast_ref::<u32>().is_none());
+    }
+
+    #[test]
+    fn test_instruction_tags() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        let tags = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<String, String>>()
+        };
+        tx_builder
+            .add_instruction_with_tags(ix(1), tags(&[("operation", "stake"), ("pool", "a")]))
+            .unwrap();
+        tx_builder
+            .add_instruction_with_tags(ix(2), tags(&[("pool", "b")]))
+            .unwrap();
+
+        let transaction = tx_builder.build_one();
+        let aggregated = transaction.tags();
+        assert_eq!(aggregated["operation"], "stake");
+        assert_eq!(aggregated["pool"], "a,b");
+        assert_eq!(
+            transaction.single_description().as_deref(),
+            Some("tags: operation=stake, pool=a,b")
+        );
+    }
+
     #[test]
     fn is_sync_send_able() {
         fn do_stuff<T: Sync + Send>(_t: T) {}
@@ -403,4 +1418,577 @@ mod tests {
 
         do_stuff(tx_builder.signature_builder);
     }
+
+    #[test]
+    fn test_edit_current_pack() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        tx_builder.add_instruction(ix(1)).unwrap();
+        tx_builder.add_instruction(ix(3)).unwrap();
+        tx_builder.insert_instruction(1, ix(2)).unwrap();
+        assert!(tx_builder.insert_instruction(4, ix(4)).is_err());
+
+        let replaced = tx_builder.replace_instruction(0, ix(0)).unwrap();
+        assert_eq!(replaced.data, vec![1]);
+        assert!(tx_builder
+            .replace_instruction(0, ix(0))
+            .is_ok_and(|replaced| replaced.data == vec![0]));
+        assert!(tx_builder.replace_instruction(3, ix(0)).is_err());
+
+        assert_eq!(tx_builder.remove_last_instruction().unwrap().data, vec![3]);
+        let data: Vec<Vec<u8>> = tx_builder
+            .instructions()
+            .into_iter()
+            .map(|instruction| instruction.data)
+            .collect();
+        assert_eq!(data, vec![vec![0], vec![2]]);
+
+        let too_big =
+            Instruction::new_with_bytes(Pubkey::default(), &[0; PACKET_DATA_SIZE], vec![]);
+        assert!(tx_builder.insert_instruction(0, too_big.clone()).is_err());
+        assert!(tx_builder.replace_instruction(0, too_big).is_err());
+        assert_eq!(tx_builder.instructions().len(), 2);
+    }
+
+    #[test]
+    fn test_dedup_instructions() {
+        let mut tx_builder = TransactionBuilder::unlimited(Arc::new(Keypair::new()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        tx_builder.add_instructions([ix(1), ix(2)]).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instructions([ix(1)]).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instructions([ix(2), ix(3), ix(3)]).unwrap();
+
+        assert_eq!(tx_builder.dedup_instructions(|a, b| a == b), 3);
+        assert_eq!(tx_builder.instruction_packs.len(), 1);
+        let data: Vec<Vec<u8>> = tx_builder
+            .instructions()
+            .into_iter()
+            .map(|instruction| instruction.data)
+            .collect();
+        assert_eq!(data, vec![vec![1], vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn test_dedup_instructions_keeps_compute_budget_and_assertions() {
+        let mut tx_builder = TransactionBuilder::unlimited(Arc::new(Keypair::new()));
+        let ix = |data: u8| Instruction::new_with_bytes(Pubkey::default(), &[data], vec![]);
+        let price = ComputeBudgetSetting::ComputeUnitPrice(100).to_instruction();
+        let assertion = AccountAssertion::NotExists {
+            account: Pubkey::new_unique(),
+        };
+        tx_builder.add_instructions([price.clone(), ix(1)]).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder
+            .add_instructions([price.clone(), ix(1), ix(2)])
+            .unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instructions([price.clone(), ix(2)]).unwrap();
+        tx_builder.add_post_assertion(assertion.clone());
+
+        assert_eq!(tx_builder.dedup_instructions(|a, b| a == b), 2);
+        let transactions: Vec<PreparedTransaction> = tx_builder.sequence().collect();
+        assert_eq!(transactions.len(), 2);
+        // every pack keeps its compute unit price
+        assert_eq!(transactions[1].instructions(), vec![price, ix(2)]);
+        // the assertions of the emptied pack move to the pack holding the kept instruction
+        assert!(transactions[0].post_assertions.is_empty());
+        assert_eq!(transactions[1].post_assertions, vec![assertion]);
+    }
+
+    #[test]
+    fn test_plan() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let writable = Pubkey::new_unique();
+        let ix = |data: u8| {
+            Instruction::new_with_bytes(
+                Pubkey::default(),
+                &[data],
+                vec![AccountMeta::new(writable, false)],
+            )
+        };
+        tx_builder
+            .add_instruction_with_description(ix(1), "first".to_string())
+            .unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instruction(ix(2)).unwrap();
+
+        let plan = tx_builder.plan(false);
+        assert_eq!(plan.instruction_packs, 2);
+        assert_eq!(plan.transactions.len(), 2);
+        assert_eq!(tx_builder.instructions().len(), 2);
+        let first = &plan.transactions[0];
+        assert_eq!(first.instructions[0].description.as_deref(), Some("first"));
+        assert_eq!(first.programs, vec![Pubkey::default()]);
+        assert_eq!(
+            first.writable_accounts,
+            vec![tx_builder.fee_payer, writable]
+        );
+        assert_eq!(
+            first.estimated_compute_units,
+            DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(first.estimated_fee_lamports, LAMPORTS_PER_SIGNATURE);
+
+        assert_eq!(tx_builder.plan(true).transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let ix = || Instruction::new_with_bytes(Pubkey::default(), &[1], vec![]);
+        tx_builder.add_instruction(ix()).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instruction(ix()).unwrap();
+        let snapshot = tx_builder.snapshot();
+
+        let signer = tx_builder.generate_signer();
+        tx_builder
+            .set_drop_unused_signers(true)
+            .set_dedup_packs(true);
+        tx_builder.add_instruction(ix()).unwrap();
+        tx_builder.finish_instruction_pack();
+        tx_builder.add_instruction(ix()).unwrap();
+        assert_eq!(tx_builder.packs_len(), 3);
+
+        tx_builder.restore(snapshot);
+        assert!(!tx_builder.drop_unused_signers);
+        assert!(!tx_builder.dedup_packs);
+        assert_eq!(tx_builder.packs_len(), 2);
+        assert_eq!(tx_builder.instructions_len(), 2);
+        assert!(tx_builder.get_signer(&signer).is_none());
+        tx_builder.add_instruction(ix()).unwrap();
+        assert_eq!(tx_builder.build_next().unwrap().instructions().len(), 1);
+        assert_eq!(tx_builder.build_next().unwrap().instructions().len(), 2);
+    }
+
+    #[test]
+    fn test_size_breakdown() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let signer = tx_builder.generate_signer();
+        tx_builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[0; 100],
+                vec![AccountMeta::new(signer, true)],
+            ))
+            .unwrap();
+        tx_builder
+            .add_instruction_with_description(
+                Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]),
+                "small".to_string(),
+            )
+            .unwrap();
+
+        let breakdown = tx_builder.current_pack_size_breakdown();
+        let transaction = tx_builder.build_one();
+        assert_eq!(
+            breakdown.total,
+            bincode::serialize(&transaction.transaction).unwrap().len()
+        );
+        assert_eq!(
+            breakdown.overhead
+                + breakdown
+                    .instructions
+                    .iter()
+                    .map(|size| size.total)
+                    .sum::<usize>(),
+            breakdown.total
+        );
+        let largest = breakdown.largest_instruction().unwrap();
+        assert_eq!(largest.index, 0);
+        // program id and signer keys, the signature of the signer, data
+        assert_eq!(largest.total, 1 + 2 + 101 + 2 * 32 + 64);
+        assert_eq!(
+            breakdown.instructions[1].description.as_deref(),
+            Some("small")
+        );
+    }
+
+    #[test]
+    fn test_drop_unused_signers() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        tx_builder.set_drop_unused_signers(true);
+        let signers: Vec<Pubkey> = (0..2).map(|_| tx_builder.generate_signer()).collect();
+        for signer in &signers {
+            tx_builder
+                .add_instruction(Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    vec![AccountMeta::new(*signer, true)],
+                ))
+                .unwrap();
+            tx_builder.finish_instruction_pack();
+        }
+
+        let first = tx_builder.build_next().unwrap();
+        assert_eq!(first.signers.len(), 2);
+        assert!(tx_builder.get_signer(&signers[0]).is_none());
+        assert!(tx_builder.get_signer(&signers[1]).is_some());
+        tx_builder.build_next().unwrap();
+        assert!(tx_builder.get_signer(&signers[1]).is_none());
+        assert!(tx_builder.get_signer(&tx_builder.fee_payer()).is_some());
+    }
+
+    #[test]
+    fn test_prune_signers() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let built = tx_builder.generate_signer();
+        let pending = tx_builder.generate_signer();
+        let unused = tx_builder.generate_signer();
+        for signer in [built, pending] {
+            tx_builder
+                .add_instruction(Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    vec![AccountMeta::new(signer, true)],
+                ))
+                .unwrap();
+            tx_builder.finish_instruction_pack();
+        }
+        tx_builder.build_next().unwrap();
+
+        tx_builder.prune_signers();
+        assert!(tx_builder.get_signer(&built).is_none());
+        assert!(tx_builder.get_signer(&unused).is_none());
+        assert!(tx_builder.get_signer(&pending).is_some());
+        assert!(tx_builder.get_signer(&tx_builder.fee_payer()).is_some());
+    }
+
+    #[test]
+    fn test_signer_management() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        let signer = tx_builder.generate_signer();
+        assert_eq!(tx_builder.signature_builder().len(), 2);
+        assert!(matches!(
+            tx_builder.remove_signer(&fee_payer.pubkey()),
+            Err(TransactionBuildError::FeePayerRemoval(_))
+        ));
+        assert!(tx_builder.remove_signer(&signer).unwrap());
+        assert!(!tx_builder.remove_signer(&signer).unwrap());
+
+        let mut other = SignatureBuilder::default();
+        let merged = other.new_signer();
+        other.add_signer(Arc::new(Keypair::new()));
+        tx_builder.merge_signers(other);
+        assert_eq!(tx_builder.signature_builder().len(), 3);
+        assert!(tx_builder.signature_builder().contains_key(&merged));
+
+        tx_builder.clear_signers();
+        assert_eq!(
+            tx_builder.signature_builder().keys().collect::<Vec<_>>(),
+            vec![&fee_payer.pubkey()]
+        );
+    }
+
+    #[test]
+    fn test_placeholder_signer() {
+        let mut tx_builder = TransactionBuilder::limited(Arc::new(Keypair::new()));
+        let cold_key = tx_builder.add_placeholder_signer(Pubkey::new_unique());
+        tx_builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![AccountMeta::new(cold_key, true)],
+            ))
+            .unwrap();
+
+        let transaction = tx_builder.build_one();
+        assert!(transaction.requires_external_signatures());
+        assert_eq!(transaction.external_signers, vec![cold_key]);
+        assert_eq!(transaction.signers.len(), 1);
+        assert_eq!(transaction.missing_signers(), vec![cold_key]);
+    }
+
+    #[test]
+    fn test_dedup_packs() {
+        let mut tx_builder = TransactionBuilder::unlimited(Arc::new(Keypair::new()));
+        tx_builder.set_dedup_packs(true);
+        let program_id = Pubkey::new_unique();
+        for data in [[1], [2], [1]] {
+            tx_builder
+                .add_instruction(Instruction::new_with_bytes(program_id, &data, vec![]))
+                .unwrap();
+            tx_builder.finish_instruction_pack();
+        }
+        assert_eq!(tx_builder.packs_len(), 2);
+
+        // the assertions of the dropped duplicate move to the queued pack
+        let account = Pubkey::new_unique();
+        tx_builder
+            .add_instruction(Instruction::new_with_bytes(program_id, &[2], vec![]))
+            .unwrap();
+        tx_builder.add_post_assertion(AccountAssertion::NotExists { account });
+        tx_builder.finish_instruction_pack();
+        assert_eq!(tx_builder.packs_len(), 2);
+        tx_builder.build_next().unwrap();
+        let second = tx_builder.build_next().unwrap();
+        assert_eq!(
+            second.post_assertions,
+            vec![AccountAssertion::NotExists { account }]
+        );
+
+        // the built packs are not queued anymore
+        tx_builder
+            .add_instruction(Instruction::new_with_bytes(program_id, &[1], vec![]))
+            .unwrap();
+        tx_builder.finish_instruction_pack();
+        assert_eq!(tx_builder.packs_len(), 1);
+    }
+
+    #[test]
+    fn test_from_instructions() {
+        let fee_payer = Arc::new(Keypair::new());
+        let instructions: Vec<Instruction> = (0..40)
+            .map(|_| {
+                solana_sdk::system_instruction::transfer(
+                    &fee_payer.pubkey(),
+                    &Pubkey::new_unique(),
+                    1,
+                )
+            })
+            .collect();
+        let tx_builder = TransactionBuilder::from_instructions(
+            fee_payer.clone(),
+            instructions.clone(),
+            PACKET_DATA_SIZE,
+        )
+        .unwrap();
+        assert!(tx_builder.packs_len() > 1);
+        assert_eq!(tx_builder.instructions(), instructions);
+
+        let mut tx_builder = tx_builder;
+        assert!(tx_builder
+            .add_instructions_split([solana_sdk::system_instruction::transfer(
+                &Pubkey::new_unique(),
+                &fee_payer.pubkey(),
+                1,
+            )])
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_transaction() {
+        let fee_payer = Arc::new(Keypair::new());
+        let instructions = vec![
+            solana_sdk::system_instruction::transfer(&fee_payer.pubkey(), &Pubkey::new_unique(), 1),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+            ),
+        ];
+        let transaction = Transaction::new_with_payer(&instructions, Some(&fee_payer.pubkey()));
+
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder.add_transaction(&transaction).unwrap();
+        assert_eq!(tx_builder.packs_len(), 1);
+        assert_eq!(tx_builder.instructions(), instructions);
+
+        let foreign = Transaction::new_with_payer(
+            &[solana_sdk::system_instruction::transfer(
+                &Pubkey::new_unique(),
+                &fee_payer.pubkey(),
+                1,
+            )],
+            None,
+        );
+        assert!(tx_builder.add_message(&foreign.message).is_err());
+        assert_eq!(tx_builder.packs_len(), 1);
+    }
+
+    #[test]
+    fn test_add_versioned_message() {
+        let fee_payer = Arc::new(Keypair::new());
+        let looked_up = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[7],
+            vec![
+                AccountMeta::new(fee_payer.pubkey(), true),
+                AccountMeta::new(looked_up[0], false),
+                AccountMeta::new_readonly(looked_up[1], false),
+            ],
+        );
+        let lookup_table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: looked_up.to_vec(),
+        };
+        let message = VersionedMessage::V0(
+            solana_sdk::message::v0::Message::try_compile(
+                &fee_payer.pubkey(),
+                &[instruction.clone()],
+                &[lookup_table.clone()],
+                Hash::default(),
+            )
+            .unwrap(),
+        );
+
+        let mut tx_builder = TransactionBuilder::limited(fee_payer);
+        assert!(tx_builder.add_versioned_message(&message, &[]).is_err());
+        tx_builder
+            .add_versioned_message(&message, &[lookup_table])
+            .unwrap();
+        assert_eq!(tx_builder.instructions(), vec![instruction]);
+    }
+
+    #[test]
+    fn test_with_fee_payer() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder
+            .add_instruction(Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            ))
+            .unwrap();
+        let prepared_transaction = tx_builder.build_one();
+
+        let rotated_fee_payer = Arc::new(Keypair::new());
+        let rotated = prepared_transaction.with_fee_payer(rotated_fee_payer.clone());
+        assert_eq!(rotated.required_signers(), vec![rotated_fee_payer.pubkey()]);
+        assert_eq!(rotated.signers.len(), 1);
+        assert_eq!(rotated.instructions(), prepared_transaction.instructions());
+    }
+
+    #[test]
+    fn test_with_fee_payer_referenced_by_instruction() {
+        let fee_payer = Arc::new(Keypair::new());
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1],
+            vec![AccountMeta::new_readonly(fee_payer.pubkey(), false)],
+        );
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder.add_instruction(instruction.clone()).unwrap();
+        let prepared_transaction = tx_builder.build_one();
+
+        // the previous fee payer is neither a signer nor write-locked anymore
+        let rotated_fee_payer = Arc::new(Keypair::new());
+        let rotated = prepared_transaction.with_fee_payer(rotated_fee_payer.clone());
+        assert_eq!(rotated.required_signers(), vec![rotated_fee_payer.pubkey()]);
+        assert_eq!(
+            rotated.writable_accounts(),
+            vec![rotated_fee_payer.pubkey()]
+        );
+        assert_eq!(rotated.instructions(), vec![instruction]);
+
+        // the previous fee payer referenced as a signer still signs, read-only
+        let signing_instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[2],
+            vec![AccountMeta::new_readonly(fee_payer.pubkey(), true)],
+        );
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder
+            .add_instruction(signing_instruction.clone())
+            .unwrap();
+        let rotated = tx_builder
+            .build_one()
+            .with_fee_payer(rotated_fee_payer.clone());
+        assert_eq!(
+            rotated.required_signers(),
+            vec![rotated_fee_payer.pubkey(), fee_payer.pubkey()]
+        );
+        assert_eq!(
+            rotated.writable_accounts(),
+            vec![rotated_fee_payer.pubkey()]
+        );
+        assert_eq!(rotated.signers.len(), 2);
+    }
+
+    #[test]
+    fn test_instructions() {
+        let fee_payer = Arc::new(Keypair::new());
+        let signer = Arc::new(Keypair::new());
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(fee_payer.pubkey(), true),
+                AccountMeta::new_readonly(signer.pubkey(), true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+        );
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder.add_signer_checked(&signer);
+        tx_builder.add_instruction(instruction.clone()).unwrap();
+        let prepared_transaction = tx_builder.build_one();
+
+        // the signer and writable flags are decompiled as they were compiled
+        assert_eq!(prepared_transaction.instructions(), vec![instruction]);
+    }
+
+    #[test]
+    fn test_with_memo() {
+        let fee_payer = Arc::new(Keypair::new());
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder.add_instruction(instruction.clone()).unwrap();
+        let prepared_transaction = tx_builder.build_one();
+
+        let with_memo = prepared_transaction.with_memo("tx-uuid").unwrap();
+        assert_eq!(
+            with_memo.instructions(),
+            vec![memo_instruction("tx-uuid"), instruction]
+        );
+        assert_eq!(with_memo.instruction_descriptions.len(), 2);
+
+        let err = prepared_transaction
+            .with_memo(&"x".repeat(PACKET_DATA_SIZE))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionBuildError>(),
+            Some(TransactionBuildError::TooBigTransaction)
+        ));
+    }
+
+    #[test]
+    fn test_prepared_transaction_display() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder
+            .add_instruction_with_description(
+                solana_sdk::system_instruction::transfer(
+                    &fee_payer.pubkey(),
+                    &Pubkey::new_unique(),
+                    1,
+                ),
+                "Pay".to_string(),
+            )
+            .unwrap();
+
+        let display = tx_builder.build_one().to_string();
+        assert!(display.contains(&format!(
+            "#0 System ({}): Pay",
+            solana_sdk::system_program::id()
+        )));
+        assert!(display.contains(&format!("- {} [signer, writable]", fee_payer.pubkey())));
+    }
+
+    #[test]
+    fn test_redacted_debug() {
+        let fee_payer = Arc::new(Keypair::new());
+        let mut tx_builder = TransactionBuilder::limited(fee_payer.clone());
+        tx_builder
+            .add_instruction_with_description(
+                Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]),
+                "Update".to_string(),
+            )
+            .unwrap();
+
+        let debug = format!("{tx_builder:#?}");
+        assert!(debug.contains(&fee_payer.pubkey().to_string()));
+        assert!(debug.contains("Update"));
+        assert!(!debug.contains(&fee_payer.to_base58_string()));
+
+        let debug = format!("{:#?}", tx_builder.build_one());
+        assert!(debug.contains(&fee_payer.pubkey().to_string()));
+        assert!(!debug.contains(&fee_payer.to_base58_string()));
+        assert!(!debug.contains(&format!("{:?}", fee_payer.to_bytes())));
+    }
 }
@@ -1,17 +1,24 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
+use std::io::Write;
 
 // Set of struct wrappers that can be used to deserialize instruction.
 // For marinade client it's the base64 format which is used in multisig like SPL Governance.
 
+// With the `serde` feature the pubkeys are base58 strings and the data is base64 (e.g. for JSON configs).
+
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransactionInstruction {
     // Target program to execute against.
+    #[cfg_attr(feature = "serde", serde(with = "pubkey_string"))]
     pub program_id: Pubkey,
     // Accounts required for the transaction.
     pub accounts: Vec<TransactionAccount>,
     // Instruction data for the transaction.
+    #[cfg_attr(feature = "serde", serde(with = "base64_string"))]
     pub data: Vec<u8>,
 }
 
@@ -26,7 +33,9 @@ impl From<&TransactionInstruction> for Instruction {
 }
 
 #[derive(Debug, BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransactionAccount {
+    #[cfg_attr(feature = "serde", serde(with = "pubkey_string"))]
     pub pubkey: Pubkey,
     pub is_signer: bool,
     pub is_writable: bool,
@@ -51,9 +60,9 @@ impl From<&AccountMeta> for TransactionAccount {
     }
 }
 
-pub fn print_base64(instructions: &Vec<Instruction>) -> anyhow::Result<()> {
-    for instruction in instructions {
-        let transaction_instruction = TransactionInstruction {
+impl From<&Instruction> for TransactionInstruction {
+    fn from(instruction: &Instruction) -> TransactionInstruction {
+        TransactionInstruction {
             program_id: instruction.program_id,
             accounts: instruction
                 .accounts
@@ -61,12 +70,348 @@ pub fn print_base64(instructions: &Vec<Instruction>) -> anyhow::Result<()> {
                 .map(TransactionAccount::from)
                 .collect(),
             data: instruction.data.clone(),
-        };
-        println!(
-            "program: {}\n  {}",
-            instruction.program_id,
-            base64::encode(transaction_instruction.try_to_vec()?)
-        );
+        }
+    }
+}
+
+/// Text encoding of the borsh serialized instructions (and of the instruction data of a plan)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Encoding {
+    /// Used by SPL Governance
+    #[default]
+    Base64,
+    /// Expected by some multisig UIs and explorers
+    Base58,
+    /// Decoded with or without the `0x` prefix
+    Hex,
+}
+
+impl Encoding {
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Base64 => base64::encode(bytes),
+            Self::Base58 => bs58::encode(bytes).into_string(),
+            Self::Hex => hex::encode(bytes),
+        }
+    }
+
+    pub fn decode(self, encoded: &str) -> anyhow::Result<Vec<u8>> {
+        let encoded = encoded.trim();
+        Ok(match self {
+            Self::Base64 => base64::decode(encoded)?,
+            Self::Base58 => bs58::decode(encoded).into_vec()?,
+            Self::Hex => hex::decode(encoded.trim_start_matches("0x"))?,
+        })
+    }
+}
+
+/// Borsh serialized instructions in the encoding.
+pub fn encode_instructions(instructions: &[Instruction], encoding: Encoding) -> Vec<String> {
+    instructions
+        .iter()
+        .map(|instruction| {
+            encoding.encode(
+                &TransactionInstruction::from(instruction)
+                    .try_to_vec()
+                    .expect("Serialization into memory cannot fail"),
+            )
+        })
+        .collect()
+}
+
+pub fn decode_instruction(encoded: &str, encoding: Encoding) -> anyhow::Result<Instruction> {
+    let transaction_instruction =
+        TransactionInstruction::try_from_slice(&encoding.decode(encoded)?)?;
+    Ok(Instruction::from(&transaction_instruction))
+}
+
+/// Borsh serialized instructions encoded in base64 (e.g. for SPL Governance proposals).
+pub fn encode_base64(instructions: &[Instruction]) -> Vec<String> {
+    encode_instructions(instructions, Encoding::Base64)
+}
+
+/// Writes the encoded instructions, each with its program.
+pub fn write_encoded<W: Write>(
+    instructions: &[Instruction],
+    encoding: Encoding,
+    writer: &mut W,
+) -> anyhow::Result<()> {
+    for (instruction, encoded) in instructions
+        .iter()
+        .zip(encode_instructions(instructions, encoding))
+    {
+        writeln!(writer, "program: {}\n  {}", instruction.program_id, encoded)?;
     }
     Ok(())
 }
+
+pub fn write_base64<W: Write>(instructions: &[Instruction], writer: &mut W) -> anyhow::Result<()> {
+    write_encoded(instructions, Encoding::Base64, writer)
+}
+
+/// Placeholder of the fee payer in the instructions of an `InstructionMessage`
+pub const FEE_PAYER_PLACEHOLDER: Pubkey = pubkey!("FeePayerP1aceho1der1111111111111111111111111");
+
+/// Ordered list of instructions exchanged as a single artifact (e.g. a whole proposal).
+/// The fee payer may be the placeholder, it's replaced by the actual one when decoded.
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub struct InstructionMessage {
+    pub fee_payer: Pubkey,
+    pub instructions: Vec<TransactionInstruction>,
+}
+
+impl InstructionMessage {
+    pub fn new(fee_payer: Pubkey, instructions: &[Instruction]) -> Self {
+        Self {
+            fee_payer,
+            instructions: instructions
+                .iter()
+                .map(TransactionInstruction::from)
+                .collect(),
+        }
+    }
+
+    /// Instructions with the fee payer of the message replaced by the fee payer.
+    pub fn instructions_for_fee_payer(&self, fee_payer: &Pubkey) -> Vec<Instruction> {
+        self.instructions
+            .iter()
+            .map(|transaction_instruction| {
+                let mut instruction = Instruction::from(transaction_instruction);
+                for account in instruction.accounts.iter_mut() {
+                    if account.pubkey == self.fee_payer {
+                        account.pubkey = *fee_payer;
+                    }
+                }
+                instruction
+            })
+            .collect()
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> String {
+        encoding.encode(
+            &self
+                .try_to_vec()
+                .expect("Serialization into memory cannot fail"),
+        )
+    }
+
+    pub fn decode(encoded: &str, encoding: Encoding) -> anyhow::Result<Self> {
+        Ok(Self::try_from_slice(&encoding.decode(encoded)?)?)
+    }
+
+    pub fn encode_base64(&self) -> String {
+        self.encode(Encoding::Base64)
+    }
+
+    pub fn decode_base64(encoded: &str) -> anyhow::Result<Self> {
+        Self::decode(encoded, Encoding::Base64)
+    }
+}
+
+pub fn print_base64(instructions: &Vec<Instruction>) -> anyhow::Result<()> {
+    write_base64(instructions, &mut std::io::stdout())
+}
+
+#[cfg(feature = "serde")]
+mod pubkey_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Pubkey::from_str(&encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod base64_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction() -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_encode_decode_instructions() {
+        let instructions = vec![instruction(), instruction()];
+        for encoding in [Encoding::Base64, Encoding::Base58, Encoding::Hex] {
+            let encoded = encode_instructions(&instructions, encoding);
+            assert_eq!(encoded.len(), 2);
+            let decoded: Vec<Instruction> = encoded
+                .iter()
+                .map(|encoded| decode_instruction(encoded, encoding).unwrap())
+                .collect();
+            assert_eq!(decoded, instructions);
+        }
+        assert_eq!(
+            encode_base64(&instructions),
+            encode_instructions(&instructions, Encoding::Base64)
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_instruction() {
+        let encoded = encode_instructions(&[instruction()], Encoding::Base58);
+        // not in the encoding
+        assert!(decode_instruction(&encoded[0], Encoding::Hex).is_err());
+        assert!(decode_instruction("0OIl", Encoding::Base58).is_err());
+        assert!(decode_instruction("!", Encoding::Base64).is_err());
+        // not a borsh serialized instruction
+        assert!(
+            decode_instruction(&Encoding::Base64.encode(&[1, 2, 3]), Encoding::Base64).is_err()
+        );
+        let bytes = Encoding::Base58.decode(&encoded[0]).unwrap();
+        assert!(decode_instruction(
+            &Encoding::Base58.encode(&bytes[..bytes.len() - 1]),
+            Encoding::Base58
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_fee_payer_placeholder_substitution() {
+        use crate::transaction_builder::TransactionBuilder;
+        use solana_sdk::signature::{Keypair, Signer};
+        use std::sync::Arc;
+
+        let other = Pubkey::new_unique();
+        let message = InstructionMessage::new(
+            FEE_PAYER_PLACEHOLDER,
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![
+                    AccountMeta::new(FEE_PAYER_PLACEHOLDER, true),
+                    AccountMeta::new(other, false),
+                ],
+            )],
+        );
+        let decoded =
+            InstructionMessage::decode(&message.encode_base64(), Encoding::Base64).unwrap();
+
+        let fee_payer = Arc::new(Keypair::new());
+        let mut builder = TransactionBuilder::limited(fee_payer.clone());
+        for instruction in decoded.instructions_for_fee_payer(&fee_payer.pubkey()) {
+            builder.add_instruction(instruction).unwrap();
+        }
+        let prepared_transaction = builder.build_one();
+        let account_keys = &prepared_transaction.transaction.message.account_keys;
+        assert_eq!(account_keys[0], fee_payer.pubkey());
+        assert!(!account_keys.contains(&FEE_PAYER_PLACEHOLDER));
+        assert_eq!(
+            prepared_transaction.instructions()[0].accounts,
+            vec![
+                AccountMeta::new(fee_payer.pubkey(), true),
+                AccountMeta::new(other, false),
+            ]
+        );
+        assert_eq!(
+            prepared_transaction.required_signers(),
+            vec![fee_payer.pubkey()]
+        );
+    }
+
+    #[test]
+    fn test_instruction_message_encode_decode() {
+        let fee_payer = Pubkey::new_unique();
+        let instructions = vec![instruction(), instruction()];
+        let message = InstructionMessage::new(fee_payer, &instructions);
+        for encoding in [Encoding::Base64, Encoding::Base58, Encoding::Hex] {
+            let decoded = InstructionMessage::decode(&message.encode(encoding), encoding).unwrap();
+            assert_eq!(decoded.fee_payer, fee_payer);
+            assert_eq!(decoded.instructions_for_fee_payer(&fee_payer), instructions);
+        }
+        let decoded = InstructionMessage::decode_base64(&message.encode_base64()).unwrap();
+        assert_eq!(decoded.instructions_for_fee_payer(&fee_payer), instructions);
+    }
+
+    #[test]
+    fn test_instruction_message_decode_malformed() {
+        let message = InstructionMessage::new(Pubkey::new_unique(), &[instruction()]);
+        let bytes = Encoding::Base64.decode(&message.encode_base64()).unwrap();
+        // truncated
+        assert!(InstructionMessage::decode(
+            &Encoding::Base64.encode(&bytes[..bytes.len() - 1]),
+            Encoding::Base64
+        )
+        .is_err());
+        // trailing bytes
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(InstructionMessage::decode_base64(&Encoding::Base64.encode(&extended)).is_err());
+        // a single instruction is not a message
+        let encoded = encode_instructions(&[instruction()], Encoding::Base64);
+        assert!(InstructionMessage::decode_base64(&encoded[0]).is_err());
+        assert!(InstructionMessage::decode_base64("not base64!").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_format() {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let transaction_instruction = TransactionInstruction::from(&Instruction::new_with_bytes(
+            program_id,
+            &[1, 2, 3],
+            vec![AccountMeta::new_readonly(account, true)],
+        ));
+        let json = serde_json::json!({
+            "program_id": program_id.to_string(),
+            "accounts": [{"pubkey": account.to_string(), "is_signer": true, "is_writable": false}],
+            "data": "AQID",
+        });
+        assert_eq!(
+            serde_json::to_value(&transaction_instruction).unwrap(),
+            json
+        );
+
+        let deserialized: TransactionInstruction = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.program_id, program_id);
+        assert_eq!(deserialized.accounts[0].pubkey, account);
+        assert_eq!(deserialized.data, vec![1, 2, 3]);
+
+        assert!(
+            serde_json::from_value::<TransactionInstruction>(serde_json::json!({
+                "program_id": "not a pubkey", "accounts": [], "data": "AQID",
+            }))
+            .is_err()
+        );
+        assert!(
+            serde_json::from_value::<TransactionInstruction>(serde_json::json!({
+                "program_id": program_id.to_string(), "accounts": [], "data": "!",
+            }))
+            .is_err()
+        );
+    }
+}